@@ -0,0 +1,56 @@
+#![feature(test)]
+
+extern crate test;
+extern crate tetr;
+
+use std::f64;
+use test::Bencher;
+
+use tetr::controller::ai::bruteforce::{self,Settings};
+use tetr::data::grid;
+use tetr::data::shapes::tetromino::{RotatedShape,Shape};
+use tetr::game::data::World as WorldTrait;
+use tetr::game::data::world::dynamic::World;
+
+const QUEUE: [Shape; 4] = [Shape::S,Shape::I,Shape::L,Shape::Z];
+
+///A partially filled 10x20 world
+fn world() -> World<bool>{
+	const ROWS: [&'static str; 6] = [
+		".........#",
+		"##......##",
+		"###..#.###",
+		"####.#####",
+		"#.########",
+		"####.#####",
+	];
+
+	let mut world = World::new(10,20);
+	for (y,row) in ROWS.iter().enumerate(){
+		for (x,c) in row.chars().enumerate(){
+			if c == '#'{
+				world.set_position(grid::Pos{x: x as grid::PosAxis,y: (20 - ROWS.len() + y) as grid::PosAxis},true).unwrap();
+			}
+		}
+	}
+	world
+}
+
+fn bench_search(b: &mut Bencher,depth: usize,beam_width: usize){
+	let world = world();
+	let settings = Settings{
+		depth      : depth,
+		beam_width : beam_width,
+		search_time: f64::INFINITY,
+		..Settings::default()
+	};
+
+	b.iter(|| bruteforce::search(&world,RotatedShape::new(Shape::T),grid::Pos{x: 4,y: 0},QUEUE.iter().cloned(),&settings));
+}
+
+///The evaluation of only the current shape
+#[bench]fn one_ply(b: &mut Bencher){bench_search(b,1,1)}
+
+#[bench]fn depth2_beam8(b: &mut Bencher){bench_search(b,2,8)}
+#[bench]fn depth3_beam8(b: &mut Bencher){bench_search(b,3,8)}
+#[bench]fn depth3_beam32(b: &mut Bencher){bench_search(b,3,32)}
@@ -0,0 +1,98 @@
+#![feature(test)]
+
+extern crate test;
+extern crate tetr;
+
+use test::Bencher;
+
+use tetr::controller::ai::evaluation::{self,Weights};
+use tetr::data::grid::{self,Grid,RectangularBound};
+use tetr::data::shapes::tetromino::{RotatedShape,Shape};
+use tetr::game::data::World as WorldTrait;
+use tetr::game::data::world::{bitboard,dynamic,CellIntersection};
+
+const SHAPES: [Shape; 7] = [Shape::I,Shape::L,Shape::O,Shape::J,Shape::T,Shape::S,Shape::Z];
+
+///Fills a 10x20 world partially, the lowest four rows being full except for the rightmost column
+fn fill<W>(mut world: W) -> W
+	where W: WorldTrait + Grid<Cell = bool>
+{
+	const ROWS: [&'static str; 8] = [
+		".........#",
+		"##......##",
+		"###..#.###",
+		"####.#####",
+		"#########.",
+		"#########.",
+		"#########.",
+		"#########.",
+	];
+
+	for (y,row) in ROWS.iter().enumerate(){
+		for (x,c) in row.chars().enumerate(){
+			if c == '#'{
+				world.set_position(grid::Pos{x: x as grid::PosAxis,y: (20 - ROWS.len() + y) as grid::PosAxis},true).unwrap();
+			}
+		}
+	}
+	world
+}
+
+fn dynamic_world() -> dynamic::World<bool>{fill(dynamic::World::new(10,20))}
+fn bitboard_world() -> bitboard::World{fill(bitboard::World::new(10,20))}
+
+///Checks every shape in every rotation at every position in and around the world
+fn bench_shape_intersects<W>(b: &mut Bencher,world: W)
+	where W: WorldTrait
+{
+	b.iter(||{
+		let mut intersections = 0;
+		for &shape in SHAPES.iter(){
+			for rotation in 0..shape.rotation_count(){
+				let shape = RotatedShape::new(shape).with_rotation(rotation);
+				for y in -2 .. world.height() as grid::PosAxis{
+					for x in -2 .. world.width() as grid::PosAxis{
+						match world.shape_intersects(&shape,grid::Pos{x: x,y: y}){
+							CellIntersection::None => (),
+							_ => intersections+= 1
+						}
+					}
+				}
+			}
+		}
+		intersections
+	});
+}
+
+///Drops an I shape in the rightmost column, completing four rows
+fn bench_imprint<W>(b: &mut Bencher,world: W)
+	where W: WorldTrait + Grid<Cell = bool> + Clone
+{
+	fn occupied(_: &RotatedShape) -> bool{true}
+	let shape = RotatedShape::new(Shape::I).with_rotation(1);
+	let pos = grid::Pos{x: 9 - shape.real_bound_x().unwrap().0 as grid::PosAxis,y: 16};
+
+	b.iter(||{
+		let mut world = world.clone();
+		world.imprint_shape(&shape,pos,&(occupied as fn(&_) -> _));
+		world.handle_full_rows(16 .. 20)
+	});
+}
+
+#[bench]fn dynamic_shape_intersects(b: &mut Bencher){bench_shape_intersects(b,dynamic_world())}
+#[bench]fn bitboard_shape_intersects(b: &mut Bencher){bench_shape_intersects(b,bitboard_world())}
+
+#[bench]fn dynamic_imprint(b: &mut Bencher){bench_imprint(b,dynamic_world())}
+#[bench]fn bitboard_imprint(b: &mut Bencher){bench_imprint(b,bitboard_world())}
+
+#[bench]fn dynamic_evaluate(b: &mut Bencher){
+	let world = dynamic_world();
+	let weights = Weights::default();
+	b.iter(|| evaluation::evaluate(&world,&weights));
+}
+
+#[bench]fn bitboard_evaluate(b: &mut Bencher){
+	let world = bitboard_world();
+	let weights = Weights::default();
+	b.iter(|| evaluation::evaluate_bitboard(&world,&weights));
+}
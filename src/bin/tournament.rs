@@ -0,0 +1,391 @@
+//!Tournament between AI controllers
+//!
+//!Plays headless versus matches between every pair of contestants and reports how they did.
+//!In a versus match, the two players have their own worlds and every completed row except a single one sends garbage to the opponent (2 rows: 1, 3 rows: 2, 4 rows: 4).
+//!A player loses when its world is lost, and the match is a draw when both lose at the same time or the time limit is reached.
+//!
+//!The contestants are read from a JSON file, for example:
+//!
+//!    [
+//!        {"name": "hard"  ,"difficulty": "Hard"},
+//!        {"name": "tuned" ,"difficulty": "Hard","weights": "weights.json"},
+//!        {"name": "deep"  ,"depth": 3,"beam_width": 16},
+//!        {"name": "pc"    ,"perfect_clear": true},
+//!        {"name": "bot"   ,"bot": "./my_bot"}
+//!    ]
+//!
+//!`difficulty` selects the preset of `bruteforce::Settings` (default: Hard), `depth` and `beam_width` override the preset, `perfect_clear` enables perfect clears, `weights` is a file with evaluation weights and `bot` is a shell command starting an external bot (See `controller::external`) that replaces the built-in AI.
+//!Matches without bots are simulated as fast as possible, while matches with bots are played in real time because the bots think in real time.
+//!The progress is reported on the standard error so that the results can be written to the standard output.
+
+#![feature(plugin)]
+#![plugin(docopt_macros)]
+#![allow(non_camel_case_types)]
+
+extern crate docopt;
+extern crate rand;
+extern crate rustc_serialize;
+extern crate tetr;
+
+use rand::Rng;
+use rustc_serialize::json;
+use std::{cmp,fs,io,process,sync,thread};
+use std::io::Write;
+use std::time::Duration;
+
+use tetr::controller::{ai,external};
+use tetr::controller::ai::bruteforce;
+use tetr::controller::registry::{self,Control,Registry};
+use tetr::data::grid;
+use tetr::game::{self,Event,Simulation};
+use tetr::game::data::{player,PlayerId,WorldId};
+use tetr::game::data::world::dynamic::World;
+use tetr::game::seed::Seed;
+
+docopt!(Args derive Debug,"
+Usage: tetr-tournament [options] <contestants>
+       tetr-tournament --help
+
+Plays versus matches between every pair of contestants listed in the given JSON file, reporting their win rates, survival times and lines sent.
+
+Options:
+  -h, --help         Show this message
+  --matches=N        Number of matches between every pair of contestants [default: 10]
+  --time=SECONDS     Time limit of a match in game time, after which it is a draw [default: 300]
+  --threads=N        Number of matches played in parallel [default: 4]
+  --seed=N           Seed for the random number generators. A random seed is used when not specified
+  --format=FORMAT    Available formats: json, csv [default: json]
+  --output=FILE      File the results are written to. Written to the standard output when not specified
+",
+	arg_contestants: String,
+	flag_matches   : usize,
+	flag_time      : f64,
+	flag_threads   : usize,
+	flag_seed      : Option<Seed>,
+	flag_format    : Format,
+	flag_output    : Option<String>,
+);
+
+#[derive(Debug,RustcDecodable)]
+enum Format{json,csv}
+
+///A controller taking part in the tournament
+#[derive(Clone,Debug,RustcDecodable)]
+struct Contestant{
+	///Unique among the contestants
+	name: String,
+	difficulty: Option<bruteforce::Difficulty>,
+	depth: Option<usize>,
+	beam_width: Option<usize>,
+	weights: Option<String>,
+	perfect_clear: Option<bool>,
+	bot: Option<String>,
+}
+
+impl Contestant{
+	///Settings of the built-in AI
+	fn settings(&self) -> Result<bruteforce::Settings,String>{
+		let mut settings = bruteforce::Settings::preset(self.difficulty.unwrap_or(bruteforce::Difficulty::Hard));
+		if let Some(depth) = self.depth{
+			settings.depth = depth;
+		}
+		if let Some(beam_width) = self.beam_width{
+			settings.beam_width = beam_width;
+		}
+		if self.perfect_clear == Some(true){
+			settings.perfect_clear = Some(ai::perfect_clear::Settings::default());
+		}
+		if let Some(ref path) = self.weights{
+			settings.weights = try!(fs::File::open(path).map_err(ai::evaluation::Error::Io).and_then(|mut file| ai::evaluation::Weights::read(&mut file)).map_err(|e| format!("Error when reading weights from {}: {:?}",path,e)));
+		}
+		Ok(settings)
+	}
+}
+
+///Result of a match between two contestants. The first contestant plays in world 0 and the second one in world 1.
+#[derive(Clone,Debug,RustcEncodable)]
+struct Match{
+	contestants: Vec<String>,
+	seed: Seed,
+
+	///Index of the winning contestant, None when it is a draw
+	winner: Option<usize>,
+
+	///Game time survived by each contestant
+	///Unit: seconds
+	survival_time: Vec<f64>,
+
+	///Garbage rows sent by each contestant
+	lines_sent: Vec<u32>,
+}
+
+///Summary of the matches of a contestant
+#[derive(Clone,Debug,RustcEncodable)]
+struct Standing{
+	name: String,
+	matches: u32,
+	wins: u32,
+	losses: u32,
+	draws: u32,
+	win_rate: f64,
+	average_survival_time: f64,
+	lines_sent: u32,
+	average_lines_sent: f64,
+}
+
+#[derive(RustcEncodable)]
+struct Results{
+	standings: Vec<Standing>,
+	matches: Vec<Match>,
+}
+
+///Number of garbage rows sent when completing the given number of rows at once
+fn garbage(full_rows: grid::SizeAxis) -> grid::SizeAxis{
+	match full_rows{
+		0 | 1 => 0,
+		2 => 1,
+		3 => 2,
+		n => n,
+	}
+}
+
+///Constructs the controller of the given contestant controlling the given player, seeding the built-in AI by the seed of the match
+fn controller(contestant: &Contestant,request_sender: &sync::mpsc::Sender<game::Request<PlayerId,WorldId>>,player_id: PlayerId,seed: Seed) -> Result<registry::BoxedController<World<bool>>,String>{
+	let controller: registry::BoxedController<World<bool>> = match contestant.bot{
+		Some(ref command) => Box::new(try!(external::Controller::new(
+			request_sender.clone(),
+			player_id,
+			process::Command::new("sh").arg("-c").arg(command),
+			external::Settings::default()
+		).map_err(|e| format!("Error when starting the bot \"{}\": {:?}",command,e)))),
+		None => Box::new(bruteforce::Controller::new(request_sender.clone(),player_id,try!(contestant.settings()),seed)),
+	};
+	Ok(controller)
+}
+
+///Plays a versus match between the two contestants
+fn play(contestants: (&Contestant,&Contestant),seed: Seed,time_limit: f64) -> Result<Match,String>{
+	let (request_sender,request_receiver) = sync::mpsc::channel();
+
+	let mut controllers = Registry::new();
+	controllers.set(0,Control::Controller(try!(controller(contestants.0,&request_sender,0,seed))));
+	controllers.set(1,Control::Controller(try!(controller(contestants.1,&request_sender,1,seed))));
+	let real_time = contestants.0.bot.is_some() || contestants.1.bot.is_some();
+
+	let player_settings = player::Settings{
+		gravityfall_frequency: 1.0,
+		fastfall_shadow      : false,
+	};
+	let settings = game::replay::Settings{
+		timestep: game::simulation::DEFAULT_TIMESTEP,
+		worlds  : vec![(0,grid::Size{x: 10,y: 20}),(1,grid::Size{x: 10,y: 20})],
+		players : vec![(0,player_settings),(1,player_settings)],
+	};
+
+	let mut simulation = Simulation::headless(seed,&settings,&mut |e| controllers.event(&e));
+
+	let mut lines_sent = vec![0u32; 2];
+	let mut survival_time = vec![0.0; 2];
+	let mut lost = [false; 2];
+	let mut time = 0.0;
+
+	while time < time_limit{
+		controllers.update(simulation.timestep(),&simulation.state.data);
+		simulation.receive(&request_receiver);
+
+		let mut sent = [0; 2];
+		simulation.step(&mut |e|{
+			if let Event::WorldImprintedShape{world,full_rows,..} = e{
+				sent[world as usize]+= garbage(full_rows);
+			}
+			controllers.event(&e);
+		});
+
+		//Send the garbage to the opponent
+		for world in 0..2{
+			if sent[world] > 0{
+				lines_sent[world]+= sent[world] as u32;
+				simulation.state.add_garbage(1 - world as WorldId,sent[world],true,&mut |e| controllers.event(&e));
+			}
+		}
+
+		time+= simulation.timestep();
+		for world in 0..2{
+			if !lost[world]{
+				lost[world] = simulation.state.data.worlds.get(world).map_or(true,|&(_,paused)| paused);
+				survival_time[world] = time;
+			}
+		}
+		if lost[0] || lost[1]{
+			break;
+		}
+
+		if real_time{
+			thread::sleep(Duration::from_millis((simulation.timestep()*1000.0) as u64));
+		}
+	}
+
+	Ok(Match{
+		contestants: vec![contestants.0.name.clone(),contestants.1.name.clone()],
+		seed: seed,
+		winner: match (lost[0],lost[1]){
+			(false,true) => Some(0),
+			(true,false) => Some(1),
+			_ => None
+		},
+		survival_time: survival_time,
+		lines_sent: lines_sent,
+	})
+}
+
+///Plays the matches in parallel
+///Returns the matches in the order of the pairings
+fn play_all(contestants: &[Contestant],pairings: Vec<(usize,usize,Seed)>,time_limit: f64,threads: usize) -> Vec<Match>{
+	let jobs: Vec<(usize,Contestant,Contestant,Seed)> = pairings.into_iter().enumerate().rev().map(|(index,(a,b,seed))| (index,contestants[a].clone(),contestants[b].clone(),seed)).collect();
+	let jobs = sync::Arc::new(sync::Mutex::new(jobs));
+	let (result_sender,result_receiver) = sync::mpsc::channel();
+
+	for _ in 0..cmp::max(threads,1){
+		let jobs = jobs.clone();
+		let result_sender = result_sender.clone();
+		thread::spawn(move ||{
+			loop{
+				let job = jobs.lock().unwrap().pop();
+				match job{
+					Some((index,a,b,seed)) => {let _ = result_sender.send((index,play((&a,&b),seed,time_limit)));},
+					None => break
+				}
+			}
+		});
+	}
+	drop(result_sender);
+
+	//The results arrive in the order the matches complete
+	let mut matches = Vec::new();
+	for (index,result) in result_receiver{
+		match result{
+			Ok(m)  => {
+				let _ = writeln!(io::stderr(),"{} vs {}: {}",m.contestants[0],m.contestants[1],m.winner.map_or("Draw",|i| &*m.contestants[i]));
+				matches.push((index,m));
+			},
+			Err(e) => {let _ = writeln!(io::stderr(),"{}",e);},
+		}
+	}
+	matches.sort_by_key(|&(index,_)| index);
+	matches.into_iter().map(|(_,m)| m).collect()
+}
+
+fn standings(contestants: &[Contestant],matches: &[Match]) -> Vec<Standing>{
+	contestants.iter().map(|contestant|{
+		let mut standing = Standing{
+			name: contestant.name.clone(),
+			matches: 0,
+			wins: 0,
+			losses: 0,
+			draws: 0,
+			win_rate: 0.0,
+			average_survival_time: 0.0,
+			lines_sent: 0,
+			average_lines_sent: 0.0,
+		};
+
+		for m in matches.iter(){
+			for i in (0..2).filter(|&i| m.contestants[i] == contestant.name){
+				standing.matches+= 1;
+				match m.winner{
+					Some(winner) if winner == i => standing.wins+= 1,
+					Some(_) => standing.losses+= 1,
+					None    => standing.draws+= 1,
+				}
+				standing.average_survival_time+= m.survival_time[i];
+				standing.lines_sent+= m.lines_sent[i];
+			}
+		}
+
+		if standing.matches > 0{
+			standing.win_rate = standing.wins as f64 / standing.matches as f64;
+			standing.average_survival_time/= standing.matches as f64;
+			standing.average_lines_sent = standing.lines_sent as f64 / standing.matches as f64;
+		}
+		standing
+	}).collect()
+}
+
+fn write_csv<W: io::Write>(writer: &mut W,standings: &[Standing]) -> io::Result<()>{
+	try!(writeln!(writer,"name,matches,wins,losses,draws,win_rate,average_survival_time,lines_sent,average_lines_sent"));
+	for s in standings.iter(){
+		try!(writeln!(writer,"\"{}\",{},{},{},{},{},{},{},{}",s.name.replace("\"","\"\""),s.matches,s.wins,s.losses,s.draws,s.win_rate,s.average_survival_time,s.lines_sent,s.average_lines_sent));
+	}
+	Ok(())
+}
+
+fn main(){
+	let args: Args = match Args::docopt().decode(){
+		Ok(args) => args,
+		Err(e)   => e.exit(),
+	};
+
+	let contestants: Vec<Contestant> = match fs::File::open(&args.arg_contestants).map_err(|e| format!("{}",e)).and_then(|mut file|{
+		let mut str = String::new();
+		try!(io::Read::read_to_string(&mut file,&mut str).map_err(|e| format!("{}",e)));
+		json::decode(&str).map_err(|e| format!("{:?}",e))
+	}){
+		Ok(contestants) => contestants,
+		Err(e) => {
+			let _ = writeln!(io::stderr(),"Error when reading contestants from {}: {}",args.arg_contestants,e);
+			process::exit(1);
+		}
+	};
+	for (i,contestant) in contestants.iter().enumerate(){
+		if let Err(e) = contestant.settings(){
+			let _ = writeln!(io::stderr(),"{}",e);
+			process::exit(1);
+		}
+
+		//The matches and standings refer to the contestants by name
+		if contestants[..i].iter().any(|other| other.name == contestant.name){
+			let _ = writeln!(io::stderr(),"Duplicate contestant name \"{}\" in {}",contestant.name,args.arg_contestants);
+			process::exit(1);
+		}
+	}
+
+	let seed = args.flag_seed.unwrap_or_else(game::seed::random);
+	let _ = writeln!(io::stderr(),"Seed: {}",seed);
+	let mut rng = game::seed::std_rng(seed);
+
+	//Every pair plays the same seeds, switching sides every other match
+	let seeds: Vec<Seed> = (0..args.flag_matches).map(|_| rng.gen()).collect();
+	let mut pairings = Vec::new();
+	for a in 0..contestants.len(){
+		for b in a+1 .. contestants.len(){
+			for (i,&seed) in seeds.iter().enumerate(){
+				pairings.push(if i%2 == 0{(a,b,seed)}else{(b,a,seed)});
+			}
+		}
+	}
+
+	let matches = play_all(&contestants,pairings,args.flag_time,args.flag_threads);
+	let results = Results{
+		standings: standings(&contestants,&matches),
+		matches: matches,
+	};
+
+	let mut output: Box<io::Write> = match args.flag_output{
+		Some(ref path) => match fs::File::create(path){
+			Ok(file) => Box::new(file),
+			Err(e) => {
+				let _ = writeln!(io::stderr(),"Error when creating {}: {}",path,e);
+				process::exit(1);
+			}
+		},
+		None => Box::new(io::stdout()),
+	};
+	let written = match args.flag_format{
+		Format::json => writeln!(output,"{}",json::as_pretty_json(&results)),
+		Format::csv  => write_csv(&mut output,&results.standings),
+	};
+	if let Err(e) = written{
+		let _ = writeln!(io::stderr(),"Error when writing the results: {}",e);
+		process::exit(1);
+	}
+}
@@ -0,0 +1,230 @@
+//!Tuner of the AI evaluation weights
+//!
+//!Evolves weight sets with a genetic algorithm.
+//!Every weight set plays a number of headless games in each generation, the same seeds being used by every weight set in the generation.
+//!The fitness of a weight set is the average number of completed rows in its games.
+//!The best weight sets are kept to the next generation and the rest of it is bred from the better half by crossover and mutation.
+
+#![feature(plugin)]
+#![plugin(docopt_macros)]
+
+extern crate docopt;
+extern crate rand;
+extern crate rustc_serialize;
+extern crate tetr;
+
+use rand::Rng;
+use rand::distributions::{IndependentSample,Normal};
+use std::{cmp,fs,io,process,sync,thread};
+use std::io::Write;
+
+use tetr::controller::Controller;
+use tetr::controller::ai::bruteforce;
+use tetr::controller::ai::evaluation::Weights;
+use tetr::data::grid;
+use tetr::game::{self,Event,Simulation};
+use tetr::game::data::{player,PlayerId,WorldId};
+use tetr::game::data::world::dynamic::World;
+use tetr::game::seed::Seed;
+
+docopt!(Args derive Debug,"
+Usage: tetr-tune [options]
+       tetr-tune --help
+
+Evolves the evaluation weights of the AI by playing headless games.
+
+Options:
+  -h, --help            Show this message
+  --population=N        Number of weight sets in every generation [default: 16]
+  --generations=N       Number of generations [default: 20]
+  --games=N             Number of games played by every weight set in a generation [default: 4]
+  --pieces=N            Maximum number of pieces in a game [default: 250]
+  --threads=N           Number of games played in parallel [default: 4]
+  --mutation=DEVIATION  Standard deviation of the mutations relative to the weights [default: 0.2]
+  --seed=N              Seed for the random number generators. A random seed is used when not specified
+  --weights=FILE        Initial weights in JSON. The default weights are used when not specified
+  --output=FILE         File the best weights are written to after every generation [default: weights.json]
+",
+	flag_population : usize,
+	flag_generations: usize,
+	flag_games      : usize,
+	flag_pieces     : u32,
+	flag_threads    : usize,
+	flag_mutation   : f64,
+	flag_seed       : Option<Seed>,
+	flag_weights    : Option<String>,
+	flag_output     : String,
+);
+
+///Number of weights that are evolved
+const GENES: usize = 10;
+
+fn genes(weights: &Weights) -> [f32; GENES]{[
+	weights.height_sum,
+	weights.height_max,
+	weights.rows_completed,
+	weights.holes,
+	weights.holes_weighted,
+	weights.bumpiness,
+	weights.bumpiness_allowance,
+	weights.wells,
+	weights.row_transitions,
+	weights.column_transitions,
+]}
+
+fn weights(genes: &[f32; GENES]) -> Weights{Weights{
+	height_sum         : genes[0],
+	height_max         : genes[1],
+	rows_completed     : genes[2],
+	holes              : genes[3],
+	holes_weighted     : genes[4],
+	bumpiness          : genes[5],
+	bumpiness_allowance: genes[6].max(0.0),
+	wells              : genes[7],
+	row_transitions    : genes[8],
+	column_transitions : genes[9],
+}}
+
+///Plays a headless game with the AI using the given weights until it loses or the limit of pieces is reached
+///Returns the number of completed rows
+fn play(weights: Weights,seed: Seed,max_pieces: u32) -> u32{
+	let (request_sender,request_receiver) = sync::mpsc::channel();
+	let mut controller: Box<Controller<World<bool>,Event<(PlayerId,WorldId),WorldId>>> = Box::new(bruteforce::Controller::new(request_sender,0,bruteforce::Settings{
+		move_time  : 0.0,
+		fall_time  : 0.0,
+		rotate_time: 0.0,
+		depth      : 1,
+		weights    : weights,
+		..bruteforce::Settings::default()
+	},seed));
+
+	let settings = game::replay::Settings{
+		timestep: game::simulation::DEFAULT_TIMESTEP,
+		worlds  : vec![(0,grid::Size{x: 10,y: 20})],
+		players : vec![(0,player::Settings{
+			gravityfall_frequency: 1.0,
+			fastfall_shadow      : false,
+		})],
+	};
+
+	let mut simulation = Simulation::headless(seed,&settings,&mut |e| controller.event(&e));
+
+	let mut pieces = 0;
+	let mut rows = 0;
+	while pieces < max_pieces && !simulation.state.data.worlds.get(0).map_or(true,|&(_,paused)| paused){
+		controller.update(simulation.timestep(),&simulation.state.data);
+		simulation.receive(&request_receiver);
+		simulation.step(&mut |e|{
+			if let Event::WorldImprintedShape{full_rows,..} = e{
+				pieces+= 1;
+				rows+= full_rows as u32;
+			}
+			controller.event(&e);
+		});
+	}
+
+	rows
+}
+
+///Plays the games of every weight set in parallel
+///Returns the average number of completed rows of every weight set
+fn fitness(population: &[Weights],seeds: &[Seed],max_pieces: u32,threads: usize) -> Vec<f64>{
+	let jobs: Vec<(usize,Weights,Seed)> = population.iter().enumerate().flat_map(|(i,&weights)| seeds.iter().map(move |&seed| (i,weights,seed))).collect();
+	let jobs = sync::Arc::new(sync::Mutex::new(jobs));
+	let (result_sender,result_receiver) = sync::mpsc::channel();
+
+	for _ in 0..cmp::max(threads,1){
+		let jobs = jobs.clone();
+		let result_sender = result_sender.clone();
+		thread::spawn(move ||{
+			loop{
+				let job = jobs.lock().unwrap().pop();
+				match job{
+					Some((i,weights,seed)) => {let _ = result_sender.send((i,play(weights,seed,max_pieces)));},
+					None => break
+				}
+			}
+		});
+	}
+	drop(result_sender);
+
+	let mut fitness = vec![0.0; population.len()];
+	for (i,rows) in result_receiver{
+		fitness[i]+= rows as f64 / seeds.len() as f64;
+	}
+	fitness
+}
+
+///Mutates every weight with the probability 1/2 by a normal distributed amount relative to the weight
+fn mutate<R: Rng>(rng: &mut R,genes: &mut [f32; GENES],deviation: f64){
+	for gene in genes.iter_mut(){
+		if rng.gen(){
+			let normal = Normal::new(0.0,deviation * (gene.abs() as f64 + 0.1));
+			*gene+= normal.ind_sample(rng) as f32;
+		}
+	}
+}
+
+fn main(){
+	let args: Args = match Args::docopt().decode(){
+		Ok(args) => args,
+		Err(e)   => e.exit(),
+	};
+
+	let seed = args.flag_seed.unwrap_or_else(game::seed::random);
+	println!("Seed: {}",seed);
+	let mut rng = game::seed::std_rng(seed);
+
+	let initial = match args.flag_weights{
+		Some(ref path) => match fs::File::open(path).map_err(tetr::controller::ai::evaluation::Error::Io).and_then(|mut file| Weights::read(&mut file)){
+			Ok(weights) => weights,
+			Err(e) => {
+				let _ = writeln!(io::stderr(),"Error when reading weights from {}: {:?}",path,e);
+				process::exit(1);
+			}
+		},
+		None => Weights::default()
+	};
+
+	//The initial weights and mutations of them
+	let population_len = cmp::max(args.flag_population,2);
+	let mut population = vec![initial];
+	while population.len() < population_len{
+		let mut genes = genes(&initial);
+		mutate(&mut rng,&mut genes,args.flag_mutation);
+		population.push(weights(&genes));
+	}
+
+	for generation in 0..args.flag_generations{
+		let seeds: Vec<Seed> = (0..cmp::max(args.flag_games,1)).map(|_| rng.gen()).collect();
+		let scores = fitness(&population,&seeds,args.flag_pieces,args.flag_threads);
+
+		//Rank by fitness, best first
+		let mut ranked: Vec<(f64,Weights)> = scores.into_iter().zip(population.into_iter()).collect();
+		ranked.sort_by(|a,b| b.0.partial_cmp(&a.0).unwrap_or(cmp::Ordering::Equal));
+
+		println!("Generation {}: Best {:.2} rows, average {:.2} rows",generation,ranked[0].0,ranked.iter().fold(0.0,|sum,&(f,_)| sum + f) / ranked.len() as f64);
+		match fs::File::create(&args.flag_output).map_err(tetr::controller::ai::evaluation::Error::Io).and_then(|mut file| ranked[0].1.write(&mut file)){
+			Ok(()) => (),
+			Err(e) => {
+				let _ = writeln!(io::stderr(),"Error when writing weights to {}: {:?}",args.flag_output,e);
+				process::exit(1);
+			},
+		}
+
+		//Keep the best quarter and breed the rest from the better half
+		let elites = cmp::max(population_len/4,1);
+		let parents = cmp::max(population_len/2,1);
+		population = ranked.iter().take(elites).map(|&(_,weights)| weights).collect();
+		while population.len() < population_len{
+			let a = genes(&ranked[rng.gen_range(0,parents)].1);
+			let b = genes(&ranked[rng.gen_range(0,parents)].1);
+			let mut child = [0.0; GENES];
+			for i in 0..GENES{
+				child[i] = if rng.gen(){a[i]}else{b[i]};
+			}
+			mutate(&mut rng,&mut child,args.flag_mutation);
+			population.push(weights(&child));
+		}
+	}
+}
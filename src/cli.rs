@@ -21,21 +21,37 @@ Options:
   -v, --version         Show version
   --credits             Show credits/staff
   --manual              Show instruction manual/guide for the game
+  --ai-difficulty=LVL   Available levels: beginner, easy, medium, hard, expert [default: hard]
+  --ai-weights=FILE     Evaluation weights of the AI in JSON (See the tuner `tetr-tune`)
+  --ai-threads=N        Number of threads the AI searches in [default: 2]
+  --bot=COMMAND         Shell command starting an external bot controlling the AI player (See `controller::external`)
   --online=CONNECTION   Available modes: none, server, client [default: none]
   --host=ADDR           Network address used for the online connection [default: 0.0.0.0]
   --port=N              Network port used for the online connection [default: 7374]
+  --record=FILE         Records the game to a replay file
+  --replay=FILE         Plays a replay file instead of starting a new game
+  --save-file=FILE      File used when saving (F5) and loading (F9) the game [default: tetr.sav]
+  --seed=N              Seed for the random number generators. A random seed is used when not specified
   --window-size=SIZE    Window size [default: 800x600]
   --window-mode=MODE    Available modes: window, fullscreen [default: window]
   --gl-backend=BACKEND  Not implemented yet. Available backends: sdl2, glfw, glutin [default: glutin]
   --gl-version=NN       Available versions: 20, 21, 30, 31, 32, 33, 40, 41, 42, 43, 44, 45 [default: 32]
 "),
-	flag_online     : OnlineConnection,
-	flag_host       : Host,
-	flag_port       : Port,
-	flag_window_size: WindowSize,
-	flag_window_mode: WindowMode,
-	flag_gl_backend : GlBackend,
-	flag_gl_version : GlVersion,
+	flag_ai_difficulty: AiDifficulty,
+	flag_ai_weights   : Option<String>,
+	flag_ai_threads   : usize,
+	flag_bot          : Option<String>,
+	flag_online       : OnlineConnection,
+	flag_host         : Host,
+	flag_port         : Port,
+	flag_seed         : Option<Seed>,
+	flag_record       : Option<String>,
+	flag_replay       : Option<String>,
+	flag_save_file    : String,
+	flag_window_size  : WindowSize,
+	flag_window_mode  : WindowMode,
+	flag_gl_backend   : GlBackend,
+	flag_gl_version   : GlVersion,
 );
 
 ///Workaround for the creation of Args because the docopt macro is not making everything public
@@ -62,9 +78,14 @@ impl Decodable for Host{
 
 pub type Port = u16;
 
+pub use ::game::seed::Seed;
+
 #[derive(Debug,RustcDecodable)]
 pub enum OnlineConnection{none,server,client}
 
+#[derive(Debug,RustcDecodable)]
+pub enum AiDifficulty{beginner,easy,medium,hard,expert}
+
 #[derive(Debug)]
 pub struct WindowSize(pub u32,pub u32);
 impl Decodable for WindowSize{
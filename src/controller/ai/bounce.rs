@@ -1,10 +1,10 @@
-use piston::input::UpdateArgs;
 use std::sync;
 
 use super::super::Controller as ControllerTrait;
 use ::game::{self,Event,Request};
 use ::game::data::{Input,PlayerId,World,WorldId};
 
+///Moves the player's shape from wall to wall, changing direction when colliding
 #[derive(Clone)]
 pub struct Controller{
 	pub request_sender: sync::mpsc::Sender<Request<PlayerId,WorldId>>,
@@ -25,8 +25,8 @@ impl Controller{
 impl<W> ControllerTrait<W,Event<(PlayerId,WorldId),WorldId>> for Controller
 	where W: World
 {
-	fn update(&mut self,args: &UpdateArgs,_: &game::Data<W>){
-		self.move_time+= args.dt;
+	fn update(&mut self,dt: f64,_: &game::Data<W>){
+		self.move_time+= dt;
 
 		if self.move_time > 0.3{
 			let _ = self.request_sender.send(Request::PlayerInput{
@@ -37,11 +37,15 @@ impl<W> ControllerTrait<W,Event<(PlayerId,WorldId),WorldId>> for Controller
 		}
 	}
 
-	fn event<'l>(&mut self,_: &Event<(game::data::PlayerId,game::data::WorldId),game::data::WorldId>){/*
+	fn event(&mut self,event: &Event<(game::data::PlayerId,game::data::WorldId),game::data::WorldId>){
 		use game::Event::*;
 
 		match event{
-			TODO: PlayerMoveCollide{..} => {self.bounce = !self.bounce;}
+			//Move in the opposite direction after colliding horizontally
+			&PlayerCollidedOnMovement{player: (player_id,_),current,target,..} if player_id == self.player_id && current.x != target.x => {
+				self.bounce = target.x > current.x;
+			},
+			_ => ()
 		}
-	*/}
+	}
 }
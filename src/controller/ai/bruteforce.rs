@@ -1,24 +1,67 @@
+//!An AI that tries every placement of the current shape and the shapes in the preview queue, choosing the best one according to an evaluation of the resulting world
+//!
+//!The placements searched are the ones reachable by inputs (See `game::pathfinder`), including tucks and spins.
+//!The search is done ply by ply, one ply for each shape.
+//!Only the `Settings::beam_width` best placements of every ply are searched further, and the search stops at the last completed ply when the time budget is exceeded.
+//!The hold piece is not considered because holding is not yet supported by the game.
+//!When enabled by `Settings::perfect_clear`, a perfect clear is searched for first (See `perfect_clear`) and followed when found.
+//!
+//!The path to the chosen placement is recalculated before every input so that movement caused by gravity is accounted for.
+//!The placement is searched for again when the world of the player changes by other means than the player (e.g. other players in the same world).
+//!When no placement can be reached, no inputs are performed until the shape or the world changes.
+//!
+//!A controller constructed with a worker pool (See `pool`) searches in the pool instead of in `update`, performing no inputs until the result arrives.
+//!Results of searches started before the latest shape or world change are discarded.
+//!
+//!To be beatable, the AI can be made to act more like a human by the settings (See `Difficulty`): a limited speed, a reaction time, sometimes choosing worse placements and sometimes misdropping.
+//!The random choices are seeded, so that a game with AI players is reproducible from the seeds of the game and the controllers.
+
+use core::cmp;
 use core::default::Default;
-use core::f32;
-use core::iter::Iterator;
-use piston::input::UpdateArgs;
+use core::f64;
+use core::iter::{self,Iterator};
+use rand::{self,Rng};
 use std::sync;
+use std::time::Instant;
 
 use super::super::Controller as ControllerTrait;
-use ::data::grid::{self,translate,RectangularBound};
-use ::data::shapes::tetromino::{Shape,Rotation};
+use super::evaluation::{self,Weights};
+use super::{perfect_clear,pool};
+use ::data::grid::{self,RectangularBound};
+use ::data::shapes::tetromino::{RotatedShape,Shape,Rotation};
 use ::data::{Cell,Grid};
-use ::game::{self,Event,Request};
-use ::game::data::{Input,PlayerId,World,WorldId};
+use ::game::{self,pathfinder,seed,Event,Request};
+use ::game::event::ShapeImprintCause;
+use ::game::data::{world,Input,PlayerId,World,WorldId};
 
-#[derive(Clone)]
 pub struct Controller{
 	pub request_sender: sync::mpsc::Sender<Request<PlayerId,WorldId>>,
 	pub player_id: game::data::PlayerId,
 	pub settings: Settings,
-	move_time_count: f64,
-	rotate_time_count: f64,
+	input_time_count: f64,
+	piece_time_count: f64,
 	target: Option<(grid::Pos,Rotation)>,
+	world: Option<WorldId>,
+	idle: bool,
+	rng: rand::XorShiftRng,
+
+	///Whether the target has been searched for again because it was unreachable
+	replanned: bool,
+
+	///Incremented every time the target is forgotten, identifying the searches in the worker pool
+	generation: u32,
+
+	///Whether a search of the current generation is in progress in the worker pool
+	searching: bool,
+
+	threaded: Option<Threaded>,
+}
+
+///Worker pool and the channel of its results, for controllers searching in a pool
+struct Threaded{
+	pool: pool::Pool,
+	result_sender: sync::mpsc::Sender<(u32,pool::Targets)>,
+	result_receiver: sync::mpsc::Receiver<(u32,pool::Targets)>,
 }
 
 #[derive(Copy,Clone,Debug,PartialEq)]
@@ -26,6 +69,88 @@ pub struct Settings{
 	pub move_time: f64,
 	pub fall_time: f64,
 	pub rotate_time: f64,
+
+	///Number of shapes to search placements for, the current one included.
+	///Limited by the number of shapes in the player's preview queue.
+	pub depth: usize,
+
+	///Number of placements of every ply that are searched further
+	pub beam_width: usize,
+
+	///Time budget of a search, checked between every searched placement.
+	///When exceeded, the best placement of the last completed ply is chosen.
+	///Unit: seconds
+	pub search_time: f64,
+
+	///Weights used when evaluating the placements
+	pub weights: Weights,
+
+	///Maximum number of shapes placed per second, limiting the final fall of each shape.
+	///Unlimited when infinite.
+	///Requirements:
+	///    pieces_per_second > 0
+	pub pieces_per_second: f64,
+
+	///Time waited before the first input of every new shape
+	///Unit: seconds
+	pub reaction_time: f64,
+
+	///Probability of choosing the next best placement instead of the best one.
+	///Applied repeatedly, so that even worse placements are chosen with a decreasing probability.
+	pub suboptimal_probability: f64,
+
+	///Probability of placing a shape in a column next to the chosen one
+	pub misdrop_probability: f64,
+
+	///Settings of the search for perfect clears, which is tried before the normal search when enabled
+	pub perfect_clear: Option<perfect_clear::Settings>,
+}
+
+impl Settings{
+	///Settings of the given difficulty
+	pub fn preset(difficulty: Difficulty) -> Self{
+		match difficulty{
+			Difficulty::Beginner => Settings{
+				move_time             : 0.35,
+				fall_time             : 0.1,
+				rotate_time           : 0.5,
+				depth                 : 1,
+				beam_width            : 1,
+				pieces_per_second     : 0.5,
+				reaction_time         : 0.8,
+				suboptimal_probability: 0.5,
+				misdrop_probability   : 0.1,
+				..Settings::default()
+			},
+			Difficulty::Easy => Settings{
+				move_time             : 0.25,
+				fall_time             : 0.08,
+				rotate_time           : 0.45,
+				depth                 : 1,
+				beam_width            : 1,
+				pieces_per_second     : 0.8,
+				reaction_time         : 0.5,
+				suboptimal_probability: 0.25,
+				misdrop_probability   : 0.05,
+				..Settings::default()
+			},
+			Difficulty::Medium => Settings{
+				pieces_per_second     : 1.25,
+				reaction_time         : 0.25,
+				suboptimal_probability: 0.1,
+				misdrop_probability   : 0.01,
+				..Settings::default()
+			},
+			Difficulty::Hard => Settings::default(),
+			Difficulty::Expert => Settings{
+				move_time  : 0.05,
+				fall_time  : 0.01,
+				rotate_time: 0.1,
+				depth      : 3,
+				..Settings::default()
+			},
+		}
+	}
 }
 
 impl Default for Settings{
@@ -33,45 +158,126 @@ impl Default for Settings{
 		move_time: 0.2,
 		fall_time: 0.05,
 		rotate_time: 0.4,
+		depth      : 2,
+		beam_width : 8,
+		search_time: 0.01,
+		weights    : Weights::default(),
+		pieces_per_second     : f64::INFINITY,
+		reaction_time         : 0.0,
+		suboptimal_probability: 0.0,
+		misdrop_probability   : 0.0,
+		perfect_clear         : None,
 	}}
 }
 
+///Named presets of the settings, from the weakest to the strongest
+#[derive(Copy,Clone,Debug,Eq,PartialEq,RustcDecodable,RustcEncodable)]
+pub enum Difficulty{
+	Beginner,
+	Easy,
+	Medium,
+	Hard,
+	Expert,
+}
+
 impl Controller{
-	pub fn new(request_sender: sync::mpsc::Sender<Request<PlayerId,WorldId>>,player_id: game::data::PlayerId,settings: Settings) -> Self{Controller{
+	///Constructs a controller with its random choices seeded by the given seed and the player id, so that controllers of different players constructed with the same seed make different choices
+	pub fn new(request_sender: sync::mpsc::Sender<Request<PlayerId,WorldId>>,player_id: game::data::PlayerId,settings: Settings,seed: seed::Seed) -> Self{Controller{
 		request_sender: request_sender,
 		player_id: player_id,
 		settings: settings,
-		move_time_count: 0.0,
-		rotate_time_count: 0.0,
+		input_time_count: 0.0,
+		piece_time_count: 0.0,
 		target: None,
+		world: None,
+		idle: false,
+		rng: seed::std_rng(seed ^ player_id as seed::Seed).gen(),
+		replanned: false,
+		generation: 0,
+		searching: false,
+		threaded: None,
 	}}
 
-	pub fn recalculate_optimal_target<W>(&mut self,world: &W,shape: Shape,pos: grid::Pos)
+	///Constructs a controller that searches in the given worker pool
+	pub fn with_pool(request_sender: sync::mpsc::Sender<Request<PlayerId,WorldId>>,player_id: game::data::PlayerId,settings: Settings,seed: seed::Seed,pool: pool::Pool) -> Self{
+		let (result_sender,result_receiver) = sync::mpsc::channel();
+		Controller{
+			threaded: Some(Threaded{
+				pool: pool,
+				result_sender: result_sender,
+				result_receiver: result_receiver,
+			}),
+			..Controller::new(request_sender,player_id,settings,seed)
+		}
+	}
+
+	///Returns the world of the controlled player, if known
+	#[inline(always)]pub fn world(&self) -> Option<WorldId>{self.world}
+
+	///Forgets the target, searching for a new one in the next update
+	fn retarget(&mut self){
+		self.target = None;
+		self.idle = false;
+		self.replanned = false;
+		self.searching = false;
+		self.generation = self.generation.wrapping_add(1);
+	}
+
+	///Recalculates the target by searching the placements of the given shape at the given position and the shapes that follows it
+	pub fn recalculate_optimal_target<W,I>(&mut self,world: &W,shape: RotatedShape,pos: grid::Pos,lookahead: I)
+		where W: World,
+		      <W as Grid>::Cell: Cell + Copy,
+		      I: IntoIterator<Item = Shape>
+	{
+		let targets = search_targets(world,shape,pos,lookahead,&self.settings);
+		self.choose_target(world,shape,pos,targets);
+	}
+
+	///Starts a search in the worker pool for the placements of the given shape at the given position and the shapes that follows it
+	fn search_threaded<W,I>(&mut self,world: &W,shape: RotatedShape,pos: grid::Pos,lookahead: I)
+		where W: World,
+		      <W as Grid>::Cell: Cell + Copy,
+		      I: IntoIterator<Item = Shape>
+	{
+		if let Some(ref threaded) = self.threaded{
+			match scratch_world(world){
+				Some(world) => threaded.pool.search(pool::Job{
+					world: world,
+					shape: shape,
+					pos: pos,
+					lookahead: lookahead.into_iter().collect(),
+					settings: self.settings,
+					generation: self.generation,
+					result_sender: threaded.result_sender.clone(),
+				}),
+
+				//The world cannot be searched, which is the same as having no possible placements
+				None => {let _ = threaded.result_sender.send((self.generation,Vec::new()));}
+			}
+		}
+		self.searching = true;
+	}
+
+	///Chooses the target from the searched placements of the given shape at the given position, the best one first
+	fn choose_target<W>(&mut self,world: &W,shape: RotatedShape,pos: grid::Pos,targets: Vec<(grid::Pos,Rotation)>)
 		where W: World,
 		      <W as Grid>::Cell: Cell + Copy
 	{
-		let mut greatest_o = f32::NEG_INFINITY;
-
-		for rotated_shape in shape.rotations(){
-			if let Some(shape_bound_x) = rotated_shape.real_bound_x(){
-				for x in -(rotated_shape.width() as grid::PosAxis)+1 .. world.width() as grid::PosAxis{
-					//TODO: This should work too (but slower): if grid::is_grid_cells_inside(world,&translate::Grid{grid: &rotated_shape,pos: grid::Pos{x: -x,y: 0}}){
-					if (shape_bound_x.0 as grid::PosAxis)+x >= 0 && (shape_bound_x.1 as grid::PosAxis)+x < world.width() as grid::PosAxis{
-						let pos = game::state::fastfallen_shape_pos(
-							&rotated_shape,
-							world,
-							pos.with_x(x)
-						);
-
-						let optimality_test_world = grid::imprint_bool::Grid{a: world,b: &translate::Grid{grid: &rotated_shape,pos: -pos}};
-//println!("{:?}",super::super::super::data::grid::printer::OccupyPrinter(&optimality_test_world));
-
-						let current_o = world_optimality2(&optimality_test_world);
-						if current_o > greatest_o{
-							greatest_o = current_o;
-							self.target = Some((pos,rotated_shape.rotation()));
-						}
-					}
+		//Sometimes choose a worse placement
+		let mut i = 0;
+		while i+1 < targets.len() && self.rng.gen::<f64>() < self.settings.suboptimal_probability{
+			i+= 1;
+		}
+		self.target = targets.get(i).cloned();
+
+		//Sometimes misdrop to a neighbouring column
+		if let Some((target_pos,target_rotation)) = self.target{
+			if self.rng.gen::<f64>() < self.settings.misdrop_probability{
+				let misdrops: Vec<_> = pathfinder::placements(world,shape,pos).into_iter()
+					.filter(|placement| placement.shape.rotation() == target_rotation && (placement.pos.x - target_pos.x).abs() == 1)
+					.collect();
+				if let Some(misdrop) = self.rng.choose(&misdrops){
+					self.target = Some((misdrop.pos,target_rotation));
 				}
 			}
 		}
@@ -82,45 +288,90 @@ impl<'l,W> ControllerTrait<W,Event<(PlayerId,WorldId),WorldId>> for Controller
 	where W: World,
 	      <W as Grid>::Cell: Cell + Copy
 {
-	fn update(&mut self,args: &UpdateArgs,game_data: &game::Data<W>){
+	fn update(&mut self,dt: f64,game_data: &game::Data<W>){
 		if let Some(player) = game_data.players.get(self.player_id as usize){
-			let (target_pos,target_rotation) = match self.target{
-				Some(target) => target,
-				None => if let Some(&(ref world,false)) = game_data.worlds.get(player.world as usize){
-					self.recalculate_optimal_target(world,player.shape.shape(),player.pos);
-					match self.target{
-						Some(target) => target,
-						None => return
+			self.world = Some(player.world);
+
+			if let Some(&(ref world,false)) = game_data.worlds.get(player.world as usize){
+				if self.idle{
+					return;
+				}
+
+				//Receive the results of the searches in the worker pool, discarding the ones of previous generations
+				let mut result = None;
+				if let Some(ref threaded) = self.threaded{
+					while let Ok((generation,targets)) = threaded.result_receiver.try_recv(){
+						if generation == self.generation{
+							result = Some(targets);
+						}
 					}
-				}else{
-					return
 				}
-			};
-
-			self.move_time_count-= args.dt;
-			self.rotate_time_count-= args.dt;
-
-			while self.move_time_count <= 0.0{
-				if player.pos.x > target_pos.x{
-					let _ = self.request_sender.send(Request::PlayerInput{input: Input::MoveLeft,player: self.player_id});
-					self.move_time_count+=self.settings.move_time;
-				}else if player.pos.x < target_pos.x{
-					let _ = self.request_sender.send(Request::PlayerInput{input: Input::MoveRight,player: self.player_id});
-					self.move_time_count+=self.settings.move_time;
-				}else if player.shape.rotation() == target_rotation{
-					let _ = self.request_sender.send(Request::PlayerInput{input: Input::SlowFall,player: self.player_id});
-					self.move_time_count+=self.settings.fall_time;
-				}else{
-					break
+				if let Some(targets) = result{
+					self.searching = false;
+					self.choose_target(world,player.shape,player.pos,targets);
+					if self.target.is_none(){
+						self.idle = true;
+						return;
+					}
 				}
-			}
 
-			while self.rotate_time_count <= 0.0{
-				if player.shape.rotation() != target_rotation{
-					let _ = self.request_sender.send(Request::PlayerInput{input: Input::RotateAntiClockwise,player: self.player_id});
-					self.rotate_time_count+=self.settings.rotate_time;
-				}else{
-					break;
+				let (target_pos,target_rotation) = match self.target{
+					Some(target) => target,
+					None => {
+						let lookahead = player.shapes_lookahead.iter().flat_map(|shapes| shapes.iter().cloned());
+						if self.threaded.is_some(){
+							//Wait for the search to complete
+							if !self.searching{
+								self.search_threaded(world,player.shape,player.pos,lookahead);
+							}
+							return;
+						}
+
+						self.recalculate_optimal_target(world,player.shape,player.pos,lookahead);
+						match self.target{
+							Some(target) => target,
+
+							//No possible placements
+							None => {
+								self.idle = true;
+								return
+							}
+						}
+					}
+				};
+
+				self.input_time_count-= dt;
+				self.piece_time_count+= dt;
+
+				if self.input_time_count <= 0.0{
+					match pathfinder::path(world,player.shape,player.pos,target_pos,target_rotation).and_then(|inputs| inputs.first().cloned()){
+						//Limit the number of shapes placed per second by waiting before the final fall
+						Some(Input::FastFall) if self.piece_time_count < 1.0/self.settings.pieces_per_second => (),
+
+						Some(input) => {
+							//The final fast fall is performed as slow falls
+							let input = if input == Input::FastFall{Input::SlowFall}else{input};
+
+							let _ = self.request_sender.send(Request::PlayerInput{input: input,player: self.player_id});
+							self.input_time_count = match input{
+								Input::RotateClockwise | Input::RotateAntiClockwise => self.settings.rotate_time,
+								Input::SlowFall | Input::FastFall => self.settings.fall_time,
+								_ => self.settings.move_time,
+							};
+						},
+
+						//The target is no longer reachable (e.g. because of gravity or the world changing). Search again once, aborting if that target is unreachable too.
+						None => {
+							self.target = None;
+							if self.replanned{
+								self.idle = true;
+							}else{
+								self.replanned = true;
+								self.searching = false;
+								self.generation = self.generation.wrapping_add(1);
+							}
+						}
+					}
 				}
 			}
 		}
@@ -130,24 +381,191 @@ impl<'l,W> ControllerTrait<W,Event<(PlayerId,WorldId),WorldId>> for Controller
 		use game::Event::*;
 
 		match event{
-			&PlayerAdded{player: (player_id,_),..} if player_id == self.player_id => {
-				self.target = None;
+			&PlayerAdded{player: (player_id,world_id),..} if player_id == self.player_id => {
+				self.input_time_count = self.settings.reaction_time;
+				self.piece_time_count = 0.0;
+				self.world = Some(world_id);
+				self.retarget();
+			},
+			&PlayerRemoved{player: (player_id,_),..} if player_id == self.player_id => {
+				self.world = None;
+				self.retarget();
+			},
+			&PlayerMovedWorld{player: (player_id,_),new: world_id,..} if player_id == self.player_id => {
+				self.world = Some(world_id);
+				self.retarget();
 			},
-			//When other players imprints on the world TODO: CAnnot know which world this controller controls its player
-			/*WorldImprintShape{cause: Some(player_id),world: world_id,..} if player_id != self.player_id && world_id==self.player.world => {
-				self.recalculate_optimal_target(&*world,player.shape.shape(),player.pos);
-			},*/
 			&PlayerChangedShape{player: (player_id,_),..} if player_id == self.player_id => {
-				self.move_time_count = 0.0;
-				self.rotate_time_count = 0.0;
+				self.input_time_count = self.settings.reaction_time;
+				self.piece_time_count = 0.0;
 
-				self.target = None;
+				self.retarget();
+			},
+			//When the world changes by something else than this controller's player
+			&WorldImprintedShape{world: world_id,ref cause,..} if Some(world_id) == self.world => match cause{
+				&ShapeImprintCause::PlayerInflicted((player_id,_)) if player_id == self.player_id => (),
+				_ => self.retarget()
+			},
+			&WorldUpdated{world: world_id} if Some(world_id) == self.world => {
+				self.retarget();
 			},
 			_ => ()
 		}
 	}
 }
 
+///World used for imprinting shapes during a search. Worlds wider than `bitboard::WIDTH_MAX` cannot be searched and have no targets.
+type ScratchWorld = world::bitboard::World;
+
+///A searched placement
+struct Node{
+	///The placement of the first shape leading to this node
+	target: (grid::Pos,Rotation),
+
+	///Position of the placed shape
+	pos: grid::Pos,
+
+	///The placed shape
+	shape: RotatedShape,
+
+	///Index of the parent node in the previous ply
+	parent: usize,
+
+	///Evaluation of the world with the shape imprinted, including the completed rows of the previous plies
+	o: f32,
+}
+
+///Searches the placements of the given shape at the given position like `search_ranked`.
+///When enabled in the settings and a perfect clear is found, the first placement of the perfect clear is the only one returned.
+pub fn search_targets<W,I>(world: &W,shape: RotatedShape,pos: grid::Pos,lookahead: I,settings: &Settings) -> Vec<(grid::Pos,Rotation)>
+	where W: World,
+	      <W as Grid>::Cell: Cell + Copy,
+	      I: IntoIterator<Item = Shape>
+{
+	let lookahead: Vec<Shape> = lookahead.into_iter().collect();
+
+	if let Some(ref perfect_clear_settings) = settings.perfect_clear{
+		if let Some(placement) = perfect_clear::search(world,shape,pos,lookahead.iter().cloned(),perfect_clear_settings).and_then(|placements| placements.into_iter().next()){
+			return vec![(placement.pos,placement.shape.rotation())];
+		}
+	}
+
+	search_ranked(world,shape,pos,lookahead,settings)
+}
+
+///Searches for the best placement of the given shape at the given position, considering the placements of the shapes that follows it.
+///Returns the position and rotation of the best placement, or None if there are no possible placements.
+#[inline]
+pub fn search<W,I>(world: &W,shape: RotatedShape,pos: grid::Pos,lookahead: I,settings: &Settings) -> Option<(grid::Pos,Rotation)>
+	where W: World,
+	      <W as Grid>::Cell: Cell + Copy,
+	      I: IntoIterator<Item = Shape>
+{
+	search_ranked(world,shape,pos,lookahead,settings).first().cloned()
+}
+
+///Searches the placements of the given shape at the given position, considering the placements of the shapes that follows it.
+///Returns the positions and rotations of the placements in the order of the best placements they lead to, the best one first.
+///The placements pruned in the last completed ply are not included, and nothing is returned for worlds that cannot be searched (See `ScratchWorld`).
+pub fn search_ranked<W,I>(world: &W,shape: RotatedShape,pos: grid::Pos,lookahead: I,settings: &Settings) -> Vec<(grid::Pos,Rotation)>
+	where W: World,
+	      <W as Grid>::Cell: Cell + Copy,
+	      I: IntoIterator<Item = Shape>
+{
+	let start_time = Instant::now();
+	let out_of_time = || {
+		let elapsed = start_time.elapsed();
+		elapsed.as_secs() as f64 + elapsed.subsec_nanos() as f64 * 1e-9 > settings.search_time
+	};
+
+	//The worlds of the previous ply together with the optimality of the rows completed in them and the placement of the first shape leading to them
+	let mut worlds = match scratch_world(world){
+		Some(world) => vec![(world,0.0,None)],
+		None => return Vec::new(),
+	};
+	let mut ranked = Vec::new();
+
+	for (depth,shape) in iter::once(shape).chain(lookahead.into_iter().map(RotatedShape::new)).take(cmp::max(settings.depth,1)).enumerate(){
+		//Every shape after the current one starts at the top
+		let pos = if depth==0{pos}else{pos.with_y(0)};
+		let mut nodes = Vec::new();
+
+		//Evaluate every placement in every world of the previous ply
+		for (parent,&(ref world,rows_o,target)) in worlds.iter().enumerate(){
+			for pathfinder::Placement{pos,shape: rotated_shape,..} in pathfinder::placements(world,shape,pos){
+				if depth > 0 && out_of_time(){
+					return ranked;
+				}
+
+				let o = {
+					let mut optimality_test_world = world.clone();
+					optimality_test_world.imprint_shape(&rotated_shape,pos,&(occupied as fn(&_) -> _));
+					evaluation::evaluate_bitboard(&optimality_test_world,&settings.weights) + rows_o
+				};
+				nodes.push(Node{
+					target: target.unwrap_or((pos,rotated_shape.rotation())),
+					pos   : pos,
+					shape : rotated_shape,
+					parent: parent,
+					o     : o,
+				});
+			}
+		}
+
+		if nodes.is_empty(){
+			break;
+		}
+
+		//Rank the placements of the first shape by the best placements they lead to
+		nodes.sort_by(|a,b| b.o.partial_cmp(&a.o).unwrap_or(cmp::Ordering::Equal));
+		ranked.clear();
+		for node in nodes.iter(){
+			if !ranked.contains(&node.target){
+				ranked.push(node.target);
+			}
+		}
+
+		//Prune all but the best placements
+		nodes.truncate(cmp::max(settings.beam_width,1));
+
+		//Imprint the placements, preparing the worlds of the next ply
+		let next_worlds: Vec<_> = nodes.iter().map(|node|{
+			let (ref parent_world,parent_rows_o,_) = worlds[node.parent];
+			let mut world = parent_world.clone();
+			let full_rows = imprint(&mut world,&node.shape,node.pos);
+			(world,parent_rows_o + settings.weights.rows_completed(full_rows),Some(node.target))
+		}).collect();
+		worlds = next_worlds;
+	}
+
+	ranked
+}
+
+///Copies the occupied cells of the world to a new scratch world, if the world can be searched
+fn scratch_world<W>(world: &W) -> Option<ScratchWorld>
+	where W: World,
+	      <W as Grid>::Cell: Cell + Copy
+{
+	ScratchWorld::try_from_world(world)
+}
+
+///Cell constructor for imprinting on a scratch world
+fn occupied(_: &RotatedShape) -> bool{true}
+
+///Imprints the shape at the given position on the scratch world and handles the full rows the same way as the game does
+///Returns the number of full rows
+pub fn imprint(world: &mut world::bitboard::World,shape: &RotatedShape,pos: grid::Pos) -> grid::SizeAxis{
+	world.imprint_shape(shape,pos,&(occupied as fn(&_) -> _));
+
+	let min_y = cmp::max(0,pos.y) as grid::SizeAxis;
+	let max_y = cmp::min(min_y + shape.height(),world.height());
+	if min_y!=max_y{
+		world.handle_full_rows(min_y .. max_y)
+	}else{
+		0
+	}
+}
+
 #[allow(unused)]
 fn world_optimality<W>(world: &W) -> f32
 	where W: Grid + RectangularBound,
@@ -174,48 +592,3 @@ fn world_optimality<W>(world: &W) -> f32
 
 	-o
 }
-
-///Greater is better
-#[allow(unused)]
-fn world_optimality2<W>(world: &W) -> f32
-	where W: Grid + RectangularBound,
-	      <W as Grid>::Cell: Cell + Copy
-{
-	let world_height = world.height();
-	let rows_completed = grid::rows_iter::Iter::new(world).filter_map(|row| if grid::row::Iter::new(row).all(|(_,cell)| cell.is_occupied()){Some(())}else{None}).count();
-	let mut columns_height_sum = 0;
-	let mut cells_vertically_blocked_penalty = 0.0;
-	let mut height_bumpiness = 0;
-
-	let mut previous_height = None::<grid::SizeAxis>;
-
-	//Iterating columns
-	for column in grid::columns_iter::Iter::new(world){
-		let mut column = grid::column::Iter::new(column);
-
-		//Find height (First occurence of a occupied cell)
-		let height = if let Some((y,_)) = column.find(|&(_,cell)| cell.is_occupied()){
-			let height = world_height - y;
-
-			//Count cells vertically blocked
-			for (_,cell) in &mut column{
-				if cell.is_empty(){
-					cells_vertically_blocked_penalty+= height as f32;
-				}
-			}
-
-			height
-		}else{
-			0
-		};
-
-		columns_height_sum+= height;
-
-		if let Some(previous_height) = previous_height{
-			height_bumpiness+= if height > previous_height{height - previous_height}else{previous_height - height}
-		}
-		previous_height = Some(height);
-	}
-
-	(-0.5*columns_height_sum as f32) + (0.25*(rows_completed as f32).powi(2)) + (-0.35*cells_vertically_blocked_penalty as f32) + (-0.3*height_bumpiness.checked_sub(4*2).unwrap_or(0) as f32)
-}
@@ -0,0 +1,234 @@
+//!Evaluation of worlds used by the AI to compare placements
+//!
+//!The evaluation is a weighted sum of features of the world.
+//!The weights can be read from and written to JSON files, for example weights found by the tuner (`tetr-tune`).
+
+use rustc_serialize::json;
+use std::io;
+
+use ::data::grid::{self,RectangularBound};
+use ::data::{Cell,Grid};
+use ::game::data::world::bitboard;
+
+///Weights of the world features in an evaluation.
+///Positive weights reward a feature while negative weights penalize it.
+#[derive(Copy,Clone,Debug,PartialEq,RustcDecodable,RustcEncodable)]
+pub struct Weights{
+	///Sum of the heights of all columns
+	pub height_sum: f32,
+
+	///Height of the highest column
+	pub height_max: f32,
+
+	///Number of completed rows squared
+	pub rows_completed: f32,
+
+	///Number of empty cells below the top of their column
+	pub holes: f32,
+
+	///Number of empty cells below the top of their column, each one multiplied by the height of the column
+	pub holes_weighted: f32,
+
+	///Sum of the height differences between neighbouring columns exceeding `bumpiness_allowance`
+	pub bumpiness: f32,
+
+	///Sum of the height differences between neighbouring columns that is not counted as bumpiness
+	pub bumpiness_allowance: f32,
+
+	///Sum of the depths of all wells, a well being a column lower than both of its neighbours (or the walls)
+	pub wells: f32,
+
+	///Number of horizontal changes between empty and occupied cells, the walls counted as occupied
+	pub row_transitions: f32,
+
+	///Number of vertical changes between empty and occupied cells, the floor counted as occupied
+	pub column_transitions: f32,
+}
+
+impl Default for Weights{
+	fn default() -> Self{Weights{
+		height_sum         : -0.5,
+		height_max         : 0.0,
+		rows_completed     : 0.25,
+		holes              : 0.0,
+		holes_weighted     : -0.35,
+		bumpiness          : -0.3,
+		bumpiness_allowance: 8.0,
+		wells              : 0.0,
+		row_transitions    : 0.0,
+		column_transitions : 0.0,
+	}}
+}
+
+impl Weights{
+	///Reads weights in JSON
+	pub fn read<R>(reader: &mut R) -> Result<Self,Error>
+		where R: io::Read
+	{
+		let mut str = String::new();
+		try!(reader.read_to_string(&mut str).map_err(Error::Io));
+		json::decode(&str).map_err(Error::Decode)
+	}
+
+	///Writes the weights in JSON
+	pub fn write<W>(&self,writer: &mut W) -> Result<(),Error>
+		where W: io::Write
+	{
+		write!(writer,"{}",json::as_pretty_json(self)).map_err(Error::Io)
+	}
+
+	///Evaluation of completing the given number of rows
+	#[inline]
+	pub fn rows_completed(&self,rows: grid::SizeAxis) -> f32{
+		self.rows_completed*(rows as f32).powi(2)
+	}
+}
+
+///Errors that can occur when reading or writing weights
+#[derive(Debug)]
+pub enum Error{
+	Io(io::Error),
+	Decode(json::DecoderError),
+}
+
+///Evaluates the world using the given weights
+///Greater is better
+pub fn evaluate<W>(world: &W,weights: &Weights) -> f32
+	where W: Grid + RectangularBound,
+	      <W as Grid>::Cell: Cell + Copy
+{
+	let world_height = world.height();
+	let rows_completed = grid::rows_iter::Iter::new(world).filter_map(|row| if grid::row::Iter::new(row).all(|(_,cell)| cell.is_occupied()){Some(())}else{None}).count();
+
+	//Iterating rows
+	let mut row_transitions = 0;
+	for row in grid::rows_iter::Iter::new(world){
+		let mut previous_occupied = true;
+		for (_,cell) in grid::row::Iter::new(row){
+			if cell.is_occupied() != previous_occupied{
+				row_transitions+= 1;
+			}
+			previous_occupied = cell.is_occupied();
+		}
+		if !previous_occupied{
+			row_transitions+= 1;
+		}
+	}
+
+	let mut heights = Vec::with_capacity(world.width() as usize);
+	let mut holes = 0;
+	let mut holes_weighted = 0.0;
+	let mut column_transitions = 0;
+
+	//Iterating columns
+	for column in grid::columns_iter::Iter::new(world){
+		let mut column = grid::column::Iter::new(column);
+
+		//Find height (First occurence of a occupied cell)
+		let height = if let Some((y,_)) = column.find(|&(_,cell)| cell.is_occupied()){
+			let height = world_height - y;
+			let mut previous_occupied = true;
+
+			//Count cells vertically blocked
+			for (_,cell) in &mut column{
+				if cell.is_empty(){
+					holes+= 1;
+					holes_weighted+= height as f32;
+				}
+				if cell.is_occupied() != previous_occupied{
+					column_transitions+= 1;
+				}
+				previous_occupied = cell.is_occupied();
+			}
+			if !previous_occupied{
+				column_transitions+= 1;
+			}
+
+			height
+		}else{
+			0
+		};
+
+		heights.push(height);
+	}
+
+	sum(weights,&heights,world_height,rows_completed as grid::SizeAxis,holes,holes_weighted,row_transitions,column_transitions)
+}
+
+///Evaluates the bitboard world using the given weights, giving the same result as `evaluate` but faster
+///Greater is better
+pub fn evaluate_bitboard(world: &bitboard::World,weights: &Weights) -> f32{
+	let world_height = world.height();
+	let full_row = world.full_row();
+	let inner_pairs = full_row >> 1;
+
+	let mut heights = vec![0; world.width() as usize];
+	let mut rows_completed = 0;
+	let mut row_transitions = 0;
+	let mut holes = 0;
+	let mut holes_weighted = 0.0;
+	let mut column_transitions = 0;
+
+	//The columns that have an occupied cell above the current row
+	let mut covered: bitboard::Row = 0;
+	let mut previous_row: bitboard::Row = 0;
+
+	for y in 0..world_height{
+		let row = world.row(y);
+
+		if row == full_row{
+			rows_completed+= 1;
+		}
+
+		//Changes between neighbouring cells and between the walls and the outermost cells
+		row_transitions+= ((row ^ (row >> 1)) & inner_pairs).count_ones();
+		row_transitions+= (!row & 1) as u32 + (!(row >> (world.width()-1)) & 1) as u32;
+
+		//Columns with their highest occupied cell in this row
+		let mut tops = row & !covered;
+		while tops != 0{
+			heights[tops.trailing_zeros() as usize] = world_height - y;
+			tops&= tops - 1;
+		}
+
+		let mut row_holes = covered & !row;
+		holes+= row_holes.count_ones();
+		while row_holes != 0{
+			holes_weighted+= heights[row_holes.trailing_zeros() as usize] as f32;
+			row_holes&= row_holes - 1;
+		}
+
+		column_transitions+= ((row ^ previous_row) & covered).count_ones();
+
+		covered|= row;
+		previous_row = row;
+	}
+
+	//Changes between the lowest cells and the floor
+	column_transitions+= (covered & !previous_row).count_ones();
+
+	sum(weights,&heights,world_height,rows_completed,holes,holes_weighted,row_transitions,column_transitions)
+}
+
+///Weighted sum of the world features
+fn sum(weights: &Weights,heights: &[grid::SizeAxis],world_height: grid::SizeAxis,rows_completed: grid::SizeAxis,holes: u32,holes_weighted: f32,row_transitions: u32,column_transitions: u32) -> f32{
+	let height_sum = heights.iter().fold(0u32,|sum,&height| sum + height as u32);
+	let height_max = heights.iter().cloned().max().unwrap_or(0);
+	let bumpiness = heights.windows(2).fold(0u32,|sum,w| sum + (if w[0] > w[1]{w[0] - w[1]}else{w[1] - w[0]}) as u32) as f32;
+	let wells = (0..heights.len()).fold(0u32,|sum,x|{
+		let left  = if x == 0{world_height}else{heights[x-1]};
+		let right = heights.get(x+1).cloned().unwrap_or(world_height);
+		let neighbour = if left < right{left}else{right};
+		sum + neighbour.saturating_sub(heights[x]) as u32
+	});
+
+	weights.height_sum*(height_sum as f32)
+	+ weights.height_max*(height_max as f32)
+	+ weights.rows_completed(rows_completed)
+	+ weights.holes*(holes as f32)
+	+ weights.holes_weighted*holes_weighted
+	+ weights.bumpiness*(bumpiness - weights.bumpiness_allowance).max(0.0)
+	+ weights.wells*(wells as f32)
+	+ weights.row_transitions*(row_transitions as f32)
+	+ weights.column_transitions*(column_transitions as f32)
+}
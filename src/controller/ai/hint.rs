@@ -0,0 +1,185 @@
+//!Hints for human players, suggesting the placement the AI would choose for their current shape
+//!
+//!A hint is searched for like the placements of the AI (See `bruteforce::search_targets`) and never performs any inputs.
+//!It is searched for again when the shape of the player changes or its world changes.
+//!
+//!Hints constructed with a worker pool (See `pool`) are searched in the pool instead of in `update`, and are shown when the result arrives.
+//!Results of searches started before the hint became outdated again are discarded.
+
+use std::sync;
+use vec_map::VecMap;
+
+use super::bruteforce::{self,Settings};
+use super::pool;
+use ::data::grid;
+use ::data::shapes::tetromino::RotatedShape;
+use ::data::{Cell,Grid};
+use ::game::{self,Event};
+use ::game::data::{world,PlayerId,World,WorldId};
+
+///The hint of a player
+struct Hint{
+	///The suggested placement
+	placement: Option<(RotatedShape,grid::Pos)>,
+
+	///World of the player when the hint was searched for
+	world: Option<WorldId>,
+
+	///Whether the hint has to be searched for again
+	outdated: bool,
+
+	///Incremented every time the hint is searched for, identifying the searches in the worker pool
+	generation: u32,
+
+	///Channel of the results of the searches in the worker pool
+	result_sender: sync::mpsc::Sender<(u32,pool::Targets)>,
+	result_receiver: sync::mpsc::Receiver<(u32,pool::Targets)>,
+}
+
+///Hints of the players that have them enabled
+pub struct Hints{
+	pub settings: Settings,
+	hints: VecMap<Hint>,
+	pool: Option<pool::Pool>,
+}
+
+impl Hints{
+	pub fn new(settings: Settings) -> Self{Hints{
+		settings: settings,
+		hints: VecMap::new(),
+		pool: None,
+	}}
+
+	///Constructs hints that are searched for in the given worker pool
+	pub fn with_pool(settings: Settings,pool: pool::Pool) -> Self{Hints{
+		pool: Some(pool),
+		..Hints::new(settings)
+	}}
+
+	///Returns whether hints are enabled for the given player
+	#[inline]
+	pub fn is_enabled(&self,player_id: PlayerId) -> bool{
+		self.hints.contains_key(player_id as usize)
+	}
+
+	///Enables or disables hints for the given player
+	pub fn set_enabled(&mut self,player_id: PlayerId,enabled: bool){
+		if enabled{
+			self.hints.entry(player_id as usize).or_insert_with(||{
+				let (result_sender,result_receiver) = sync::mpsc::channel();
+				Hint{
+					placement: None,
+					world: None,
+					outdated: true,
+					generation: 0,
+					result_sender: result_sender,
+					result_receiver: result_receiver,
+				}
+			});
+		}else{
+			self.hints.remove(player_id as usize);
+		}
+	}
+
+	///Enables hints for the given player if disabled and vice versa
+	///Returns whether hints are enabled after the toggle
+	pub fn toggle(&mut self,player_id: PlayerId) -> bool{
+		let enabled = !self.is_enabled(player_id);
+		self.set_enabled(player_id,enabled);
+		enabled
+	}
+
+	///Returns the suggested placement of the current shape of the given player, the shape and its position
+	#[inline]
+	pub fn get(&self,player_id: PlayerId) -> Option<(RotatedShape,grid::Pos)>{
+		self.hints.get(player_id as usize).and_then(|hint| hint.placement)
+	}
+
+	///Returns the players with hints enabled and their suggested placements
+	pub fn iter<'s>(&'s self) -> Box<Iterator<Item = (PlayerId,(RotatedShape,grid::Pos))> + 's>{
+		Box::new(self.hints.iter().filter_map(|(player_id,hint)| hint.placement.map(|placement| (player_id as PlayerId,placement))))
+	}
+
+	///Marks every hint as outdated, for example when the game data has been replaced
+	pub fn outdate_all(&mut self){
+		for (_,hint) in self.hints.iter_mut(){
+			hint.outdated = true;
+		}
+	}
+
+	///Searches for the outdated hints, and receives the results of the searches in the worker pool
+	pub fn update<W>(&mut self,game_data: &game::Data<W>)
+		where W: World,
+		      <W as Grid>::Cell: Cell + Copy
+	{
+		for (player_id,hint) in self.hints.iter_mut(){
+			let player = game_data.players.get(player_id);
+
+			if hint.outdated{
+				hint.outdated = false;
+				hint.placement = None;
+				hint.world = None;
+				hint.generation = hint.generation.wrapping_add(1);
+
+				if let Some(player) = player{
+					hint.world = Some(player.world);
+
+					if let Some(&(ref world,false)) = game_data.worlds.get(player.world as usize){
+						let lookahead = player.shapes_lookahead.iter().flat_map(|shapes| shapes.iter().cloned());
+						match (&self.pool,world::bitboard::World::try_from_world(world)){
+							(&Some(ref pool),Some(scratch_world)) => pool.search(pool::Job{
+								world: scratch_world,
+								shape: player.shape,
+								pos: player.pos,
+								lookahead: lookahead.collect(),
+								settings: self.settings,
+								generation: hint.generation,
+								result_sender: hint.result_sender.clone(),
+							}),
+							//Worlds too wide for the search get no hint
+							(&Some(_),None) => (),
+							(&None,_) => hint.placement = bruteforce::search_targets(world,player.shape,player.pos,lookahead,&self.settings).first().map(|&(pos,rotation)|
+								(player.shape.with_rotation(rotation),pos)
+							),
+						}
+					}
+				}
+			}
+
+			//Results of the current search, discarding the ones of previous searches
+			while let Ok((generation,targets)) = hint.result_receiver.try_recv(){
+				if generation == hint.generation{
+					hint.placement = player.and_then(|player| targets.first().map(|&(pos,rotation)| (player.shape.with_rotation(rotation),pos)));
+				}
+			}
+		}
+	}
+
+	///Marks the hints affected by the event as outdated
+	pub fn event(&mut self,event: &Event<(PlayerId,WorldId),WorldId>){
+		use game::Event::*;
+
+		match event{
+			&PlayerAdded{player: (player_id,_),..}        |
+			&PlayerRemoved{player: (player_id,_),..}      |
+			&PlayerMovedWorld{player: (player_id,_),..}   |
+			&PlayerChangedShape{player: (player_id,_),..} => {
+				if let Some(hint) = self.hints.get_mut(player_id as usize){
+					hint.outdated = true;
+				}
+			},
+
+			&WorldImprintedShape{world: world_id,..} |
+			&WorldUpdated{world: world_id}           |
+			&WorldRemoved{world: world_id}           |
+			&WorldPaused{world: world_id}            |
+			&WorldUnpaused{world: world_id}          => {
+				for (_,hint) in self.hints.iter_mut().filter(|&(_,ref hint)| hint.world == Some(world_id)){
+					hint.outdated = true;
+				}
+			},
+
+			_ => ()
+		}
+	}
+}
@@ -2,3 +2,7 @@
 
 pub mod bounce;
 pub mod bruteforce;
+pub mod evaluation;
+pub mod hint;
+pub mod perfect_clear;
+pub mod pool;
@@ -0,0 +1,135 @@
+//!Search for perfect clears, sequences of placements leaving the world without any occupied cells
+//!
+//!A depth first search over the reachable placements (See `game::pathfinder`) of the current shape and the shapes in the preview queue, in their order.
+//!The occupied cells have to fit in the lowest rows of the world, at most `Settings::max_height` of them, and the search only continues with placements keeping every occupied cell inside these rows.
+//!Worlds already searched are pruned.
+//!Empty regions are not pruned by their sizes, because the regions separated by a row merge when the row is cleared later in the sequence, letting a shape fill cells of both.
+//!The hold piece is not considered because holding is not yet supported by the game.
+
+use core::cmp;
+use std::collections::HashSet;
+use std::time::Instant;
+
+use super::bruteforce;
+use ::data::grid::{self,RectangularBound};
+use ::data::shapes::tetromino::{RotatedShape,Shape};
+use ::data::{Cell,Grid};
+use ::game::pathfinder;
+use ::game::data::World;
+use ::game::data::world::bitboard;
+
+#[derive(Copy,Clone,Debug,PartialEq)]
+pub struct Settings{
+	///Maximum number of rows to clear
+	pub max_height: grid::SizeAxis,
+
+	///Time budget of a search. When exceeded, the search gives up.
+	///Unit: seconds
+	pub search_time: f64,
+}
+
+impl Default for Settings{
+	fn default() -> Self{Settings{
+		max_height : 4,
+		search_time: 0.05,
+	}}
+}
+
+///Searches for a sequence of placements of the given shape at the given position and the shapes following it that leaves the world empty.
+///Every shape after the current one is assumed to start at the top of the world at the same horizontal position.
+///Returns the placements in order, or None if there is no perfect clear using the given shapes, the time budget was exceeded or the world is wider than `bitboard::WIDTH_MAX`.
+pub fn search<W,I>(world: &W,shape: RotatedShape,pos: grid::Pos,queue: I,settings: &Settings) -> Option<Vec<pathfinder::Placement>>
+	where W: World,
+	      <W as Grid>::Cell: Cell + Copy,
+	      I: IntoIterator<Item = Shape>
+{
+	let start_time = Instant::now();
+	let shapes: Vec<RotatedShape> = Some(shape).into_iter().chain(queue.into_iter().map(RotatedShape::new)).collect();
+	let world = match bitboard::World::try_from_world(world){
+		Some(world) => world,
+		None => return None,
+	};
+
+	let occupied_cells = (0..world.height()).fold(0,|sum,y| sum + world.row(y).count_ones());
+	let stack_height = (0..world.height()).position(|y| world.row(y) != 0).map_or(0,|y| world.height() - y as grid::SizeAxis);
+
+	//Try the lowest number of rows first. The empty cells in the rows have to be filled by whole shapes.
+	for height in cmp::max(stack_height,1) .. cmp::min(settings.max_height,world.height())+1{
+		let empty_cells = height as u32 * world.width() as u32 - occupied_cells;
+		if empty_cells % 4 != 0 || (empty_cells / 4) as usize > shapes.len(){
+			continue;
+		}
+
+		let mut search = Search{
+			shapes: &shapes,
+			start: pos,
+			start_time: start_time,
+			settings: settings,
+			searched: HashSet::new(),
+			placements: Vec::new(),
+		};
+		match search.search(world.clone(),height,0){
+			Some(true)  => return Some(search.placements),
+			Some(false) => (),
+			None        => return None,
+		}
+	}
+
+	None
+}
+
+struct Search<'l>{
+	shapes: &'l [RotatedShape],
+	start: grid::Pos,
+	start_time: Instant,
+	settings: &'l Settings,
+
+	///Worlds already searched, together with the index of the next shape
+	searched: HashSet<(Vec<bitboard::Row>,usize)>,
+
+	///Placements leading to the current world
+	placements: Vec<pathfinder::Placement>,
+}
+
+impl<'l> Search<'l>{
+	///Searches for a perfect clear in the given world, whose occupied cells are in the lowest `height` rows, starting with the shape at the given index.
+	///Returns whether a perfect clear was found, or None if the time budget was exceeded.
+	fn search(&mut self,world: bitboard::World,height: grid::SizeAxis,index: usize) -> Option<bool>{
+		if height == 0{
+			return Some(true);
+		}
+		if index >= self.shapes.len(){
+			return Some(false);
+		}
+
+		let elapsed = self.start_time.elapsed();
+		if elapsed.as_secs() as f64 + elapsed.subsec_nanos() as f64 * 1e-9 > self.settings.search_time{
+			return None;
+		}
+
+		let key = ((world.height()-height .. world.height()).map(|y| world.row(y)).collect(),index);
+		if !self.searched.insert(key){
+			return Some(false);
+		}
+
+		let pos = if index==0{self.start}else{self.start.with_y(0)};
+		for placement in pathfinder::placements(&world,self.shapes[index],pos){
+			let mut next = world.clone();
+			let full_rows = bruteforce::imprint(&mut next,&placement.shape,placement.pos);
+			let next_height = height.saturating_sub(full_rows);
+
+			//Every occupied cell has to stay in the rows to clear
+			if (0 .. next.height()-next_height).any(|y| next.row(y) != 0){
+				continue;
+			}
+
+			self.placements.push(placement);
+			match self.search(next,next_height,index+1){
+				Some(false) => {self.placements.pop();},
+				result      => return result,
+			}
+		}
+
+		Some(false)
+	}
+}
@@ -0,0 +1,91 @@
+//!A pool of worker threads searching placements for AI controllers
+//!
+//!Searches are sent to the pool as jobs and performed by the first idle worker, so that the searches of multiple controllers are done in parallel and never block the main loop.
+//!The result of a job is sent back through the channel given with the job, together with the generation of the job so that the controller can discard results that are no longer relevant.
+//!The workers stop when every handle to the pool is dropped.
+
+use std::{sync,thread};
+
+use super::bruteforce::{self,Settings};
+use ::data::grid;
+use ::data::shapes::tetromino::{RotatedShape,Rotation,Shape};
+use ::game::data::world::bitboard;
+
+///Placements in the order of preference, the best one first. See `bruteforce::search_targets`.
+pub type Targets = Vec<(grid::Pos,Rotation)>;
+
+///A search to be performed by the pool
+pub struct Job{
+	///Copy of the world to search in
+	pub world: bitboard::World,
+
+	///The shape to place
+	pub shape: RotatedShape,
+
+	///Current position of the shape
+	pub pos: grid::Pos,
+
+	///The shapes following the current one
+	pub lookahead: Vec<Shape>,
+
+	pub settings: Settings,
+
+	///Identifier of the job, sent back together with the result
+	pub generation: u32,
+
+	///Where the generation and the result is sent to when the search is complete
+	pub result_sender: sync::mpsc::Sender<(u32,Targets)>,
+}
+
+///Handle to a pool of worker threads
+#[derive(Clone)]
+pub struct Pool{
+	job_sender: sync::mpsc::Sender<Job>,
+}
+
+impl Pool{
+	///Starts a pool with the given number of worker threads
+	///Requirements:
+	///    threads > 0
+	pub fn new(threads: usize) -> Self{
+		let (job_sender,job_receiver) = sync::mpsc::channel::<Job>();
+		let job_receiver = sync::Arc::new(sync::Mutex::new(job_receiver));
+
+		for _ in 0..threads{
+			let job_receiver = job_receiver.clone();
+			thread::spawn(move ||{
+				loop{
+					let job = match job_receiver.lock(){
+						Ok(receiver) => receiver.recv(),
+						Err(_)       => break
+					};
+					match job{
+						Ok(job) => {
+							let targets = bruteforce::search_targets(&job.world,job.shape,job.pos,job.lookahead,&job.settings);
+
+							//The controller not waiting for the result anymore is not an error
+							let _ = job.result_sender.send((job.generation,targets));
+						},
+
+						//Every handle to the pool is dropped
+						Err(_) => break
+					}
+				}
+			});
+		}
+
+		Pool{job_sender: job_sender}
+	}
+
+	///Constructs a pool without worker threads, returning the receiver of its jobs.
+	///The searches are to be performed by the owner of the receiver (e.g. on demand in tests).
+	pub fn manual() -> (Self,sync::mpsc::Receiver<Job>){
+		let (job_sender,job_receiver) = sync::mpsc::channel();
+		(Pool{job_sender: job_sender},job_receiver)
+	}
+
+	///Queues a search
+	pub fn search(&self,job: Job){
+		let _ = self.job_sender.send(job);
+	}
+}
@@ -0,0 +1,383 @@
+//!A controller delegating the decisions to an external bot process
+//!
+//!The bot is a child process communicating through its standard input and output, one JSON object per line.
+//!The protocol is modelled on the community Tetris Bot Protocol, every message having a `type` field.
+//!
+//!Bot to game:
+//!  `{"type": "info", "name": NAME, "version": VERSION, "author": AUTHOR}`: Sent once when started, before anything else.
+//!  `{"type": "placement", "x": X, "y": Y, "rotation": ROTATION}`: Reply to `start`. Moves the shape to the given placement by the shortest path (See `game::pathfinder`).
+//!  `{"type": "inputs", "inputs": [INPUT, ...]}`: Reply to `start`. Performs the given inputs in order. Inputs are the names of the variants of `game::data::Input` (e.g. "MoveLeft").
+//!  `{"type": "error", "message": MESSAGE}`: Reports an error in the bot, stopping the controller.
+//!
+//!Game to bot:
+//!  `{"type": "start", "board": [[CELL, ...], ...], "current": SHAPE, "x": X, "y": Y, "rotation": ROTATION, "queue": [SHAPE, ...], "hold": null}`: Sent for every new shape.
+//!    The board is the rows of the world from the top, each cell being null when empty and "G" when occupied.
+//!    Shapes are the names of the variants of `data::shapes::tetromino::Shape` (e.g. "T").
+//!    The hold is always null because holding is not yet supported by the game.
+//!  `{"type": "error", "message": MESSAGE}`: Sent when the placement replied by the bot cannot be reached. The shape is not moved any further, and the next shape is sent as usual.
+//!  `{"type": "quit"}`: Sent when the controller is dropped.
+
+use rustc_serialize::json::Json;
+use std::collections::{BTreeMap,VecDeque};
+use std::io::{self,BufRead,Write};
+use std::time::Instant;
+use std::{process,sync,thread};
+
+use super::Controller as ControllerTrait;
+use ::data::grid;
+use ::data::shapes::tetromino::{Rotation,RotatedShape,Shape};
+use ::data::{Cell,Grid};
+use ::game::{self,pathfinder,Event,Request};
+use ::game::data::{Input,Player,PlayerId,World,WorldId};
+
+pub struct Controller{
+	pub request_sender: sync::mpsc::Sender<Request<PlayerId,WorldId>>,
+	pub player_id: game::data::PlayerId,
+	pub settings: Settings,
+	process: process::Child,
+	stdin: process::ChildStdin,
+	messages: sync::mpsc::Receiver<io::Result<String>>,
+	state: State,
+	info: Option<Info>,
+	new_shape: bool,
+	input_time_count: f64,
+	error: Option<Error>,
+}
+
+#[derive(Copy,Clone,Debug,PartialEq)]
+pub struct Settings{
+	///Time between every input
+	///Unit: seconds
+	pub input_time: f64,
+
+	///Maximum time to wait for the `info` message after the bot is started.
+	///The bot times out on the first update without the message when zero.
+	///Unit: seconds
+	pub info_timeout: f64,
+
+	///Maximum time to wait for a reply to a `start` message
+	///Unit: seconds
+	pub move_timeout: f64,
+}
+
+impl Default for Settings{
+	fn default() -> Self{Settings{
+		input_time  : 0.05,
+		info_timeout: 5.0,
+		move_timeout: 1.0,
+	}}
+}
+
+///Information about the bot
+#[derive(Clone,Debug,PartialEq)]
+pub struct Info{
+	pub name: String,
+	pub version: String,
+	pub author: String,
+}
+
+///What the controller is doing
+enum State{
+	///Waiting for the `info` message since the given time
+	Starting(Instant),
+
+	///Waiting for a new shape
+	Idle,
+
+	///Waiting for a reply to the `start` message sent at the given time for the given shape
+	Thinking(Instant,RotatedShape),
+
+	///Moving the shape to a placement
+	Placing(grid::Pos,Rotation),
+
+	///Performing inputs
+	Performing(VecDeque<Input>),
+}
+
+impl State{
+	#[inline]fn is_starting(&self) -> bool{if let &State::Starting(_) = self{true}else{false}}
+	#[inline]fn is_thinking(&self) -> bool{if let &State::Thinking(..) = self{true}else{false}}
+}
+
+///Errors that can occur when communicating with the bot
+#[derive(Debug)]
+pub enum Error{
+	///Unable to start the bot process
+	Spawn(io::Error),
+
+	///Unable to read from or write to the bot
+	Io(io::Error),
+
+	///The bot exited
+	Exited,
+
+	///The bot did not reply in time
+	Timeout,
+
+	///The bot sent a message not following the protocol
+	InvalidMessage(String),
+
+	///The bot reported an error
+	Bot(String),
+}
+
+impl Controller{
+	///Starts the bot process by the given command and constructs a controller for the given player communicating with it
+	pub fn new(request_sender: sync::mpsc::Sender<Request<PlayerId,WorldId>>,player_id: game::data::PlayerId,command: &mut process::Command,settings: Settings) -> Result<Self,Error>{
+		let mut process = try!(command
+			.stdin(process::Stdio::piped())
+			.stdout(process::Stdio::piped())
+			.spawn()
+			.map_err(Error::Spawn)
+		);
+		let stdin = process.stdin.take().unwrap();
+		let stdout = process.stdout.take().unwrap();
+
+		//Read the lines in a separate thread, making it possible to wait for messages without blocking
+		let (message_sender,message_receiver) = sync::mpsc::channel();
+		thread::spawn(move ||{
+			for line in io::BufReader::new(stdout).lines(){
+				if message_sender.send(line).is_err(){
+					break;
+				}
+			}
+		});
+
+		Ok(Controller{
+			request_sender  : request_sender,
+			player_id       : player_id,
+			settings        : settings,
+			process         : process,
+			stdin           : stdin,
+			messages        : message_receiver,
+			state           : State::Starting(Instant::now()),
+			info            : None,
+			new_shape       : true,
+			input_time_count: 0.0,
+			error           : None,
+		})
+	}
+
+	///Returns the information about the bot, if received
+	#[inline(always)]pub fn info(&self) -> Option<&Info>{self.info.as_ref()}
+
+	///Returns the error that stopped the controller, if any
+	#[inline(always)]pub fn error(&self) -> Option<&Error>{self.error.as_ref()}
+
+	///Stops the controller because of the given error, reporting it on the standard error stream
+	fn fail(&mut self,error: Error){
+		let _ = writeln!(io::stderr(),"External bot (Player {}): {:?}",self.player_id,error);
+		self.error = Some(error);
+		let _ = self.process.kill();
+	}
+
+	///Sends a message to the bot
+	fn send(&mut self,message: Json) -> Result<(),Error>{
+		writeln!(self.stdin,"{}",message).and_then(|_| self.stdin.flush()).map_err(Error::Io)
+	}
+
+	///Handles a message from the bot
+	fn receive(&mut self,line: &str) -> Result<(),Error>{
+		let message = try!(Json::from_str(line).map_err(|_| Error::InvalidMessage(line.to_owned())));
+		let invalid = || Error::InvalidMessage(line.to_owned());
+
+		match message.find("type").and_then(Json::as_string){
+			Some("info") if self.state.is_starting() => {
+				let field = |name: &str| message.find(name).and_then(Json::as_string).map_or_else(String::new,|str| str.to_owned());
+				self.info = Some(Info{
+					name   : field("name"),
+					version: field("version"),
+					author : field("author"),
+				});
+				self.state = State::Idle;
+			},
+			Some("placement") => if let State::Thinking(_,shape) = self.state{
+				let field = |name: &str| message.find(name).and_then(Json::as_i64);
+				match (field("x"),field("y"),field("rotation")){
+					(Some(x),Some(y),Some(rotation)) if rotation >= 0 && rotation < shape.rotation_count() as i64 => {
+						self.state = State::Placing(grid::Pos{x: x as grid::PosAxis,y: y as grid::PosAxis},rotation as Rotation);
+					},
+					_ => return Err(invalid())
+				}
+			}else{
+				return Err(invalid());
+			},
+			Some("inputs") if self.state.is_thinking() => {
+				let inputs = try!(message.find("inputs").and_then(Json::as_array).ok_or_else(&invalid));
+				let inputs = try!(inputs.iter().map(|input| input.as_string().and_then(input_from_str)).collect::<Option<VecDeque<_>>>().ok_or_else(&invalid));
+				self.state = State::Performing(inputs);
+			},
+			Some("error") => {
+				return Err(Error::Bot(message.find("message").and_then(Json::as_string).unwrap_or("").to_owned()));
+			},
+			_ => return Err(invalid())
+		}
+
+		Ok(())
+	}
+
+	///Sends the `start` message for the player's current shape
+	fn start<W>(&mut self,player: &Player,world: &W) -> Result<(),Error>
+		where W: World,
+		      <W as Grid>::Cell: Cell + Copy
+	{
+		let board = grid::rows_iter::Iter::new(world).map(|row|
+			Json::Array(grid::row::Iter::new(row).map(|(_,cell)| if cell.is_occupied(){Json::String("G".to_owned())}else{Json::Null}).collect())
+		).collect();
+		let queue = player.shapes_lookahead.iter().flat_map(|shapes| shapes.iter()).map(|&shape| Json::String(shape_to_string(shape))).collect();
+
+		let mut message = BTreeMap::new();
+		message.insert("type".to_owned()    ,Json::String("start".to_owned()));
+		message.insert("board".to_owned()   ,Json::Array(board));
+		message.insert("current".to_owned() ,Json::String(shape_to_string(player.shape.shape())));
+		message.insert("x".to_owned()       ,Json::I64(player.pos.x as i64));
+		message.insert("y".to_owned()       ,Json::I64(player.pos.y as i64));
+		message.insert("rotation".to_owned(),Json::U64(player.shape.rotation() as u64));
+		message.insert("queue".to_owned()   ,Json::Array(queue));
+		message.insert("hold".to_owned()    ,Json::Null);
+		self.send(Json::Object(message))
+	}
+
+	///Returns the next input to perform, if any.
+	///An unreachable placement is reported on the standard error stream and to the bot, and has no inputs.
+	fn next_input<W>(&mut self,player: &Player,world: &W) -> Result<Option<Input>,Error>
+		where W: World
+	{
+		Ok(match self.state{
+			State::Placing(pos,rotation) => match pathfinder::path(world,player.shape,player.pos,pos,rotation){
+				Some(inputs) => inputs.first().cloned(),
+				None => {
+					let message = format!("Unreachable placement: x: {}, y: {}, rotation: {}",pos.x,pos.y,rotation);
+					let _ = writeln!(io::stderr(),"External bot (Player {}): {}",self.player_id,message);
+
+					let mut reply = BTreeMap::new();
+					reply.insert("type".to_owned()   ,Json::String("error".to_owned()));
+					reply.insert("message".to_owned(),Json::String(message));
+					try!(self.send(Json::Object(reply)));
+					None
+				}
+			},
+			State::Performing(ref mut inputs) => inputs.pop_front(),
+			_ => None
+		})
+	}
+
+	///Advances the controller, returning an error when the communication fails
+	fn advance<W>(&mut self,dt: f64,game_data: &game::Data<W>) -> Result<(),Error>
+		where W: World,
+		      <W as Grid>::Cell: Cell + Copy
+	{
+		//Messages
+		loop{
+			match self.messages.try_recv(){
+				Ok(Ok(line))  => try!(self.receive(&line)),
+				Ok(Err(e))    => return Err(Error::Io(e)),
+				Err(sync::mpsc::TryRecvError::Empty)        => break,
+				Err(sync::mpsc::TryRecvError::Disconnected) => return Err(Error::Exited),
+			}
+		}
+
+		//Timeouts
+		match self.state{
+			State::Starting(time) if elapsed(time) >= self.settings.info_timeout => return Err(Error::Timeout),
+			State::Thinking(time,_) if elapsed(time) >= self.settings.move_timeout => return Err(Error::Timeout),
+			_ => ()
+		}
+
+		if let Some(player) = game_data.players.get(self.player_id as usize){
+			if let Some(&(ref world,false)) = game_data.worlds.get(player.world as usize){
+				match self.state{
+					State::Starting(_) | State::Thinking(..) => (),
+
+					//New shape
+					State::Idle => if self.new_shape{
+						self.new_shape = false;
+						try!(self.start(player,world));
+						self.state = State::Thinking(Instant::now(),player.shape);
+					},
+
+					//Perform the inputs
+					State::Placing(..) | State::Performing(_) => {
+						self.input_time_count-= dt;
+						if self.input_time_count <= 0.0{
+							match try!(self.next_input(player,world)){
+								Some(input) => {
+									let _ = self.request_sender.send(Request::PlayerInput{input: input,player: self.player_id});
+									self.input_time_count = self.settings.input_time;
+								},
+								None => self.state = State::Idle
+							}
+						}
+					},
+				}
+			}
+		}
+
+		Ok(())
+	}
+}
+
+impl<W> ControllerTrait<W,Event<(PlayerId,WorldId),WorldId>> for Controller
+	where W: World,
+	      <W as Grid>::Cell: Cell + Copy
+{
+	fn update(&mut self,dt: f64,game_data: &game::Data<W>){
+		if self.error.is_none(){
+			if let Err(e) = self.advance(dt,game_data){
+				self.fail(e);
+			}
+		}
+	}
+
+	fn event(&mut self,event: &Event<(game::data::PlayerId,game::data::WorldId),game::data::WorldId>){
+		use game::Event::*;
+
+		match event{
+			&PlayerAdded{player: (player_id,_),..} |
+			&PlayerChangedShape{player: (player_id,_),..} if player_id == self.player_id => {
+				self.new_shape = true;
+
+				//Stop moving the previous shape
+				match self.state{
+					State::Placing(..) | State::Performing(_) => self.state = State::Idle,
+					_ => ()
+				}
+			},
+			_ => ()
+		}
+	}
+}
+
+impl Drop for Controller{
+	fn drop(&mut self){
+		if self.error.is_none(){
+			let mut message = BTreeMap::new();
+			message.insert("type".to_owned(),Json::String("quit".to_owned()));
+			let _ = self.send(Json::Object(message));
+		}
+		let _ = self.process.kill();
+		let _ = self.process.wait();
+	}
+}
+
+///Seconds elapsed since the given time
+fn elapsed(time: Instant) -> f64{
+	let elapsed = time.elapsed();
+	elapsed.as_secs() as f64 + elapsed.subsec_nanos() as f64 * 1e-9
+}
+
+fn shape_to_string(shape: Shape) -> String{
+	format!("{:?}",shape)
+}
+
+fn input_from_str(str: &str) -> Option<Input>{
+	Some(match str{
+		"MoveLeft"            => Input::MoveLeft,
+		"MoveRight"           => Input::MoveRight,
+		"SlowFall"            => Input::SlowFall,
+		"FastFall"            => Input::FastFall,
+		"RotateClockwise"     => Input::RotateClockwise,
+		"RotateAntiClockwise" => Input::RotateAntiClockwise,
+		_ => return None
+	})
+}
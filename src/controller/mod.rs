@@ -1,15 +1,15 @@
 pub mod ai;
+pub mod external;
+pub mod registry;
 
 
 
-use piston::input::UpdateArgs;
-
 use ::game;
 
 ///Controls a player and its world with inputs
 pub trait Controller<World,Event>{
-	///Called for each update step
-	fn update(&mut self,args: &UpdateArgs,game_data: &game::Data<World>);
+	///Called for each update step with the time passed since the last one (Unit: seconds)
+	fn update(&mut self,dt: f64,game_data: &game::Data<World>);
 
 	///Event listener. Called for each defined ingame event occcurring
 	fn event<'l>(&mut self,event: &Event);
@@ -0,0 +1,194 @@
+//!Bindings of players to what controls them
+//!
+//!Every player in the game state has a binding, created when the player is added and removed when the player is removed.
+//!Players without a prepared binding are controlled by a human.
+//!A binding can be prepared before the player is added, and swapped at any time.
+//!Only the players controlled by the network are controlled by the requests received over the network (See `Registry::accepts_network_request`).
+//!
+//!Controllers only receive the events relevant to their player: the events of the player, the events of the world the player is in and the events of the whole game.
+
+use vec_map::VecMap;
+
+use super::Controller as ControllerTrait;
+use ::game::{self,Event,Request};
+use ::game::data::{PlayerId,WorldId};
+
+///A controller of a player
+pub type BoxedController<W> = Box<ControllerTrait<W,Event<(PlayerId,WorldId),WorldId>>>;
+
+///What controls a player
+pub enum Control<W>{
+	///Controlled locally by inputs from a human, for example by keys
+	Human,
+
+	///Controlled by a remote peer, performing the inputs received over the network
+	Network,
+
+	///Controlled by a controller, for example an AI
+	Controller(BoxedController<W>),
+}
+
+impl<W> Control<W>{
+	#[inline]
+	pub fn is_human(&self) -> bool{
+		if let &Control::Human = self{true}else{false}
+	}
+
+	#[inline]
+	pub fn is_network(&self) -> bool{
+		if let &Control::Network = self{true}else{false}
+	}
+}
+
+struct Binding<W>{
+	///The world the player is in, or None when the player does not exist yet
+	world: Option<WorldId>,
+
+	control: Control<W>,
+}
+
+///Controls of the players, keyed by their ids
+pub struct Registry<W>{
+	bindings: VecMap<Binding<W>>,
+}
+
+impl<W> Registry<W>{
+	pub fn new() -> Self{Registry{
+		bindings: VecMap::new(),
+	}}
+
+	///Returns the control of the given player
+	#[inline]
+	pub fn get(&self,player_id: PlayerId) -> Option<&Control<W>>{
+		self.bindings.get(player_id as usize).map(|binding| &binding.control)
+	}
+
+	///Returns whether the given player is controlled by a human
+	#[inline]
+	pub fn is_human(&self,player_id: PlayerId) -> bool{
+		self.get(player_id).map_or(false,Control::is_human)
+	}
+
+	///Returns whether the given player is controlled by the network
+	#[inline]
+	pub fn is_network(&self,player_id: PlayerId) -> bool{
+		self.get(player_id).map_or(false,Control::is_network)
+	}
+
+	///Returns whether a request received over the network is to be performed.
+	///A remote peer may add players, and may only control, remove or move the players controlled by the network, so that it cannot control the other players.
+	///Requests for the worlds or the whole game are never performed.
+	pub fn accepts_network_request(&self,request: &Request<PlayerId,WorldId>) -> bool{
+		match request{
+			&Request::PlayerInput{player: player_id,..}     |
+			&Request::PlayerRemove{player: player_id}       |
+			&Request::PlayerMoveWorld{player: player_id,..} => self.is_network(player_id),
+
+			&Request::PlayerAdd{..} => true,
+
+			&Request::WorldRemove{..}  |
+			&Request::WorldRestart{..} |
+			&Request::WorldPause{..}   |
+			&Request::WorldUnpause{..} |
+			&Request::GamePause        |
+			&Request::GameUnpause      |
+			&Request::GameRestart      |
+			&Request::GameQuit         => false,
+		}
+	}
+
+	///Binds the given player to the given control, replacing its current control.
+	///When the player already exists, a new controller is signaled a `PlayerAdded` event so that it can start controlling the player.
+	///Returns the replaced control.
+	pub fn set(&mut self,player_id: PlayerId,mut control: Control<W>) -> Option<Control<W>>{
+		let world = self.bindings.get(player_id as usize).and_then(|binding| binding.world);
+
+		if let (Some(world_id),&mut Control::Controller(ref mut controller)) = (world,&mut control){
+			controller.event(&Event::PlayerAdded{player: (player_id,world_id)});
+		}
+
+		self.bindings.insert(player_id as usize,Binding{
+			world: world,
+			control: control,
+		}).map(|binding| binding.control)
+	}
+
+	///Updates every controller
+	pub fn update(&mut self,dt: f64,game_data: &game::Data<W>){
+		for (_,binding) in self.bindings.iter_mut(){
+			if let Control::Controller(ref mut controller) = binding.control{
+				controller.update(dt,game_data);
+			}
+		}
+	}
+
+	///Keeps the bindings in sync with the players and signals the event to the controllers it is relevant to
+	pub fn event(&mut self,event: &Event<(PlayerId,WorldId),WorldId>){
+		use game::Event::*;
+
+		match event{
+			&PlayerAdded{player: (player_id,world_id)} => {
+				let binding = self.bindings.entry(player_id as usize).or_insert(Binding{
+					world: None,
+					control: Control::Human,
+				});
+				binding.world = Some(world_id);
+			},
+			&PlayerMovedWorld{player: (player_id,_),new: world_id,..} => {
+				if let Some(binding) = self.bindings.get_mut(player_id as usize){
+					binding.world = Some(world_id);
+				}
+			},
+			_ => ()
+		}
+
+		match event{
+			//Player events
+			&PlayerAdded{player: (player_id,_),..}              |
+			&PlayerRemoved{player: (player_id,_),..}            |
+			&PlayerMovedWorld{player: (player_id,_),..}         |
+			&PlayerCollidedOnRotation{player: (player_id,_),..} |
+			&PlayerCollidedOnMovement{player: (player_id,_),..} |
+			&PlayerRotated{player: (player_id,_),..}            |
+			&PlayerMoved{player: (player_id,_),..}              |
+			&PlayerChangedShape{player: (player_id,_),..}       |
+			&PlayerFinesseFault{player: (player_id,_),..}       => {
+				if let Some(&mut Binding{control: Control::Controller(ref mut controller),..}) = self.bindings.get_mut(player_id as usize){
+					controller.event(event);
+				}
+			},
+
+			//World events
+			&WorldImprintedShape{world: world_id,..} |
+			&WorldAdded{world: world_id}             |
+			&WorldUpdated{world: world_id}           |
+			&WorldRemoved{world: world_id}           |
+			&WorldPaused{world: world_id}            |
+			&WorldUnpaused{world: world_id}          => {
+				for (_,binding) in self.bindings.iter_mut(){
+					if binding.world == Some(world_id){
+						if let Control::Controller(ref mut controller) = binding.control{
+							controller.event(event);
+						}
+					}
+				}
+			},
+
+			//Game events
+			&GamePaused   |
+			&GameUnpaused |
+			&GameQuit     => {
+				for (_,binding) in self.bindings.iter_mut(){
+					if let Control::Controller(ref mut controller) = binding.control{
+						controller.event(event);
+					}
+				}
+			},
+		}
+
+		//The binding of a removed player is removed after the controller has been signaled
+		if let &PlayerRemoved{player: (player_id,_),..} = event{
+			self.bindings.remove(player_id as usize);
+		}
+	}
+}
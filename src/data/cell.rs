@@ -12,7 +12,7 @@ pub trait Cell: Sized{
 	fn is_empty(self) -> bool{!self.is_occupied()}
 }
 
-#[derive(Clone,Copy,Eq,PartialEq)]
+#[derive(Clone,Copy,Eq,PartialEq,Serialize,Deserialize)]
 pub struct ShapeCell(pub Option<Shape>);
 
 impl Cell for ShapeCell{
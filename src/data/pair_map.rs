@@ -1,5 +1,5 @@
 use std::hash::Hash;
-use std::collections::HashMap;
+use std::collections::hash_map::{self,HashMap};
 
 ///A naive and probably inefficient implementation of a bijective map (list of pairs/bidirectional map/bag) using two HashMaps
 pub struct PairMap<A: Copy + Hash,B: Copy + Hash>(HashMap<A,B>,HashMap<B,A>);
@@ -29,14 +29,12 @@ impl<A,B> /*Map<A,B> for*/ PairMap<A,B>
 	pub fn get(&self,key: A) -> Option<B>{self.0.get(&key).map(|&v| v)}
 	pub fn get_mut(&mut self,key: A) -> Option<&mut B>{self.0.get_mut(&key)}
 	pub fn insert(&mut self,key: A,value: B) -> Option<B>{
-		match self.0.insert(key,value){
-			Some(old_value) => {
-				self.1.remove(&old_value);
-				self.1.insert(value,key);
-				Some(old_value)
-			},
-			None => None
+		let old_value = self.0.insert(key,value);
+		if let Some(ref old_value) = old_value{
+			self.1.remove(old_value);
 		}
+		self.1.insert(value,key);
+		old_value
 	}
 	pub fn remove(&mut self,key: A) -> Option<B>{
 		if let Some(value) = self.0.remove(&key){
@@ -56,6 +54,9 @@ impl<A,B> /*Map<A,B> for*/ PairMap<A,B>
 	pub fn len(&self) -> usize{
 		self.0.len()
 	}
+	pub fn iter(&self) -> hash_map::Iter<A,B>{
+		self.0.iter()
+	}
 /*}
 
 impl<A,B> Map<B,A> for PairMap<A,B>
@@ -66,14 +67,12 @@ impl<A,B> Map<B,A> for PairMap<A,B>
 	pub fn get2(&self,key: B) -> Option<A>{self.1.get(&key).map(|&v| v)}
 	pub fn get_mut2(&mut self,key: B) -> Option<&mut A>{self.1.get_mut(&key)}
 	pub fn insert2(&mut self,key: B,value: A) -> Option<A>{
-		match self.1.insert(key,value){
-			Some(old_value) => {
-				self.0.remove(&old_value);
-				self.0.insert(value,key);
-				Some(old_value)
-			},
-			None => None
+		let old_value = self.1.insert(key,value);
+		if let Some(ref old_value) = old_value{
+			self.0.remove(old_value);
 		}
+		self.0.insert(value,key);
+		old_value
 	}
 	pub fn remove2(&mut self,key: B) -> Option<A>{
 		if let Some(value) = self.1.remove(&key){
@@ -0,0 +1,279 @@
+//!Checksums of game data
+//!
+//!Used for verifying that two game states are equal without comparing them directly, for example when detecting desynchronization in replays or online games.
+//!The checksums are cheap to calculate and stable between runs and platforms.
+//!
+//!`checksum` calculates a single checksum of the whole game data.
+//!When two of them mismatch, `Checksums` can be used to find out which worlds and which parts of the players that differ.
+
+use core::{fmt,mem};
+use core::hash::Hasher as HasherTrait;
+
+use ::data::{Cell,Grid};
+use ::data::grid::cells_iter;
+use ::game::Data;
+use ::game::data::{Player,PlayerId,World,WorldId};
+
+///Type of a checksum
+pub type Checksum = u64;
+
+///Calculates the checksum of the game data.
+///Equal to `Checksums::new(data).checksum()`, but without any allocations.
+pub fn checksum<W>(data: &Data<W>) -> Checksum
+	where W: World,
+	      <W as Grid>::Cell: Cell + Copy
+{
+	let mut hasher = Hasher::new();
+
+	for (world_id,&(ref world,paused)) in data.worlds.iter(){
+		hasher.write_u8(world_id as WorldId);
+		hasher.write_u64(world_checksum(world,paused));
+	}
+
+	for (player_id,player) in data.players.iter(){
+		hasher.write_u8(player_id as PlayerId);
+		PlayerChecksums::new(player).write_to(&mut hasher);
+	}
+
+	hasher.finish()
+}
+
+///Calculates the checksum of a world from its dimensions, the occupation of its cells and its pause state
+pub fn world_checksum<W>(world: &W,paused: bool) -> Checksum
+	where W: World,
+	      <W as Grid>::Cell: Cell + Copy
+{
+	let mut hasher = Hasher::new();
+	hasher.write_u8(world.width());
+	hasher.write_u8(world.height());
+	hasher.write_u8(paused as u8);
+	for (_,cell) in cells_iter::Iter::new(world){
+		hasher.write_u8(cell.is_occupied() as u8);
+	}
+	hasher.finish()
+}
+
+///Checksums of every part of a player
+#[derive(Copy,Clone,Debug,Eq,PartialEq,Serialize,Deserialize)]
+pub struct PlayerChecksums{
+	///The world the player resides in
+	pub world: Checksum,
+
+	///Position of the player and its fastfall shadow
+	pub position: Checksum,
+
+	///Current shape and its rotation
+	pub shape: Checksum,
+
+	///The queue of shapes coming next
+	pub queue: Checksum,
+
+	///Points
+	pub score: Checksum,
+
+	///Time counts and settings
+	pub timing: Checksum,
+}
+
+impl PlayerChecksums{
+	pub fn new(player: &Player) -> Self{
+		fn hash<F: FnOnce(&mut Hasher)>(f: F) -> Checksum{
+			let mut hasher = Hasher::new();
+			f(&mut hasher);
+			hasher.finish()
+		}
+
+		PlayerChecksums{
+			world: hash(|h| h.write_u8(player.world)),
+			position: hash(|h|{
+				h.write_i16(player.pos.x);
+				h.write_i16(player.pos.y);
+				match player.shadow_pos{
+					Some(shadow_pos) => {
+						h.write_u8(1);
+						h.write_i16(shadow_pos.x);
+						h.write_i16(shadow_pos.y);
+					},
+					None => h.write_u8(0)
+				}
+			}),
+			shape: hash(|h|{
+				h.write_u8(player.shape.shape() as u8);
+				h.write_u8(player.shape.rotation());
+			}),
+			queue: hash(|h| match player.shapes_lookahead{
+				Some(ref shapes_lookahead) => {
+					h.write_u8(1);
+					for &shape in shapes_lookahead.iter(){
+						h.write_u8(shape as u8);
+					}
+				},
+				None => h.write_u8(0)
+			}),
+			score: hash(|h| h.write_u32(player.points)),
+			timing: hash(|h|{
+				h.write_u64(unsafe{mem::transmute::<f64,u64>(player.gravityfall_time_count)});
+				h.write_u64(unsafe{mem::transmute::<f64,u64>(player.settings.gravityfall_frequency)});
+				h.write_u8(player.settings.fastfall_shadow as u8);
+			}),
+		}
+	}
+
+	fn write_to(&self,hasher: &mut Hasher){
+		hasher.write_u64(self.world);
+		hasher.write_u64(self.position);
+		hasher.write_u64(self.shape);
+		hasher.write_u64(self.queue);
+		hasher.write_u64(self.score);
+		hasher.write_u64(self.timing);
+	}
+}
+
+///Checksums of every world and player in the game data
+#[derive(Clone,Debug,Eq,PartialEq,Serialize,Deserialize)]
+pub struct Checksums{
+	pub worlds: Vec<(WorldId,Checksum)>,
+	pub players: Vec<(PlayerId,PlayerChecksums)>,
+}
+
+impl Checksums{
+	pub fn new<W>(data: &Data<W>) -> Self
+		where W: World,
+		      <W as Grid>::Cell: Cell + Copy
+	{Checksums{
+		worlds : data.worlds.iter().map(|(world_id,&(ref world,paused))| (world_id as WorldId,world_checksum(world,paused))).collect(),
+		players: data.players.iter().map(|(player_id,player)| (player_id as PlayerId,PlayerChecksums::new(player))).collect(),
+	}}
+
+	///Combines the checksums to a single checksum of the whole game data
+	pub fn checksum(&self) -> Checksum{
+		let mut hasher = Hasher::new();
+
+		for &(world_id,world) in self.worlds.iter(){
+			hasher.write_u8(world_id);
+			hasher.write_u64(world);
+		}
+
+		for &(player_id,ref player) in self.players.iter(){
+			hasher.write_u8(player_id);
+			player.write_to(&mut hasher);
+		}
+
+		hasher.finish()
+	}
+
+	///Returns the differences between two game datas' checksums
+	pub fn differences(&self,other: &Self) -> Vec<Difference>{
+		let mut differences = Vec::new();
+
+		for &(world_id,world) in self.worlds.iter(){
+			match other.worlds.iter().find(|&&(id,_)| id == world_id){
+				Some(&(_,other_world)) => if world != other_world{
+					differences.push(Difference::World(world_id));
+				},
+				None => differences.push(Difference::WorldExistence(world_id)),
+			}
+		}
+		for &(world_id,_) in other.worlds.iter(){
+			if !self.worlds.iter().any(|&(id,_)| id == world_id){
+				differences.push(Difference::WorldExistence(world_id));
+			}
+		}
+
+		for &(player_id,ref player) in self.players.iter(){
+			match other.players.iter().find(|&&(id,_)| id == player_id){
+				Some(&(_,ref other_player)) => {
+					if player.world    != other_player.world   {differences.push(Difference::PlayerWorld(player_id));}
+					if player.position != other_player.position{differences.push(Difference::PlayerPosition(player_id));}
+					if player.shape    != other_player.shape   {differences.push(Difference::PlayerShape(player_id));}
+					if player.queue    != other_player.queue   {differences.push(Difference::PlayerQueue(player_id));}
+					if player.score    != other_player.score   {differences.push(Difference::PlayerScore(player_id));}
+					if player.timing   != other_player.timing  {differences.push(Difference::PlayerTiming(player_id));}
+				},
+				None => differences.push(Difference::PlayerExistence(player_id)),
+			}
+		}
+		for &(player_id,_) in other.players.iter(){
+			if !self.players.iter().any(|&(id,_)| id == player_id){
+				differences.push(Difference::PlayerExistence(player_id));
+			}
+		}
+
+		differences
+	}
+
+	///Prints the differences between the expected and the actual checksums, prefixed by the given label
+	pub fn log_differences(&self,actual: &Self,label: &str){
+		let differences = self.differences(actual);
+		if differences.is_empty(){
+			println!("{}: Checksums mismatch but no differing part were found",label);
+		}else{
+			for difference in differences{
+				println!("{}: {}",label,difference);
+			}
+		}
+	}
+}
+
+///A part of the game data which differs between two checksums
+#[derive(Copy,Clone,Debug,Eq,PartialEq)]
+pub enum Difference{
+	WorldExistence(WorldId),
+	World(WorldId),
+	PlayerExistence(PlayerId),
+	PlayerWorld(PlayerId),
+	PlayerPosition(PlayerId),
+	PlayerShape(PlayerId),
+	PlayerQueue(PlayerId),
+	PlayerScore(PlayerId),
+	PlayerTiming(PlayerId),
+}
+
+impl fmt::Display for Difference{
+	fn fmt(&self,f: &mut fmt::Formatter) -> fmt::Result{
+		match *self{
+			Difference::WorldExistence(id)  => write!(f,"World {} only exists in one of the states",id),
+			Difference::World(id)           => write!(f,"World {} differs in cells or pause state",id),
+			Difference::PlayerExistence(id) => write!(f,"Player {} only exists in one of the states",id),
+			Difference::PlayerWorld(id)     => write!(f,"Player {} resides in different worlds",id),
+			Difference::PlayerPosition(id)  => write!(f,"Player {} differs in position",id),
+			Difference::PlayerShape(id)     => write!(f,"Player {} differs in shape or rotation",id),
+			Difference::PlayerQueue(id)     => write!(f,"Player {} differs in shape queue",id),
+			Difference::PlayerScore(id)     => write!(f,"Player {} differs in score",id),
+			Difference::PlayerTiming(id)    => write!(f,"Player {} differs in time counts or settings",id),
+		}
+	}
+}
+
+///A 64 bit FNV-1a hasher.
+///Unlike the hashers in the standard library, the result is guaranteed to be the same between runs and versions.
+#[derive(Copy,Clone,Debug,Eq,PartialEq)]
+pub struct Hasher(u64);
+
+impl Hasher{
+	const OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+	const PRIME: u64 = 0x100000001b3;
+
+	#[inline(always)]pub fn new() -> Self{Hasher(Self::OFFSET_BASIS)}
+}
+
+impl HasherTrait for Hasher{
+	#[inline(always)]
+	fn finish(&self) -> u64{self.0}
+
+	fn write(&mut self,bytes: &[u8]){
+		for &byte in bytes{
+			self.0 = (self.0 ^ byte as u64).wrapping_mul(Self::PRIME);
+		}
+	}
+
+	//Integers are written in little endian byte order independent of the platform
+	#[inline]fn write_u16(&mut self,i: u16){self.write(&[i as u8,(i >> 8) as u8])}
+	#[inline]fn write_u32(&mut self,i: u32){self.write_u16(i as u16);self.write_u16((i >> 16) as u16)}
+	#[inline]fn write_u64(&mut self,i: u64){self.write_u32(i as u32);self.write_u32((i >> 32) as u32)}
+	#[inline]fn write_usize(&mut self,i: usize){self.write_u64(i as u64)}
+	#[inline]fn write_i16(&mut self,i: i16){self.write_u16(i as u16)}
+	#[inline]fn write_i32(&mut self,i: i32){self.write_u32(i as u32)}
+	#[inline]fn write_i64(&mut self,i: i64){self.write_u64(i as u64)}
+	#[inline]fn write_isize(&mut self,i: isize){self.write_u64(i as u64)}
+}
@@ -23,6 +23,7 @@ pub enum Input{
 	Pause,
 }
 
+#[cfg(feature = "window")]
 pub mod key{
 	use piston::input::Key;
 	use std::collections::hash_map::HashMap;
@@ -2,7 +2,7 @@ use core::mem;
 use std::collections::hash_map::HashMap;
 
 ///Kind of data mappings
-#[derive(Copy,Clone,Debug,Eq,PartialEq,Hash)]
+#[derive(Copy,Clone,Debug,Eq,PartialEq,Hash,Serialize,Deserialize)]
 pub enum Key{
 	World(super::WorldId),
 	Player(super::PlayerId)
@@ -13,6 +13,7 @@ pub enum Key{
 ///The second field contains a map of datas.
 ///When looking up a mapping and it does not exist, it falls back to the more global one in the following order:
 ///  Player -> World -> Global
+#[derive(Clone,Serialize,Deserialize)]
 pub struct Mappings<T>(T,pub HashMap<Key,T>);
 
 impl<T> Mappings<T>{
@@ -29,6 +30,41 @@ impl<T> Mappings<T>{
 		self.1.insert(mapping,self.0.clone());
 	}
 
+	///Sets the data of the specified mapping, replacing the existing data
+	#[inline]pub fn insert(&mut self,mapping: Key,data: T){
+		self.1.insert(mapping,data);
+	}
+
+	///Replaces the data of the specified mapping with a copy of the data of another mapping.
+	///When the other mapping does not exist, the specified mapping is removed, making lookups of it fall back to the more global ones.
+	pub fn copy_from(&mut self,mapping: Key,from: Key)
+		where T: Clone
+	{
+		match self.1.get(&from).cloned(){
+			Some(data) => {self.1.insert(mapping,data);},
+			None       => {self.1.remove(&mapping);},
+		}
+	}
+
+	///Replaces the data of the specified mappings that exist with copies of the given data.
+	///Mappings that does not exist are left falling back to the more global ones.
+	pub fn replace_existing<I>(&mut self,mappings: I,data: T)
+		where I: IntoIterator<Item = Key>,
+		      T: Clone
+	{
+		for mapping in mappings{
+			if let Some(existing) = self.1.get_mut(&mapping){
+				*existing = data.clone();
+			}
+		}
+	}
+
+	///Removes the specified mapping, making lookups of it fall back to the more global ones.
+	///Returns the data of the removed mapping if it existed.
+	#[inline]pub fn remove(&mut self,mapping: Key) -> Option<T>{
+		self.1.remove(&mapping)
+	}
+
 	///Lookup data from a world mapping with fallbacks.
 	pub fn world_get_mut(&mut self,world: super::WorldId) -> &mut T{
 		self.1.get_mut(&Key::World(world)).unwrap_or(&mut self.0)
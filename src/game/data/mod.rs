@@ -1,3 +1,4 @@
+pub mod checksum;
 pub mod input;
 pub mod mappings;
 pub mod player;
@@ -10,11 +11,9 @@ pub use self::world::World;
 
 
 
-use serde::{Serialize,Serializer};
+use serde::{de,Serialize,Serializer,Deserialize,Deserializer};
 use vec_map::VecMap;
 
-use ::data::{grid,Cell,Grid};
-
 ///Type of the world id
 pub type WorldId = u8;
 
@@ -23,7 +22,7 @@ pub type PlayerId = u8;
 
 #[derive(Clone)]
 pub struct Data<W>{
-	///Mappings of world ids to worlds
+	///Mappings of world ids to worlds and whether they are paused
 	pub worlds: VecMap<(W,bool)>,
 
 	///Mappings of player ids to players
@@ -37,47 +36,37 @@ impl<W> Data<W>{
 	}}
 }
 
+///Serialized as a pair of sequences: The worlds with their ids and pause states, and the players with their ids
 impl<W> Serialize for Data<W>
-	where W: World,
-	      W: Grid,
-	      <W as Grid>::Cell: Cell + Copy
+	where W: Serialize
 {
-	#[inline]
 	fn serialize<S>(&self,serializer: &mut S) -> Result<(),S::Error>
 		where S: Serializer
 	{
-		grid::serde::GridSerializer::<_,W>::new(&self.worlds[0].0).visit(serializer)
+		let worlds : Vec<(WorldId,&W,bool)> = self.worlds.iter().map(|(world_id,&(ref world,paused))| (world_id as WorldId,world,paused)).collect();
+		let players: Vec<(PlayerId,&Player)> = self.players.iter().map(|(player_id,player)| (player_id as PlayerId,player)).collect();
+		(worlds,players).serialize(serializer)
 	}
 }
-/*impl Deserialize for Data<W>{
-	#[inline]
+
+impl<W> Deserialize for Data<W>
+	where W: Deserialize
+{
 	fn deserialize<D>(deserializer: &mut D) -> Result<Self,D::Error>
 		where D: Deserializer
 	{
-		struct V<W>;
-		impl de::Visitor for V<W>{
-			type Value = Data<W>;
+		let (worlds,players): (Vec<(WorldId,W,bool)>,Vec<(PlayerId,Player)>) = try!(Deserialize::deserialize(deserializer));
 
-			fn visit_str<E>(&mut self,s: &str) -> Result<Self::Value,E>
-				where E: de::Error,
-			{
-				if s=="TETR"{
-					Ok(Data<W>)
-				}else{
-					Err(de::Error::syntax("Expected `TETR` as the protocol id"))
-				}
-			}
-
-			fn visit_bytes<E>(&mut self,s: &[u8]) -> Result<Self::Value,E>
-				where E: de::Error,
-			{
-				if s==b"TETR"{
-					Ok(Data<W>)
-				}else{
-					Err(de::Error::syntax("Expected `TETR` as the protocol id"))
-				}
+		let mut data = Data::new();
+		for (world_id,world,paused) in worlds{
+			data.worlds.insert(world_id as usize,(world,paused));
+		}
+		for (player_id,player) in players{
+			if !data.worlds.contains_key(player.world as usize){
+				return Err(de::Error::custom(format!("Player {} resides in the non-existent world {}",player_id,player.world)));
 			}
+			data.players.insert(player_id as usize,player);
 		}
-		deserializer.visit_string(V)
+		Ok(data)
 	}
-}*/
+}
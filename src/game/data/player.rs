@@ -1,18 +1,18 @@
 ///Data related to players
 
-use fixed_circular_buffer::CircularBuffer;
+use std::collections::VecDeque;
 
 use ::data::grid;
 use ::data::shapes::tetromino::{RotatedShape,Shape};
 use ::game;
 
 ///Player state data
-#[derive(Clone,PartialEq)]
+#[derive(Clone,PartialEq,Serialize,Deserialize)]
 pub struct Player{
 	pub pos                   : grid::Pos,
 	pub shadow_pos            : Option<grid::Pos>,
-	pub shapes_lookahead      : Option<CircularBuffer<Shape>>,
-	pub shape                 : RotatedShape,//TODO: Consider only having a circular buffer with shapes, and a separate rotation field. Then the circular buffer won't need to be wrapped in a Option because it is guaranteed to be non-empty
+	pub shapes_lookahead      : Option<VecDeque<Shape>>,//The length of the queue is constant
+	pub shape                 : RotatedShape,//TODO: Consider only having a queue with shapes, and a separate rotation field. Then the queue won't need to be wrapped in a Option because it is guaranteed to be non-empty
 	pub world                 : game::data::WorldId,
 	pub points                : u32,
 	pub gravityfall_time_count: f64,//Unit: seconds
@@ -23,7 +23,8 @@ impl Player{
 	///Returns the next shape from the queue while queuing the given shape
 	pub fn next_shape(&mut self,queued_shape: Shape) -> Shape{
 		if let Some(ref mut shapes_lookahead) = self.shapes_lookahead{
-			shapes_lookahead.queue(queued_shape)
+			shapes_lookahead.push_back(queued_shape);
+			shapes_lookahead.pop_front().unwrap()
 		}else{
 			queued_shape
 		}
@@ -0,0 +1,276 @@
+use core::ops::Range;
+use serde::{de,Serialize,Serializer,Deserialize,Deserializer};
+
+use super::World as WorldTrait;
+use ::data::grid::{self,Grid,RectangularBound};
+use ::data::shapes::tetromino::RotatedShape;
+use ::data::Cell as CellTrait;
+
+///Type of a row, each bit being a cell. The least significant bit is the leftmost cell.
+pub type Row = u64;
+
+///Maximum width of the world
+pub const WIDTH_MAX: grid::SizeAxis = 64;
+
+///Rectangular dynamic sized game world where every row is an integer with one bit for every cell, only keeping track of whether the cells are occupied.
+///Faster than the other worlds for collision checks and line clears, making it suitable for AI searches.
+#[derive(Clone,Eq,PartialEq)]
+pub struct World{
+	rows : Box<[Row]>,
+	width: grid::SizeAxis,
+}
+
+impl Grid for World{
+	type Cell = bool;
+
+	#[inline(always)]
+	unsafe fn pos(&self,pos: grid::Pos) -> bool{
+		(self.rows[pos.y as usize] >> pos.x) & 1 == 1
+	}
+
+	fn is_out_of_bounds(&self,pos: grid::Pos) -> bool{grid::is_position_outside_rectangle(self,pos)}
+}
+
+impl grid::RectangularBound for World{
+	#[inline(always)]
+	fn width(&self) -> grid::SizeAxis{self.width}
+
+	#[inline(always)]
+	fn height(&self) -> grid::SizeAxis{self.rows.len() as grid::SizeAxis}
+}
+
+impl WorldTrait for World{
+	#[inline(always)]
+	unsafe fn set_pos(&mut self,x: usize,y: usize,state: bool){
+		if state{
+			self.rows[y]|= 1 << x;
+		}else{
+			self.rows[y]&= !(1 << x);
+		}
+	}
+
+	fn clear(&mut self){
+		for row in self.rows.iter_mut(){
+			*row = 0;
+		}
+	}
+
+	fn shape_intersects(&self,shape: &RotatedShape,pos: grid::Pos) -> super::CellIntersection{
+		for (dy,mask) in shape_rows(shape).iter().cloned().enumerate(){
+			if mask == 0{
+				continue;
+			}
+
+			let y = pos.y + dy as grid::PosAxis;
+			let first = mask.trailing_zeros() as grid::PosAxis;
+			let last = (63 - mask.leading_zeros()) as grid::PosAxis;
+
+			if y < 0 || y >= self.height() as grid::PosAxis{
+				return super::CellIntersection::OutOfBounds(grid::Pos{x: pos.x + first,y: y});
+			}
+
+			//Partially outside horizontally: Check cell by cell to find the first intersecting cell
+			if pos.x + first < 0 || pos.x + last >= self.width as grid::PosAxis{
+				for x in first .. last+1{
+					if (mask >> x) & 1 == 1{
+						let cell_pos = grid::Pos{x: pos.x + x,y: y};
+						if cell_pos.x < 0 || cell_pos.x >= self.width as grid::PosAxis{
+							return super::CellIntersection::OutOfBounds(cell_pos);
+						}else if unsafe{self.pos(cell_pos)}{
+							return super::CellIntersection::Imprint(cell_pos);
+						}
+					}
+				}
+			}
+
+			let hits = shift(mask,pos.x) & self.rows[y as usize];
+			if hits != 0{
+				return super::CellIntersection::Imprint(grid::Pos{x: hits.trailing_zeros() as grid::PosAxis,y: y});
+			}
+		}
+
+		super::CellIntersection::None
+	}
+
+	fn imprint_shape(&mut self,shape: &RotatedShape,pos: grid::Pos,cell_constructor: &fn(&RotatedShape) -> bool){
+		let state = cell_constructor(shape);
+		let full = self.full_row();
+
+		for (dy,mask) in shape_rows(shape).iter().cloned().enumerate(){
+			let y = pos.y + dy as grid::PosAxis;
+			if mask != 0 && y >= 0 && y < self.height() as grid::PosAxis{
+				let mask = shift(mask,pos.x) & full;
+				if state{
+					self.rows[y as usize]|= mask;
+				}else{
+					self.rows[y as usize]&= !mask;
+				}
+			}
+		}
+	}
+
+	fn handle_full_rows(&mut self,y_check: Range<grid::SizeAxis>) -> grid::SizeAxis{
+		debug_assert!(y_check.start < y_check.end);
+		debug_assert!(y_check.end <= self.height());
+
+		let full = self.full_row();
+		let mut full_row_count: grid::SizeAxis = 0;
+
+		//Remove the full rows in the checked range by moving every row above down
+		for y in (0 .. y_check.end).rev(){
+			if y >= y_check.start && self.rows[y as usize] == full{
+				full_row_count+= 1;
+			}else if full_row_count > 0{
+				self.rows[(y + full_row_count) as usize] = self.rows[y as usize];
+			}
+		}
+
+		//Clear the rows at the top
+		for y in 0 .. full_row_count{
+			self.rows[y as usize] = 0;
+		}
+
+		full_row_count
+	}
+
+	fn clear_row(&mut self,y: grid::SizeAxis){
+		debug_assert!(y < self.height());
+
+		self.rows[y as usize] = 0;
+	}
+
+	fn copy_row(&mut self,y_from: grid::SizeAxis,y_to: grid::SizeAxis){
+		debug_assert!(y_from != y_to);
+		debug_assert!(y_from < self.height());
+		debug_assert!(y_to < self.height());
+
+		self.rows[y_to as usize] = self.rows[y_from as usize];
+	}
+}
+
+impl World{
+	///Constructs an empty world
+	///Requirements:
+	///    0 < width <= WIDTH_MAX
+	pub fn new(width: grid::SizeAxis,height: grid::SizeAxis) -> Self{
+		assert!(width > 0 && width <= WIDTH_MAX);
+
+		World{
+			rows : vec![0; height as usize].into_boxed_slice(),
+			width: width,
+		}
+	}
+
+	///Constructs a world with the same size and occupied cells as the given world
+	///Requirements:
+	///    0 < world.width() <= WIDTH_MAX
+	pub fn from_world<W>(world: &W) -> Self
+		where W: WorldTrait,
+		      <W as Grid>::Cell: CellTrait + Copy
+	{
+		let mut new = World::new(world.width(),world.height());
+		for (pos,cell) in grid::cells_iter::Iter::new(world){
+			if cell.is_occupied(){
+				unsafe{new.set_pos(pos.x as usize,pos.y as usize,true)};
+			}
+		}
+		new
+	}
+
+	///Constructs a world like `from_world`, or returns None when the world has no columns or is wider than `WIDTH_MAX`
+	pub fn try_from_world<W>(world: &W) -> Option<Self>
+		where W: WorldTrait,
+		      <W as Grid>::Cell: CellTrait + Copy
+	{
+		if world.width() > 0 && world.width() <= WIDTH_MAX{
+			Some(World::from_world(world))
+		}else{
+			None
+		}
+	}
+
+	///Returns the row at the given y coordinate
+	///Requirements:
+	///    y < height()
+	#[inline(always)]
+	pub fn row(&self,y: grid::SizeAxis) -> Row{self.rows[y as usize]}
+
+	///Returns a row where every cell is occupied
+	#[inline(always)]
+	pub fn full_row(&self) -> Row{
+		if self.width == WIDTH_MAX{!0}else{(1 << self.width) - 1}
+	}
+
+	///Returns the height of the column at the given x coordinate, which is the distance from the bottom to the top of its highest occupied cell
+	///Requirements:
+	///    x < width()
+	pub fn column_height(&self,x: grid::SizeAxis) -> grid::SizeAxis{
+		match self.rows.iter().position(|row| (row >> x) & 1 == 1){
+			Some(y) => self.height() - y as grid::SizeAxis,
+			None    => 0
+		}
+	}
+
+	///Returns the number of empty cells with an occupied cell somewhere above it in the same column
+	pub fn holes(&self) -> u32{
+		let mut covered = 0;
+		let mut holes = 0;
+		for &row in self.rows.iter(){
+			holes+= (covered & !row).count_ones();
+			covered|= row;
+		}
+		holes
+	}
+}
+
+///Returns the rows of the shape, each row as a mask of its occupied cells
+fn shape_rows(shape: &RotatedShape) -> [Row; 4]{
+	let (width,data) = shape.shape().data(shape.rotation());
+	let mut rows = [0; 4];
+	for (y,row) in data.chunks(width as usize).enumerate().take(4){
+		rows[y] = row.iter().enumerate().fold(0,|mask,(x,&cell)| if cell{mask | 1 << x}else{mask});
+	}
+	rows
+}
+
+///Shifts a mask horizontally by the given number of cells. Cells shifted outside of the row are discarded.
+#[inline(always)]
+fn shift(mask: Row,x: grid::PosAxis) -> Row{
+	if x >= 64 || x <= -64{
+		0
+	}else if x >= 0{
+		mask << x
+	}else{
+		mask >> -x
+	}
+}
+
+///Serialized as the width followed by the rows
+impl Serialize for World{
+	fn serialize<S>(&self,serializer: &mut S) -> Result<(),S::Error>
+		where S: Serializer
+	{
+		(self.width,&*self.rows).serialize(serializer)
+	}
+}
+
+impl Deserialize for World{
+	fn deserialize<D>(deserializer: &mut D) -> Result<Self,D::Error>
+		where D: Deserializer
+	{
+		let (width,rows): (grid::SizeAxis,Vec<Row>) = try!(Deserialize::deserialize(deserializer));
+
+		if width == 0 || width > WIDTH_MAX || rows.len() > grid::SizeAxis::max_value() as usize{
+			return Err(de::Error::custom(format!("Invalid world dimensions: {} rows with the width {}",rows.len(),width)));
+		}
+
+		let world = World{
+			rows : rows.into_boxed_slice(),
+			width: width,
+		};
+		if world.rows.iter().any(|&row| row & !world.full_row() != 0){
+			return Err(de::Error::custom(format!("Invalid world: Occupied cells outside of the width {}",width)));
+		}
+		Ok(world)
+	}
+}
@@ -1,6 +1,7 @@
 use core::iter::{self,FromIterator};
 use core::ops::Range;
 use core::ptr;
+use serde::{de,Serialize,Serializer,Deserialize,Deserializer};
 
 use super::World as WorldTrait;
 use ::data::grid::{self,Grid,RectangularBound};
@@ -168,3 +169,29 @@ impl<Cell: CellTrait + Copy> World<Cell>{
 		}
 	}
 }
+
+///Serialized as the width followed by the cells row by row
+impl<Cell: Serialize> Serialize for World<Cell>{
+	fn serialize<S>(&self,serializer: &mut S) -> Result<(),S::Error>
+		where S: Serializer
+	{
+		(self.width,&*self.slice).serialize(serializer)
+	}
+}
+
+impl<Cell: Deserialize> Deserialize for World<Cell>{
+	fn deserialize<D>(deserializer: &mut D) -> Result<Self,D::Error>
+		where D: Deserializer
+	{
+		let (width,cells): (grid::SizeAxis,Vec<Cell>) = try!(Deserialize::deserialize(deserializer));
+
+		if width == 0 || cells.len() % (width as usize) != 0 || cells.len() / (width as usize) > grid::SizeAxis::max_value() as usize{
+			return Err(de::Error::custom(format!("Invalid world dimensions: {} cells with the width {}",cells.len(),width)));
+		}
+
+		Ok(World{
+			slice: cells.into_boxed_slice(),
+			width: width,
+		})
+	}
+}
@@ -1,5 +1,6 @@
 //!A game world where player's reside in
 
+pub mod bitboard;
 pub mod default;
 pub mod dynamic;
 
@@ -13,10 +13,10 @@ pub enum Event<P,W>{//TODO: Merge with server packets in online multiplayer if p
 	PlayerAdded{
 		player: P,
 	},
-	PlayerRemoved{//TODO: Implement
+	PlayerRemoved{
 		player: P,
 	},
-	PlayerMovedWorld{//TODO: Implement
+	PlayerMovedWorld{
 		player: P,
 		old: W,
 		new: W
@@ -27,11 +27,11 @@ pub enum Event<P,W>{//TODO: Merge with server packets in online multiplayer if p
 		target: Rotation,
 		cause: RotationCause,
 	},
-	PlayerCollidedOnMovement{//TODO: Implement
+	PlayerCollidedOnMovement{
 		player: P,
-		current: grid::PosAxis,
-		target: grid::PosAxis,
-		cause: RotationCause,
+		current: grid::Pos,
+		target: grid::Pos,
+		cause: MovementCause,
 	},
 	PlayerRotated{//TODO: Implement
 		player: P,
@@ -51,6 +51,12 @@ pub enum Event<P,W>{//TODO: Merge with server packets in online multiplayer if p
 		pos: grid::Pos,
 		cause: ShapeChangeCause,
 	},
+	PlayerFinesseFault{
+		player: P,
+		shape: (RotatedShape,grid::Pos),
+		inputs: u32,
+		minimum_inputs: u32,
+	},
 	WorldImprintedShape{
 		world: W,
 		shape: (RotatedShape,grid::Pos),
@@ -60,20 +66,21 @@ pub enum Event<P,W>{//TODO: Merge with server packets in online multiplayer if p
 	WorldAdded{//TODO: Implement
 		world: W,
 	},
-	WorldUpdated{//TODO: Implement
+	WorldUpdated{
 		world: W,
 	},
-	WorldRemoved{//TODO: Implement
+	WorldRemoved{
 		world: W,
 	},
-	WorldPaused{//TODO: Implement
+	WorldPaused{
 		world: W,
 	},
-	WorldUnpaused{//TODO: Implement
+	WorldUnpaused{
 		world: W,
 	},
-	GamePaused,//TODO: Implement
-	GameUnpaused,//TODO: Implement
+	GamePaused,
+	GameUnpaused,
+	GameQuit,
 }
 
 #[derive(Clone,Debug,Serialize,Deserialize)]
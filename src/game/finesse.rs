@@ -0,0 +1,166 @@
+//!Finesse analysis, checking whether the players place their shapes using as few inputs as possible
+//!
+//!For every imprinted shape, the moves and rotations performed since the shape spawned are compared to the minimum number needed to reach the placement by moving and rotating the shape before fast falling it (See `pathfinder::drop_path`).
+//!Falls are not counted, and placements that cannot be reached without slow falls (e.g. tucks under overhangs) are not judged.
+//!
+//!Only the players the tracking is enabled for are judged (e.g. the players controlled by humans), so that the placements of the other players do not cost a path search.
+//!The tracking of a player starts at the next shape spawned after enabling it.
+
+use vec_map::VecMap;
+
+use ::data::grid;
+use ::data::shapes::tetromino::RotatedShape;
+use ::game::pathfinder;
+use ::game::data::{Input,Player,PlayerId,World};
+
+///Finesse statistics of a player
+#[derive(Copy,Clone,Debug,Default,Eq,PartialEq)]
+pub struct Stats{
+	///Number of judged shapes
+	pub pieces: u32,
+
+	///Number of judged shapes placed using more inputs than necessary
+	pub faults: u32,
+
+	///Number of moves and rotations used for the judged shapes
+	pub inputs: u32,
+
+	///Minimum number of moves and rotations needed for the judged shapes
+	pub minimum_inputs: u32,
+}
+
+impl Stats{
+	///Number of moves and rotations used exceeding the minimum
+	#[inline]
+	pub fn extra_inputs(&self) -> u32{self.inputs.saturating_sub(self.minimum_inputs)}
+}
+
+///The shape of a player since it spawned
+#[derive(Copy,Clone,Debug)]
+struct Piece{
+	shape: RotatedShape,
+	pos: grid::Pos,
+
+	///Number of moves and rotations performed
+	inputs: u32,
+}
+
+///A fault of a player, using more moves and rotations than necessary
+#[derive(Copy,Clone,Debug,Eq,PartialEq)]
+pub struct Fault{
+	pub inputs: u32,
+	pub minimum_inputs: u32,
+}
+
+///Tracks the inputs of the players' shapes and their finesse statistics
+#[derive(Clone,Debug)]
+pub struct Tracker{
+	pieces: VecMap<Piece>,
+
+	///Statistics of the players the tracking is enabled for
+	stats: VecMap<Stats>,
+}
+
+impl Tracker{
+	pub fn new() -> Self{Tracker{
+		pieces: VecMap::new(),
+		stats : VecMap::new(),
+	}}
+
+	///Returns the statistics of the given player, or None when the tracking is not enabled for the player
+	#[inline]
+	pub fn stats(&self,player_id: PlayerId) -> Option<&Stats>{
+		self.stats.get(player_id as usize)
+	}
+
+	///Returns whether the tracking is enabled for the given player
+	#[inline]
+	pub fn is_enabled(&self,player_id: PlayerId) -> bool{
+		self.stats.contains_key(player_id as usize)
+	}
+
+	///Enables the tracking for the given player, which may not exist yet.
+	///The tracking starts at the next shape spawned for the player.
+	pub fn enable(&mut self,player_id: PlayerId){
+		self.stats.entry(player_id as usize).or_insert_with(Stats::default);
+	}
+
+	///Disables the tracking for the given player, forgetting its statistics
+	pub fn disable(&mut self,player_id: PlayerId){
+		self.pieces.remove(player_id as usize);
+		self.stats.remove(player_id as usize);
+	}
+
+	///Starts tracking a new shape of the given player at its spawn position when the tracking is enabled for the player
+	pub fn spawn(&mut self,player_id: PlayerId,shape: RotatedShape,pos: grid::Pos){
+		if self.is_enabled(player_id){
+			self.pieces.insert(player_id as usize,Piece{
+				shape: shape,
+				pos: pos,
+				inputs: 0,
+			});
+		}
+	}
+
+	///Counts an input performed by the given player
+	pub fn input(&mut self,player_id: PlayerId,input: Input){
+		if let Some(piece) = self.pieces.get_mut(player_id as usize){
+			match input{
+				Input::MoveLeft | Input::MoveRight | Input::RotateAntiClockwise | Input::RotateClockwise => piece.inputs+= 1,
+				_ => ()
+			}
+		}
+	}
+
+	///Judges the shape of the given player that is imprinted at the given placement, stopping tracking it.
+	///Returns the fault when more moves and rotations than necessary were used.
+	pub fn imprint<W>(&mut self,player_id: PlayerId,world: &W,shape: &RotatedShape,pos: grid::Pos) -> Option<Fault>
+		where W: World
+	{
+		let piece = match self.pieces.remove(player_id as usize){
+			Some(piece) => piece,
+			None => return None
+		};
+
+		//The fast fall at the end is not counted
+		let minimum_inputs = match pathfinder::drop_path(world,piece.shape,piece.pos,pos,shape.rotation()){
+			Some(inputs) => inputs.len() as u32 - 1,
+			None => return None
+		};
+
+		let stats = self.stats.entry(player_id as usize).or_insert_with(Stats::default);
+		stats.pieces+= 1;
+		stats.inputs+= piece.inputs;
+		stats.minimum_inputs+= minimum_inputs;
+
+		if piece.inputs > minimum_inputs{
+			stats.faults+= 1;
+			Some(Fault{
+				inputs: piece.inputs,
+				minimum_inputs: minimum_inputs,
+			})
+		}else{
+			None
+		}
+	}
+
+	///Resets the statistics of the given player when the tracking is enabled for the player
+	pub fn reset(&mut self,player_id: PlayerId){
+		if let Some(stats) = self.stats.get_mut(player_id as usize){
+			*stats = Stats::default();
+		}
+	}
+
+	///Forgets every shape and statistic, tracking the current shapes of the given players as if they just spawned.
+	///The tracking stays enabled for the given players it was enabled for.
+	///Used when the game data is replaced (e.g. when loading a save), where the inputs already performed are unknown.
+	pub fn restart(&mut self,players: &VecMap<Player>){
+		let enabled: Vec<usize> = self.stats.keys().filter(|&player_id| players.contains_key(player_id)).collect();
+		self.pieces.clear();
+		self.stats.clear();
+		for player_id in enabled{
+			self.enable(player_id as PlayerId);
+			self.spawn(player_id as PlayerId,players[player_id].shape,players[player_id].pos);
+		}
+	}
+}
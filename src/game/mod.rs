@@ -2,10 +2,17 @@
 
 pub mod data;
 pub mod event;
+pub mod finesse;
+pub mod pathfinder;
+pub mod replay;
 pub mod request;
+pub mod save;
+pub mod seed;
+pub mod simulation;
 pub mod state;
 
 pub use self::data::Data;
 pub use self::event::Event;
 pub use self::request::Request;
+pub use self::simulation::Simulation;
 pub use self::state::State;
@@ -0,0 +1,172 @@
+//!Reachability search of a player's shape
+//!
+//!A breadth first search over the positions and rotations of the shape, moving it by performing inputs using the same rules as the players (See `input::perform`).
+//!This finds placements that cannot be reached by fast falling from above, for example tucks under overhangs and rotations into narrow spaces.
+//!Because the search is breadth first, the input sequences found are the shortest ones.
+
+use std::collections::{HashMap,HashSet,VecDeque};
+
+use ::data::grid;
+use ::data::shapes::tetromino::{Rotation,RotatedShape};
+use ::game;
+use ::game::data::{player,Input,Player,World};
+
+///The inputs searched, in order of preference
+pub const INPUTS: [Input; 5] = [
+	Input::MoveLeft,
+	Input::MoveRight,
+	Input::RotateAntiClockwise,
+	Input::RotateClockwise,
+	Input::SlowFall,
+];
+
+///The inputs searched when the shape is only moved and rotated before the fast fall, in order of preference
+pub const DROP_INPUTS: [Input; 4] = [
+	Input::MoveLeft,
+	Input::MoveRight,
+	Input::RotateAntiClockwise,
+	Input::RotateClockwise,
+];
+
+///A reachable placement of a shape where it will be imprinted on the world
+#[derive(Clone,Debug,PartialEq)]
+pub struct Placement{
+	///Position of the shape when imprinted
+	pub pos: grid::Pos,
+
+	///The shape with the rotation when imprinted
+	pub shape: RotatedShape,
+
+	///The shortest input sequence moving the shape from the starting position to the placement, ending with a fast fall
+	pub inputs: Vec<Input>,
+}
+
+///Key of a visited position and rotation
+type Key = (grid::PosAxis,grid::PosAxis,Rotation);
+
+#[inline(always)]
+fn key(pos: grid::Pos,shape: &RotatedShape) -> Key{(pos.x,pos.y,shape.rotation())}
+
+///Breadth first search from the given position and shape using the given inputs.
+///Calls `visit` with every reached position and shape in the order they are reached, stopping when it returns false.
+///Returns the visited positions and shapes with the previous ones and the inputs used to reach them.
+fn search<W,F>(world: &W,shape: RotatedShape,pos: grid::Pos,searched_inputs: &[Input],mut visit: F) -> HashMap<Key,Option<(Key,Input)>>
+	where W: World,
+	      F: FnMut(grid::Pos,RotatedShape) -> bool
+{
+	//A player only used for performing the inputs on
+	let mut player = Player{
+		pos                   : pos,
+		shadow_pos            : None,
+		shapes_lookahead      : None,
+		shape                 : shape,
+		world                 : 0,
+		points                : 0,
+		gravityfall_time_count: 0.0,
+		settings              : player::Settings{
+			gravityfall_frequency: 1.0,
+			fastfall_shadow      : false,
+		},
+	};
+
+	let mut visited = HashMap::new();
+	let mut queue = VecDeque::new();
+
+	visited.insert(key(player.pos,&player.shape),None);
+	queue.push_back((player.pos,player.shape));
+
+	while let Some((pos,shape)) = queue.pop_front(){
+		if !visit(pos,shape){
+			break;
+		}
+
+		for &input in searched_inputs.iter(){
+			player.pos = pos;
+			player.shape = shape;
+			::input::perform(input,(0,&mut player),(0,world),&mut |_| ());
+
+			let new_key = key(player.pos,&player.shape);
+			if !visited.contains_key(&new_key){
+				visited.insert(new_key,Some((key(pos,&shape),input)));
+				queue.push_back((player.pos,player.shape));
+			}
+		}
+	}
+
+	visited
+}
+
+///Reconstructs the input sequence reaching the given key
+fn inputs(visited: &HashMap<Key,Option<(Key,Input)>>,mut key: Key) -> Vec<Input>{
+	let mut inputs = Vec::new();
+	while let Some(&Some((previous,input))) = visited.get(&key){
+		inputs.push(input);
+		key = previous;
+	}
+	inputs.reverse();
+	inputs
+}
+
+///Returns every placement reachable by the shape from the given position in the world, each one with the shortest input sequence reaching it
+pub fn placements<W>(world: &W,shape: RotatedShape,pos: grid::Pos) -> Vec<Placement>
+	where W: World
+{
+	//Every reached position fast falls to a placement. The first one reached for every placement is the nearest one.
+	let mut placements: Vec<(grid::Pos,RotatedShape,Key)> = Vec::new();
+	let mut found: HashSet<Key> = HashSet::new();
+	let visited = search(world,shape,pos,&INPUTS,|pos,shape|{
+		let placement_pos = game::state::fastfallen_shape_pos(&shape,world,pos);
+		if found.insert(key(placement_pos,&shape)){
+			placements.push((placement_pos,shape,key(pos,&shape)));
+		}
+		true
+	});
+
+	placements.into_iter().map(|(pos,shape,key)|{
+		let mut inputs = inputs(&visited,key);
+		inputs.push(Input::FastFall);
+		Placement{
+			pos   : pos,
+			shape : shape,
+			inputs: inputs,
+		}
+	}).collect()
+}
+
+///Returns the shortest input sequence moving the shape from the given position to the given placement, ending with a fast fall.
+///Returns None when the placement is unreachable.
+#[inline]
+pub fn path<W>(world: &W,shape: RotatedShape,pos: grid::Pos,target_pos: grid::Pos,target_rotation: Rotation) -> Option<Vec<Input>>
+	where W: World
+{
+	path_with(world,shape,pos,target_pos,target_rotation,&INPUTS)
+}
+
+///Returns the shortest input sequence moving and rotating the shape at the height of the given position and then fast falling it to the given placement, as when dropping the shape from above.
+///Returns None when the placement cannot be reached without slow falls (e.g. tucks under overhangs).
+#[inline]
+pub fn drop_path<W>(world: &W,shape: RotatedShape,pos: grid::Pos,target_pos: grid::Pos,target_rotation: Rotation) -> Option<Vec<Input>>
+	where W: World
+{
+	path_with(world,shape,pos,target_pos,target_rotation,&DROP_INPUTS)
+}
+
+fn path_with<W>(world: &W,shape: RotatedShape,pos: grid::Pos,target_pos: grid::Pos,target_rotation: Rotation,searched_inputs: &[Input]) -> Option<Vec<Input>>
+	where W: World
+{
+	let mut found = None;
+	let visited = search(world,shape,pos,searched_inputs,|p,shape|{
+		if shape.rotation() == target_rotation && game::state::fastfallen_shape_pos(&shape,world,p) == target_pos{
+			found = Some(key(p,&shape));
+			false
+		}else{
+			true
+		}
+	});
+
+	found.map(|key|{
+		let mut inputs = inputs(&visited,key);
+		inputs.push(Input::FastFall);
+		inputs
+	})
+}
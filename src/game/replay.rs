@@ -0,0 +1,320 @@
+//!Recording of games for replaying
+//!
+//!A replay consists of the seed and the settings that the game started with, followed by every request performed together with the tick it was performed at.
+//!Changes made directly to the game state, without going through requests, are not recorded.
+//!
+//!The checksum of the game data is recorded every tick, making it possible to verify that the playback is deterministic and to find the exact tick where it diverges.
+//!The checksums of every part of the game data are recorded periodically, telling which parts differ.
+//!
+//!Replays are serialized by `serde` and `bincode`, prefixed by the format version so that replays of other versions are recognized.
+
+use bincode;
+use rand;
+use std::io;
+
+use ::data::{grid,Cell,Grid};
+use ::game::{Data,Event,Request,Simulation,State};
+use ::game::data::{mappings,player,PlayerId,World,WorldId};
+use ::game::data::checksum::{self,Checksum,Checksums};
+use ::game::seed::Seed;
+use ::game::simulation::{Snapshot,Tick};
+
+///Type of the replay format version
+pub type Version = u16;
+
+///The current replay format version
+pub const VERSION: Version = 1;
+
+///Number of ticks between every recorded checksum of the parts of the game data
+pub const CHECKSUM_PARTS_INTERVAL: Tick = 60;
+
+///Number of ticks between every snapshot taken during playback
+pub const SNAPSHOT_INTERVAL: Tick = 600;
+
+///Minimum playback speed
+pub const SPEED_MIN: f64 = 0.25;
+
+///Maximum playback speed
+pub const SPEED_MAX: f64 = 8.0;
+
+///Initial settings of a game
+#[derive(Clone,Debug,PartialEq,Serialize,Deserialize)]
+pub struct Settings{
+	///Duration of every tick
+	///Unit: seconds/tick
+	pub timestep: f64,
+
+	///Sizes of the worlds in the order they were added
+	pub worlds: Vec<(WorldId,grid::Size)>,
+
+	///Settings of the players and the worlds they reside in, in the order they were added
+	pub players: Vec<(WorldId,player::Settings)>,
+}
+
+impl Settings{
+	///Sets up the worlds and players of the settings in the given game state.
+	///Every world and player gets its own random number generator copied from the global one.
+	pub fn setup<W,Rng,EL>(&self,state: &mut State<W,Rng>,new_world: fn(grid::Size) -> W,event_listener: &mut EL)
+		where W: World,
+		      <W as Grid>::Cell: Cell,
+		      Rng: rand::Rng + Clone,
+		      EL: FnMut(Event<(PlayerId,WorldId),WorldId>)
+	{
+		for &(world_id,size) in self.worlds.iter(){
+			state.data.worlds.insert(world_id as usize,(new_world(size),false));
+			state.rngs.insert_from_global(mappings::Key::World(world_id));
+		}
+
+		for &(world_id,settings) in self.players.iter(){
+			let rng = state.rngs.global().clone();
+			state.add_player_with_rng(world_id,settings,Some(rng),event_listener);
+		}
+	}
+}
+
+///A recorded game
+#[derive(Clone,Debug,PartialEq,Serialize,Deserialize)]
+pub struct Replay{
+	///Seed of the global random number generator
+	pub seed: Seed,
+
+	///Initial settings of the game
+	pub settings: Settings,
+
+	///Every performed request and the tick it was performed at, in the order they were performed
+	pub requests: Vec<(Tick,Request<PlayerId,WorldId>)>,
+
+	///Checksums of the game data after the update of every tick, in tick order.
+	///Contains the checksum of the whole game data, and the checksums of every part of it at every `CHECKSUM_PARTS_INTERVAL`th tick.
+	pub checksums: Vec<(Tick,Checksum,Option<Checksums>)>,
+
+	///Number of recorded ticks
+	pub ticks: Tick,
+}
+
+impl Replay{
+	///Constructs an empty replay of a game starting with the given seed and settings
+	pub fn new(seed: Seed,settings: Settings) -> Self{Replay{
+		seed     : seed,
+		settings : settings,
+		requests : Vec::new(),
+		checksums: Vec::new(),
+		ticks    : 0,
+	}}
+
+	///Records a request performed at the given tick
+	#[inline]pub fn record(&mut self,tick: Tick,request: Request<PlayerId,WorldId>){
+		self.requests.push((tick,request));
+	}
+
+	///Records the end of an update of the given tick, recording the checksum of the game data, and the checksums of its parts at every `CHECKSUM_PARTS_INTERVAL`th tick
+	pub fn record_update<W>(&mut self,tick: Tick,data: &Data<W>)
+		where W: World,
+		      <W as Grid>::Cell: Cell + Copy
+	{
+		if tick % CHECKSUM_PARTS_INTERVAL == 0{
+			let checksums = Checksums::new(data);
+			self.checksums.push((tick,checksums.checksum(),Some(checksums)));
+		}else{
+			self.checksums.push((tick,checksum::checksum(data),None));
+		}
+		self.ticks = tick + 1;
+	}
+
+	///Writes the replay in the current format version
+	pub fn write<Wr>(&self,writer: &mut Wr) -> Result<(),Error>
+		where Wr: io::Write
+	{
+		try!(bincode::serde::serialize_into(writer,&VERSION,bincode::SizeLimit::Infinite).map_err(Error::Serialize));
+		bincode::serde::serialize_into(writer,self,bincode::SizeLimit::Infinite).map_err(Error::Serialize)
+	}
+
+	///Reads a replay of the current format version
+	pub fn read<R>(reader: &mut R) -> Result<Self,Error>
+		where R: io::Read
+	{
+		match try!(bincode::serde::deserialize_from(reader,bincode::SizeLimit::Infinite).map_err(Error::Deserialize)){
+			VERSION => bincode::serde::deserialize_from(reader,bincode::SizeLimit::Infinite).map_err(Error::Deserialize),
+			version => Err(Error::UnsupportedVersion(version))
+		}
+	}
+}
+
+///Plays a replay back by simulating its recorded requests
+///
+///Seeking backwards is done by restoring the closest earlier snapshot and simulating forward from it.
+///Snapshots are taken every `SNAPSHOT_INTERVAL`th tick the first time it is simulated.
+pub struct Playback<W,Rng>
+	where W: World
+{
+	///The simulation of the replayed game
+	pub simulation: Simulation<W,Rng>,
+
+	///The replay being played
+	replay: Replay,
+
+	///Index of the next request in the replay to schedule
+	next_request: usize,
+
+	///Index of the next checksum in the replay to verify
+	next_checksum: usize,
+
+	///Snapshots in tick order
+	snapshots: Vec<Snapshot<W,Rng>>,
+
+	///The first tick where the checksum of the game data differed from the recorded one
+	divergence: Option<Tick>,
+
+	///Whether the differing parts of the game data have been logged since the divergence.
+	///They are logged at the first tick with recorded checksums of the parts.
+	differences_logged: bool,
+
+	///Whether the playback is paused
+	pub paused: bool,
+
+	///Speed multiplier of the playback
+	speed: f64,
+
+	///Time not yet simulated
+	///Unit: seconds
+	time_count: f64,
+}
+
+impl<W,Rng> Playback<W,Rng>
+	where W: World + Clone,
+	      <W as Grid>::Cell: Cell + Copy,
+	      Rng: rand::Rng + Clone + for<'s> rand::SeedableRng<&'s [usize]>
+{
+	///Constructs a playback of the replay, setting up the worlds and players using the given world constructor.
+	///The given game state should be empty, with its global random number generator seeded by the replay's seed.
+	pub fn new(replay: Replay,mut state: State<W,Rng>,new_world: fn(grid::Size) -> W) -> Self{
+		replay.settings.setup(&mut state,new_world,&mut |_|{});
+
+		let simulation = Simulation::new(state,replay.settings.timestep);
+		let snapshot = simulation.snapshot();
+
+		Playback{
+			simulation        : simulation,
+			replay            : replay,
+			next_request      : 0,
+			next_checksum     : 0,
+			snapshots         : vec![snapshot],
+			divergence        : None,
+			differences_logged: false,
+			paused            : false,
+			speed             : 1.0,
+			time_count        : 0.0,
+		}
+	}
+
+	///Returns the replay being played
+	#[inline(always)]pub fn replay(&self) -> &Replay{&self.replay}
+
+	///Returns the current tick
+	#[inline(always)]pub fn tick(&self) -> Tick{self.simulation.tick()}
+
+	///Returns whether the playback has reached the end of the replay
+	#[inline]pub fn is_finished(&self) -> bool{self.simulation.tick() >= self.replay.ticks}
+
+	///Returns the first tick where the checksum of the game data differed from the recorded one, if it has happened
+	#[inline(always)]pub fn divergence(&self) -> Option<Tick>{self.divergence}
+
+	///Returns the speed multiplier of the playback
+	#[inline(always)]pub fn speed(&self) -> f64{self.speed}
+
+	///Sets the speed multiplier of the playback, limited to the range `SPEED_MIN` to `SPEED_MAX`
+	pub fn set_speed(&mut self,speed: f64){
+		self.speed = speed.max(SPEED_MIN).min(SPEED_MAX);
+	}
+
+	///Advances the playback by the given real time passed, scaled by the speed multiplier
+	///Unit: seconds
+	pub fn update<EL>(&mut self,dt: f64,event_listener: &mut EL)
+		where EL: FnMut(Event<(PlayerId,WorldId),WorldId>)
+	{
+		if self.paused{
+			return;
+		}
+
+		self.time_count+= dt * self.speed;
+		while self.time_count >= self.simulation.timestep() && !self.is_finished(){
+			self.time_count-= self.simulation.timestep();
+			self.step(event_listener);
+		}
+	}
+
+	///Advances the playback by one tick
+	pub fn step<EL>(&mut self,event_listener: &mut EL)
+		where EL: FnMut(Event<(PlayerId,WorldId),WorldId>)
+	{
+		//Snapshot
+		let tick = self.simulation.tick();
+		if tick % SNAPSHOT_INTERVAL == 0 && self.snapshots.last().map_or(true,|snapshot| snapshot.tick() < tick){
+			self.snapshots.push(self.simulation.snapshot());
+		}
+
+		//Schedule the requests of this tick
+		while let Some(&(request_tick,request)) = self.replay.requests.get(self.next_request){
+			if request_tick > tick{
+				break;
+			}
+			self.simulation.schedule(request_tick,request);
+			self.next_request+= 1;
+		}
+
+		self.simulation.step(event_listener);
+
+		//Verify checksum
+		while let Some(&(checksum_tick,recorded,ref recorded_parts)) = self.replay.checksums.get(self.next_checksum){
+			if checksum_tick > tick{
+				break;
+			}
+			self.next_checksum+= 1;
+
+			if checksum_tick != tick{
+				continue;
+			}
+
+			if self.divergence.is_none() && checksum::checksum(&self.simulation.state.data) != recorded{
+				println!("Replay: Desynchronized at tick {}",tick);
+				self.divergence = Some(tick);
+			}
+
+			if let (Some(_),false,&Some(ref recorded_parts)) = (self.divergence,self.differences_logged,recorded_parts){
+				recorded_parts.log_differences(&Checksums::new(&self.simulation.state.data),"Replay");
+				self.differences_logged = true;
+			}
+		}
+	}
+
+	///Seeks to the given tick, limited to the end of the replay
+	pub fn seek(&mut self,tick: Tick){
+		let tick = if tick > self.replay.ticks{self.replay.ticks}else{tick};
+
+		//Restore the closest snapshot when seeking backwards
+		if tick < self.simulation.tick(){
+			let snapshot_index = match self.snapshots.iter().rposition(|snapshot| snapshot.tick() <= tick){
+				Some(i) => i,
+				None => 0
+			};
+			self.simulation.restore(&self.snapshots[snapshot_index]);
+
+			let restored_tick = self.simulation.tick();
+			self.next_request  = self.replay.requests.iter().position(|&(request_tick,_)| request_tick >= restored_tick).unwrap_or(self.replay.requests.len());
+			self.next_checksum = self.replay.checksums.iter().position(|&(checksum_tick,_,_)| checksum_tick >= restored_tick).unwrap_or(self.replay.checksums.len());
+		}
+
+		while self.simulation.tick() < tick{
+			self.step(&mut |_|{});
+		}
+		self.time_count = 0.0;
+	}
+}
+
+///Errors that can occur when writing or reading replays
+#[derive(Debug)]
+pub enum Error{
+	Serialize(bincode::serde::SerializeError),
+	Deserialize(bincode::serde::DeserializeError),
+	UnsupportedVersion(Version),
+}
@@ -18,6 +18,11 @@ pub enum Request<P,W>{
 		player: P
 	},
 
+	PlayerMoveWorld{
+		player: P,
+		world: W
+	},
+
 	/*
 	PlayerSet{
 		player: P
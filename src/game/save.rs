@@ -0,0 +1,57 @@
+//!Saving and loading of games
+//!
+//!A save consists of the game data, the random number generators and the pause state of the game, making it possible to resume the game exactly where it was saved.
+//!Saves are serialized by `serde` and `bincode`, prefixed by the format version.
+//!
+//!Finesse statistics are not saved, and start over for the shapes of the loaded game.
+
+use bincode;
+use serde::{Serialize,Deserialize};
+use std::io;
+
+use ::game::{Data,State};
+use ::game::data::{Mappings,World};
+
+///Type of the save format version
+pub type Version = u16;
+
+///The current save format version
+pub const VERSION: Version = 1;
+
+///Writes the state of the game
+pub fn write<W,Rng,Wr>(state: &State<W,Rng>,writer: &mut Wr) -> Result<(),Error>
+	where W: World + Serialize,
+	      Rng: Serialize,
+	      Wr: io::Write
+{
+	try!(bincode::serde::serialize_into(writer,&VERSION,bincode::SizeLimit::Infinite).map_err(Error::Serialize));
+	bincode::serde::serialize_into(writer,&(&state.data,&state.rngs,state.paused),bincode::SizeLimit::Infinite).map_err(Error::Serialize)
+}
+
+///Reads a saved game, replacing the state of the game.
+///The state is left unchanged on errors.
+pub fn read<W,Rng,R>(state: &mut State<W,Rng>,reader: &mut R) -> Result<(),Error>
+	where W: World + Deserialize,
+	      Rng: Deserialize,
+	      R: io::Read
+{
+	match try!(bincode::serde::deserialize_from(reader,bincode::SizeLimit::Infinite).map_err(Error::Deserialize)){
+		1 => {
+			let (data,rngs,paused): (Data<W>,Mappings<Rng>,bool) = try!(bincode::serde::deserialize_from(reader,bincode::SizeLimit::Infinite).map_err(Error::Deserialize));
+			state.data   = data;
+			state.rngs   = rngs;
+			state.paused = paused;
+			state.finesse.restart(&state.data.players);
+			Ok(())
+		},
+		version => Err(Error::UnsupportedVersion(version))
+	}
+}
+
+///Errors that can occur when writing or reading saves
+#[derive(Debug)]
+pub enum Error{
+	Serialize(bincode::serde::SerializeError),
+	Deserialize(bincode::serde::DeserializeError),
+	UnsupportedVersion(Version),
+}
@@ -0,0 +1,114 @@
+//!Deterministic seeding of random number generators
+//!
+//!A game is seeded once at the start by constructing the global random number generator from a `Seed`.
+//!The per-world and per-player generators are copies of the global one (See `Mappings::insert_from_global`).
+//!When a world restarts, a new generator is seeded by the next value of the global generator and copied to the existing mappings of the world and its players (See `State::reset_world`).
+//!This makes every game reproducible from its seed and the sequence of requests performed.
+
+use rand::{self,SeedableRng};
+use serde::{de,Serialize,Serializer,Deserialize,Deserializer};
+
+///Type of the seed that all random number generators in a game are derived from
+pub type Seed = u64;
+
+///The standard random number generator used in games, which is able to be serialized
+pub type StdRng = SeededRng<rand::StdRng>;
+
+///Maximum number of values generated by a deserialized random number generator (See `SeededRng`).
+///Far more than any game generates, while bounding the time it takes to deserialize corrupted or crafted data.
+pub const MAX_GENERATED: u64 = 1 << 24;
+
+///Returns a new seed using the entropy of the operating system
+pub fn random() -> Seed{
+	rand::random()
+}
+
+///Constructs the standard random number generator from a seed.
+///The seed is split into 32 bit words so that no information is lost on platforms with 32 bit words.
+pub fn std_rng(seed: Seed) -> StdRng{
+	SeededRng::from_seed(&[(seed & 0xFFFF_FFFF) as usize,(seed >> 32) as usize][..])
+}
+
+///A random number generator that keeps track of its seed and the number of values generated by it.
+///This makes it possible to serialize the state of generators with private internals.
+///
+///Every generated value advances the underlying generator by exactly one 64 bit value.
+///When deserializing, the generator is seeded and then advanced by the number of values generated before serialization, which is an error when it exceeds `MAX_GENERATED`.
+#[derive(Clone)]
+pub struct SeededRng<R>{
+	rng: R,
+	seed: Vec<usize>,
+	generated: u64,
+}
+
+impl<R> SeededRng<R>{
+	///Returns the seed of the generator
+	#[inline(always)]pub fn seed(&self) -> &[usize]{&self.seed}
+
+	///Returns the number of values generated since seeding
+	#[inline(always)]pub fn generated(&self) -> u64{self.generated}
+}
+
+impl<R> rand::Rng for SeededRng<R>
+	where R: rand::Rng
+{
+	#[inline]
+	fn next_u32(&mut self) -> u32{
+		self.next_u64() as u32
+	}
+
+	#[inline]
+	fn next_u64(&mut self) -> u64{
+		self.generated+= 1;
+		self.rng.next_u64()
+	}
+}
+
+impl<'s,R> SeedableRng<&'s [usize]> for SeededRng<R>
+	where R: rand::Rng + SeedableRng<&'s [usize]>
+{
+	fn reseed(&mut self,seed: &'s [usize]){
+		self.rng.reseed(seed);
+		self.seed = seed.to_vec();
+		self.generated = 0;
+	}
+
+	fn from_seed(seed: &'s [usize]) -> Self{SeededRng{
+		rng: R::from_seed(seed),
+		seed: seed.to_vec(),
+		generated: 0,
+	}}
+}
+
+///Serialized as the seed and the number of values generated
+impl<R> Serialize for SeededRng<R>{
+	fn serialize<S>(&self,serializer: &mut S) -> Result<(),S::Error>
+		where S: Serializer
+	{
+		(&self.seed,self.generated).serialize(serializer)
+	}
+}
+
+impl<R> Deserialize for SeededRng<R>
+	where R: rand::Rng + for<'s> SeedableRng<&'s [usize]>
+{
+	fn deserialize<D>(deserializer: &mut D) -> Result<Self,D::Error>
+		where D: Deserializer
+	{
+		let (seed,generated): (Vec<usize>,u64) = try!(Deserialize::deserialize(deserializer));
+		if generated > MAX_GENERATED{
+			return Err(de::Error::invalid_value("Too many values generated by the random number generator"));
+		}
+
+		let mut rng = R::from_seed(&seed[..]);
+		for _ in 0..generated{
+			rng.next_u64();
+		}
+
+		Ok(SeededRng{
+			rng: rng,
+			seed: seed,
+			generated: generated,
+		})
+	}
+}
@@ -0,0 +1,182 @@
+//!Headless simulation of a game state
+//!
+//!Advances the game state in fixed time steps (ticks) independent of real time and without any window or graphics.
+//!Requests are scheduled to be performed at specific ticks.
+//!Controllers can be driven by calling `Controller::update` with `timestep()` before each step and then receiving their requests using `receive`.
+
+use rand;
+use std::collections::BTreeMap;
+use std::sync;
+
+use ::data::{Cell,Grid};
+use ::game::{finesse,replay,seed,Data,Event,Request,State};
+use ::game::data::{Mappings,PlayerId,World,WorldId};
+use ::game::data::world::dynamic;
+
+///Type of a simulation tick (number of time steps since the start of the simulation)
+pub type Tick = u64;
+
+///Default duration of a time step
+///Unit: seconds/tick
+pub const DEFAULT_TIMESTEP: f64 = 1.0/60.0;
+
+///A game state advancing in fixed time steps
+pub struct Simulation<W,Rng>
+	where W: World
+{
+	///The simulated game state
+	pub state: State<W,Rng>,
+
+	///The current tick, which is the tick of the next step
+	tick: Tick,
+
+	///Duration of every time step
+	///Unit: seconds/tick
+	timestep: f64,
+
+	///Scheduled requests ordered by tick, then by the order they were scheduled in
+	requests: BTreeMap<Tick,Vec<Request<PlayerId,WorldId>>>,
+
+	///Whether a request for quitting the game has been performed
+	quit: bool,
+}
+
+impl<W,Rng> Simulation<W,Rng>
+	where W: World
+{
+	///Constructs a simulation starting at tick 0 with the given game state and time step duration
+	pub fn new(state: State<W,Rng>,timestep: f64) -> Self{Simulation{
+		state   : state,
+		tick    : 0,
+		timestep: timestep,
+		requests: BTreeMap::new(),
+		quit    : false,
+	}}
+
+	///Returns the current tick, which is the tick of the next step
+	#[inline(always)]pub fn tick(&self) -> Tick{self.tick}
+
+	///Returns the duration of every time step
+	///Unit: seconds/tick
+	#[inline(always)]pub fn timestep(&self) -> f64{self.timestep}
+
+	///Returns whether a request for quitting the game has been performed
+	#[inline(always)]pub fn has_quit(&self) -> bool{self.quit}
+
+	///Returns whether there are requests scheduled which has not been performed yet
+	#[inline]pub fn has_scheduled_requests(&self) -> bool{!self.requests.is_empty()}
+
+	///Schedules a request to be performed at the given tick.
+	///Requests scheduled for a tick that already has passed will be performed at the next step.
+	pub fn schedule(&mut self,tick: Tick,request: Request<PlayerId,WorldId>){
+		self.requests.entry(tick).or_insert_with(Vec::new).push(request);
+	}
+
+	///Schedules all the pending requests from the receiver to be performed at the next step
+	pub fn receive(&mut self,receiver: &sync::mpsc::Receiver<Request<PlayerId,WorldId>>){
+		let tick = self.tick;
+		while let Ok(request) = receiver.try_recv(){
+			self.schedule(tick,request);
+		}
+	}
+
+	///Advances the simulation by one time step.
+	///The requests scheduled up to and including the current tick are performed before the game state is updated.
+	pub fn step<EL>(&mut self,event_listener: &mut EL)
+		where <W as Grid>::Cell: Cell,
+		      Rng: rand::Rng + Clone + for<'s> rand::SeedableRng<&'s [usize]>,
+		      EL: FnMut(Event<(PlayerId,WorldId),WorldId>)
+	{
+		{
+			let &mut Simulation{ref mut state,ref mut quit,ref mut requests,tick,timestep} = self;
+			let mut listener = |e: Event<(PlayerId,WorldId),WorldId>|{
+				if let Event::GameQuit = e{
+					*quit = true;
+				}
+				event_listener(e);
+			};
+
+			//Requests
+			loop{
+				let request_tick = match requests.keys().next(){
+					Some(&request_tick) if request_tick <= tick => request_tick,
+					_ => break
+				};
+
+				for request in requests.remove(&request_tick).unwrap(){
+					state.request(request,&mut listener);
+				}
+			}
+
+			//Update
+			state.update(timestep,&mut listener);
+		}
+
+		self.tick+= 1;
+	}
+
+	///Returns a copy of the current simulation state which can be restored later
+	pub fn snapshot(&self) -> Snapshot<W,Rng>
+		where W: Clone,
+		      Rng: Clone
+	{Snapshot{
+		tick    : self.tick,
+		data    : self.state.data.clone(),
+		rngs    : self.state.rngs.clone(),
+		paused  : self.state.paused,
+		finesse : self.state.finesse.clone(),
+		requests: self.requests.clone(),
+		quit    : self.quit,
+	}}
+
+	///Restores the simulation state from a snapshot
+	pub fn restore(&mut self,snapshot: &Snapshot<W,Rng>)
+		where W: Clone,
+		      Rng: Clone
+	{
+		self.tick          = snapshot.tick;
+		self.state.data    = snapshot.data.clone();
+		self.state.rngs    = snapshot.rngs.clone();
+		self.state.paused  = snapshot.paused;
+		self.state.finesse = snapshot.finesse.clone();
+		self.requests      = snapshot.requests.clone();
+		self.quit          = snapshot.quit;
+	}
+
+	///Advances the simulation until the given tick is reached or the game has quit
+	pub fn run_until<EL>(&mut self,tick: Tick,event_listener: &mut EL)
+		where <W as Grid>::Cell: Cell,
+		      Rng: rand::Rng + Clone + for<'s> rand::SeedableRng<&'s [usize]>,
+		      EL: FnMut(Event<(PlayerId,WorldId),WorldId>)
+	{
+		while self.tick < tick && !self.quit{
+			self.step(event_listener);
+		}
+	}
+}
+
+impl Simulation<dynamic::World<bool>,seed::StdRng>{
+	///Constructs a simulation starting at tick 0 with a headless game state (See `State::headless`)
+	pub fn headless<EL>(seed: seed::Seed,settings: &replay::Settings,event_listener: &mut EL) -> Self
+		where EL: FnMut(Event<(PlayerId,WorldId),WorldId>)
+	{
+		Simulation::new(State::headless(seed,settings,event_listener),settings.timestep)
+	}
+}
+
+///A copy of the simulation state at a certain tick
+#[derive(Clone)]
+pub struct Snapshot<W,Rng>{
+	tick    : Tick,
+	data    : Data<W>,
+	rngs    : Mappings<Rng>,
+	paused  : bool,
+	finesse : finesse::Tracker,
+	requests: BTreeMap<Tick,Vec<Request<PlayerId,WorldId>>>,
+	quit    : bool,
+}
+
+impl<W,Rng> Snapshot<W,Rng>{
+	///Returns the tick the snapshot was taken at
+	#[inline(always)]pub fn tick(&self) -> Tick{self.tick}
+}
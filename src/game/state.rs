@@ -1,12 +1,12 @@
 use core::cmp;
-use piston::input::UpdateArgs;
-use rand::{self,Rand};
+use rand::{self,Rand,SeedableRng};
 
 use ::data::{grid,Cell,Grid};
 use ::data::grid::RectangularBound;
 use ::data::shapes::tetromino::{Shape,RotatedShape};
-use ::game::{data,event,Data,Event};
+use ::game::{data,event,finesse,replay,seed,Data,Event,Request};
 use ::game::data::{world,player,Player,PlayerId,World,WorldId};
+use ::game::data::world::dynamic;
 
 ///The ingame game state
 pub struct State<W,Rng>
@@ -22,7 +22,13 @@ pub struct State<W,Rng>
 	pub imprint_cell: fn(&RotatedShape) -> <W as Grid>::Cell,
 
 	///Function that returns the origin position of a player based on shape and world
-	pub respawn_pos: fn(&RotatedShape,&W) -> grid::Pos
+	pub respawn_pos: fn(&RotatedShape,&W) -> grid::Pos,
+
+	///Whether the whole game is paused
+	pub paused: bool,
+
+	///Finesse analysis of the players' inputs
+	pub finesse: finesse::Tracker,
 }
 
 impl<W,Rng> State<W,Rng>
@@ -38,15 +44,21 @@ impl<W,Rng> State<W,Rng>
 		rngs        : data::Mappings::new(rng),
 		imprint_cell: imprint_cell,
 		respawn_pos : respawn_pos,
+		paused      : false,
+		finesse     : finesse::Tracker::new(),
 	}}
 
-	///Updates the game state
-	pub fn update<EL>(&mut self, args: &UpdateArgs,event_listener: &mut EL)
+	///Updates the game state, advancing it by the given time step (Unit: seconds)
+	pub fn update<EL>(&mut self,dt: f64,event_listener: &mut EL)
 		where W: World,
 		      <W as Grid>::Cell: Cell,
 		      Rng: rand::Rng,
 		      EL: FnMut(Event<(PlayerId,WorldId),WorldId>)
 	{
+		if self.paused{
+			return;
+		}
+
 		//Players
 		'player_loop: for (player_id,player) in self.data.players.iter_mut(){
 			let player_id = player_id    as PlayerId;
@@ -55,7 +67,7 @@ impl<W,Rng> State<W,Rng>
 			if let Some(&mut(ref mut world,ref mut paused)) = self.data.worlds.get_mut(player.world as usize){
 				if !*paused{
 					//Add the time since the last update to the time counts
-					player.gravityfall_time_count -= args.dt;
+					player.gravityfall_time_count -= dt;
 
 					//Gravity: If the time count is greater than the shape move frequency, then repeat until it is smaller
 					while player.gravityfall_time_count <= 0.0{
@@ -63,7 +75,7 @@ impl<W,Rng> State<W,Rng>
 						player.gravityfall_time_count += player.settings.gravityfall_frequency;
 
 						//If able to move (no collision below)
-						if move_player(player,world,grid::Pos{x: 0,y: 1}){
+						if move_player((player_id,player),(world_id,world),grid::Pos{x: 0,y: 1},event::MovementCause::Gravity,event_listener){
 							event_listener(Event::PlayerMoved{
 								player: (player_id,world_id),
 								old: player.pos,
@@ -71,6 +83,16 @@ impl<W,Rng> State<W,Rng>
 								cause: event::MovementCause::Gravity,
 							});
 						}else{
+							//Judge the inputs used for the placement before the world changes
+							if let Some(fault) = self.finesse.imprint(player_id,world,&player.shape,player.pos){
+								event_listener(Event::PlayerFinesseFault{
+									player: (player_id,world_id),
+									shape: (player.shape,player.pos),
+									inputs: fault.inputs,
+									minimum_inputs: fault.minimum_inputs,
+								});
+							}
+
 							//Imprint the current shape onto the world
 							world.imprint_shape(&player.shape,player.pos,&self.imprint_cell);
 
@@ -95,6 +117,7 @@ impl<W,Rng> State<W,Rng>
 							if !respawn_player((player_id,player),(world_id,world),shape,self.respawn_pos,event_listener){
 								*paused = true;
 							}
+							self.finesse.spawn(player_id,player.shape,player.pos);
 						}
 					}
 				}
@@ -104,20 +127,40 @@ impl<W,Rng> State<W,Rng>
 
 	///Adds a player to the specified world and with the specified player settings
 	///Returns the new player id
+	#[inline]
 	pub fn add_player<EL>(&mut self,world_id: WorldId,settings: player::Settings,event_listener: &mut EL) -> Option<PlayerId>
 		where W: World,
 		      Rng: rand::Rng,
 		      EL: FnMut(Event<(PlayerId,WorldId),WorldId>)
+	{
+		self.add_player_with_rng(world_id,settings,None,event_listener)
+	}
+
+	///Adds a player like `add_player`, mapping the given random number generator to the new player before its first shape is chosen.
+	///Without a generator, the player falls back to the one of its world.
+	///Returns the new player id
+	pub fn add_player_with_rng<EL>(&mut self,world_id: WorldId,settings: player::Settings,rng: Option<Rng>,event_listener: &mut EL) -> Option<PlayerId>
+		where W: World,
+		      Rng: rand::Rng,
+		      EL: FnMut(Event<(PlayerId,WorldId),WorldId>)
 	{
 		//If the world exists
 		if let Some(&mut(ref mut world,_)) = self.data.worlds.get_mut(world_id as usize){
-			//Id is incremental
-			let new_id = self.data.players.len();
+			//Id is the lowest unused one
+			let new_id = match (0..).find(|&id| !self.data.players.contains_key(id)){
+				Some(id) if id <= PlayerId::max_value() as usize => id,
+				_ => return None
+			};
+			if let Some(rng) = rng{
+				self.rngs.insert(data::mappings::Key::Player(new_id as PlayerId),rng);
+			}
 			//Use a random shape with a random rotation
 			let shape = RotatedShape::new(<Shape as rand::Rand>::rand(self.rngs.player_get_mut(world_id,new_id as PlayerId)));
+			let pos = (self.respawn_pos)(&shape,world);
 
+			self.finesse.spawn(new_id as PlayerId,shape,pos);
 			self.data.players.insert(new_id,Player{
-				pos                   : (self.respawn_pos)(&shape,world),
+				pos                   : pos,
 				shadow_pos            : None,
 				shapes_lookahead      : None,
 				shape                 : shape,
@@ -137,13 +180,25 @@ impl<W,Rng> State<W,Rng>
 		}
 	}
 
-	///Resets the specified world, respawning all players and resetting time counts
+	///Resets the specified world, respawning all players and resetting time counts.
+	///The random number generators of the world and its players are reseeded by a new generator seeded from the global one (See `game::seed`).
 	pub fn reset_world<EL>(&mut self,world_id: WorldId,event_listener: &mut EL)
 		where W: World,
 		      <W as Grid>::Cell: Cell,
-		      Rng: rand::Rng,
+		      Rng: rand::Rng + Clone + for<'s> SeedableRng<&'s [usize]>,
 		      EL: FnMut(Event<(PlayerId,WorldId),WorldId>)
 	{
+		//Reseed the random number generators of the world and its players
+		if self.data.worlds.contains_key(world_id as usize){
+			let rng = Rng::from_seed(&[rand::Rng::gen::<usize>(self.rngs.global())][..]);
+			let mappings: Vec<data::mappings::Key> = self.data.players.iter()
+				.filter(|&(_,player)| player.world == world_id)
+				.map(|(player_id,_)| data::mappings::Key::Player(player_id as PlayerId))
+				.chain(Some(data::mappings::Key::World(world_id)))
+				.collect();
+			self.rngs.replace_existing(mappings,rng);
+		}
+
 		if let Some(&mut(ref mut world,ref mut paused)) = self.data.worlds.get_mut(world_id as usize){
 			//Clear world
 			world.clear();
@@ -162,21 +217,345 @@ impl<W,Rng> State<W,Rng>
 				);
 				//Resets the gravity trigger time counter
 				player.gravityfall_time_count = player.settings.gravityfall_frequency;
+				//Resets the finesse statistics
+				self.finesse.reset(player_id as PlayerId);
+				self.finesse.spawn(player_id as PlayerId,player.shape,player.pos);
 			}
+
+			event_listener(Event::WorldUpdated{
+				world: world_id,
+			});
 		};
 	}
+
+	///Removes the specified player
+	///Returns whether the player existed
+	pub fn remove_player<EL>(&mut self,player_id: PlayerId,event_listener: &mut EL) -> bool
+		where EL: FnMut(Event<(PlayerId,WorldId),WorldId>)
+	{
+		if let Some(player) = self.data.players.remove(player_id as usize){
+			self.rngs.remove(data::mappings::Key::Player(player_id));
+			self.finesse.disable(player_id);
+
+			event_listener(Event::PlayerRemoved{
+				player: (player_id,player.world),
+			});
+
+			true
+		}else{
+			false
+		}
+	}
+
+	///Moves the specified player to another world, respawning it at the origin position of the new world with its current shape.
+	///The random number generator of the player is replaced by a copy of the one of the new world (Or removed, falling back to the one of the new world, when the world has none of its own), so that the player gets the shapes of the new world from then on.
+	///Returns whether the move was successful.
+	///It fails when the player or the world does not exist, when the player already resides in the world or when the shape collides at the origin position of the new world.
+	pub fn move_player_to_world<EL>(&mut self,player_id: PlayerId,world_id: WorldId,event_listener: &mut EL) -> bool
+		where W: World,
+		      Rng: Clone,
+		      EL: FnMut(Event<(PlayerId,WorldId),WorldId>)
+	{
+		if let Some(player) = self.data.players.get_mut(player_id as usize){
+			if player.world == world_id{
+				return false;
+			}
+
+			if let Some(&(ref world,_)) = self.data.worlds.get(world_id as usize){
+				//Check for collision at spawn position before detaching the player from its old world
+				let pos = (self.respawn_pos)(&player.shape,world);
+				if let world::CellIntersection::None = world.shape_intersects(&player.shape,pos){
+					let old_world_id = player.world;
+
+					player.world = world_id;
+					player.pos = pos;
+					player.shadow_pos = if player.settings.fastfall_shadow{
+						Some(fastfallen_shape_pos(&player.shape,world,player.pos))
+					}else{
+						None
+					};
+					player.gravityfall_time_count = player.settings.gravityfall_frequency;
+					self.finesse.spawn(player_id,player.shape,player.pos);
+					self.rngs.copy_from(data::mappings::Key::Player(player_id),data::mappings::Key::World(world_id));
+
+					event_listener(Event::PlayerMovedWorld{
+						player: (player_id,world_id),
+						old: old_world_id,
+						new: world_id,
+					});
+
+					return true;
+				}
+			}
+		}
+
+		false
+	}
+
+	///Removes the specified world and all the players in it
+	///Returns whether the world existed
+	pub fn remove_world<EL>(&mut self,world_id: WorldId,event_listener: &mut EL) -> bool
+		where EL: FnMut(Event<(PlayerId,WorldId),WorldId>)
+	{
+		if !self.data.worlds.contains_key(world_id as usize){
+			return false;
+		}
+
+		//Remove all players in the world
+		let player_ids: Vec<PlayerId> = self.data.players.iter()
+			.filter(|&(_,player)| player.world == world_id)
+			.map(|(player_id,_)| player_id as PlayerId)
+			.collect();
+		for player_id in player_ids{
+			self.remove_player(player_id,event_listener);
+		}
+
+		self.data.worlds.remove(world_id as usize);
+		self.rngs.remove(data::mappings::Key::World(world_id));
+
+		event_listener(Event::WorldRemoved{
+			world: world_id,
+		});
+
+		true
+	}
+
+	///Adds garbage to the bottom of the specified world, pushing its rows upwards.
+	///Every garbage row is filled with the given cell except for a hole in a column chosen by the random number generator of the world, the same column for every row.
+	///The players in the world are moved upwards when the garbage pushes into them.
+	///The world is lost (paused) when an occupied cell is pushed out at the top or a player cannot be moved out of the way.
+	///Returns whether the world existed
+	pub fn add_garbage<EL>(&mut self,world_id: WorldId,rows: grid::SizeAxis,cell: <W as Grid>::Cell,event_listener: &mut EL) -> bool
+		where W: World,
+		      <W as Grid>::Cell: Cell + Copy,
+		      Rng: rand::Rng,
+		      EL: FnMut(Event<(PlayerId,WorldId),WorldId>)
+	{
+		if let Some(&mut(ref mut world,ref mut paused)) = self.data.worlds.get_mut(world_id as usize){
+			let rows = cmp::min(rows,world.height());
+			if rows == 0{
+				return true;
+			}
+
+			//Occupied cells pushed out at the top
+			if (0..rows).any(|y| (0..world.width()).any(|x| world.position(grid::Pos{x: x as grid::PosAxis,y: y as grid::PosAxis}).map_or(false,|cell| cell.is_occupied()))){
+				*paused = true;
+			}
+
+			//Push the rows upwards
+			for y in 0 .. world.height()-rows{
+				world.copy_row(y + rows,y);
+			}
+
+			//Fill the garbage rows
+			let hole = rand::Rng::gen_range(self.rngs.world_get_mut(world_id),0,world.width());
+			for y in world.height()-rows .. world.height(){
+				for x in 0..world.width(){
+					unsafe{world.set_pos(x as usize,y as usize,if x == hole{<<W as Grid>::Cell as Cell>::empty()}else{cell})};
+				}
+			}
+
+			//Move the players in the world out of the way
+			for (_,player) in self.data.players.iter_mut().filter(|&(_,ref player)| player.world == world_id){
+				let mut moved = 0;
+				loop{
+					match world.shape_intersects(&player.shape,player.pos){
+						world::CellIntersection::None => break,
+						_ if moved == rows => {
+							*paused = true;
+							break;
+						},
+						_ => {
+							player.pos.y-= 1;
+							moved+= 1;
+						}
+					}
+				}
+
+				if player.settings.fastfall_shadow{
+					player.shadow_pos = Some(fastfallen_shape_pos(&player.shape,world,player.pos));
+				}
+			}
+
+			event_listener(Event::WorldUpdated{
+				world: world_id,
+			});
+
+			true
+		}else{
+			false
+		}
+	}
+
+	///Pauses or unpauses the specified world
+	///Returns whether the world existed
+	pub fn set_world_paused<EL>(&mut self,world_id: WorldId,paused: bool,event_listener: &mut EL) -> bool
+		where EL: FnMut(Event<(PlayerId,WorldId),WorldId>)
+	{
+		if let Some(&mut(_,ref mut world_paused)) = self.data.worlds.get_mut(world_id as usize){
+			if *world_paused != paused{
+				*world_paused = paused;
+
+				event_listener(if paused{
+					Event::WorldPaused{world: world_id}
+				}else{
+					Event::WorldUnpaused{world: world_id}
+				});
+			}
+			true
+		}else{
+			false
+		}
+	}
+
+	///Pauses or unpauses the whole game
+	pub fn set_paused<EL>(&mut self,paused: bool,event_listener: &mut EL)
+		where EL: FnMut(Event<(PlayerId,WorldId),WorldId>)
+	{
+		if self.paused != paused{
+			self.paused = paused;
+
+			event_listener(if paused{
+				Event::GamePaused
+			}else{
+				Event::GameUnpaused
+			});
+		}
+	}
+
+	///Performs the given request on the game state, signaling the resulting events
+	///Returns whether the request was fulfilled
+	pub fn request<EL>(&mut self,request: Request<PlayerId,WorldId>,event_listener: &mut EL) -> bool
+		where W: World,
+		      <W as Grid>::Cell: Cell,
+		      Rng: rand::Rng + Clone + for<'s> SeedableRng<&'s [usize]>,
+		      EL: FnMut(Event<(PlayerId,WorldId),WorldId>)
+	{
+		match request{
+			Request::PlayerInput{input,player: player_id} => {
+				if self.paused{
+					return false;
+				}
+
+				if let Some(player) = self.data.players.get_mut(player_id as usize){
+					let world_id = player.world;
+					if let Some(&mut(ref mut world,false)) = self.data.worlds.get_mut(world_id as usize){
+						::input::perform(input,(player_id,player),(world_id,world),event_listener);
+						self.finesse.input(player_id,input);
+						return true;
+					}
+				}
+				false
+			},
+
+			Request::PlayerAdd{settings,world: world_id} => {
+				self.add_player(world_id,settings,event_listener).is_some()
+			},
+
+			Request::PlayerRemove{player: player_id} => {
+				self.remove_player(player_id,event_listener)
+			},
+
+			Request::PlayerMoveWorld{player: player_id,world: world_id} => {
+				self.move_player_to_world(player_id,world_id,event_listener)
+			},
+
+			Request::WorldRemove{world: world_id} => {
+				self.remove_world(world_id,event_listener)
+			},
+
+			Request::WorldRestart{world: world_id} => {
+				if self.data.worlds.contains_key(world_id as usize){
+					self.reset_world(world_id,event_listener);
+					true
+				}else{
+					false
+				}
+			},
+
+			Request::WorldPause{world: world_id} => {
+				self.set_world_paused(world_id,true,event_listener)
+			},
+
+			Request::WorldUnpause{world: world_id} => {
+				self.set_world_paused(world_id,false,event_listener)
+			},
+
+			Request::GamePause => {
+				self.set_paused(true,event_listener);
+				true
+			},
+
+			Request::GameUnpause => {
+				self.set_paused(false,event_listener);
+				true
+			},
+
+			Request::GameRestart => {
+				let world_ids: Vec<WorldId> = self.data.worlds.keys().map(|world_id| world_id as WorldId).collect();
+				for world_id in world_ids{
+					self.reset_world(world_id,event_listener);
+				}
+				self.set_paused(false,event_listener);
+				true
+			},
+
+			Request::GameQuit => {
+				event_listener(Event::GameQuit);
+				true
+			},
+		}
+	}
+}
+
+impl State<dynamic::World<bool>,seed::StdRng>{
+	///Constructs a game state without any visual information in the worlds, with the worlds and players of the settings set up.
+	///Shapes spawn at the top center of the worlds.
+	pub fn headless<EL>(seed: seed::Seed,settings: &replay::Settings,event_listener: &mut EL) -> Self
+		where EL: FnMut(Event<(PlayerId,WorldId),WorldId>)
+	{
+		let mut state = State::headless_empty(seed);
+		settings.setup(&mut state,State::headless_world,event_listener);
+		state
+	}
+
+	///Constructs a game state without any worlds or players, for setting up the worlds later using `headless_world`
+	pub fn headless_empty(seed: seed::Seed) -> Self{State::new(
+		seed::std_rng(seed),
+		{fn f(_: &RotatedShape) -> bool{true}f}as fn(&_) -> _,
+		{fn f(shape: &RotatedShape,world: &dynamic::World<bool>) -> grid::Pos{grid::Pos{
+			x: world.width() as grid::PosAxis/2 - shape.center_x() as grid::PosAxis,
+			y: 0
+		}}f}as fn(&_,&_) -> _,
+	)}
+
+	///Constructs an empty world without any visual information
+	pub fn headless_world(size: grid::Size) -> dynamic::World<bool>{
+		dynamic::World::new(size.x,size.y)
+	}
 }
 
 ///Moves player if there are no collisions at the new position.
+///Signals `PlayerCollidedOnMovement` when colliding.
 ///Returns whether the movement was successful or not due to collisions.
-pub fn move_player<W>(player: &mut Player,world: &W,delta: grid::Pos) -> bool
-	where W: World
+pub fn move_player<W,EL>((player_id,player): (PlayerId,&mut Player),(world_id,world): (WorldId,&W),delta: grid::Pos,cause: event::MovementCause,event_listener: &mut EL) -> bool
+	where W: World,
+	      EL: FnMut(Event<(PlayerId,WorldId),WorldId>)
 {
 	//Collision check
 	match world.shape_intersects(&player.shape,player.pos + delta){
 		//Collided => cannot move
 		world::CellIntersection::Imprint(_) |
-		world::CellIntersection::OutOfBounds(_) => false,
+		world::CellIntersection::OutOfBounds(_) => {
+			event_listener(Event::PlayerCollidedOnMovement{
+				player: (player_id,world_id),
+				current: player.pos,
+				target: player.pos + delta,
+				cause: cause,
+			});
+
+			false
+		},
 
 		//No collision, able to move and does so
 		world::CellIntersection::None => {
@@ -1,22 +1,24 @@
 //! Input code
 
 use ::data::grid;
-use ::game::data::{Input,Player,World};
-use ::game;
+use ::game::data::{Input,Player,PlayerId,World,WorldId};
+use ::game::{self,event,Event};
 
 ///Performs an action based on the given input on a player in a world
-pub fn perform<W>(input: Input,player: &mut Player,world: &W)
-	where W: World
+pub fn perform<W,EL>(input: Input,(player_id,player): (PlayerId,&mut Player),(world_id,world): (WorldId,&W),event_listener: &mut EL)
+	where W: World,
+	      EL: FnMut(Event<(PlayerId,WorldId),WorldId>)
 {
 	match input{
 		Input::MoveLeft => {
-			game::state::move_player(player,world,grid::Pos{x: -1, y: 0});
+			game::state::move_player((player_id,player),(world_id,world),grid::Pos{x: -1, y: 0},event::MovementCause::Input(input),event_listener);
 		},
 		Input::MoveRight => {
-			game::state::move_player(player,world,grid::Pos{x: 1, y: 0});
+			game::state::move_player((player_id,player),(world_id,world),grid::Pos{x: 1, y: 0},event::MovementCause::Input(input),event_listener);
 		},
 		Input::SlowFall => {
-			player.gravityfall_time_count = if game::state::move_player(player,world,grid::Pos{x: 0,y: 1}){
+			let moved = game::state::move_player((player_id,&mut *player),(world_id,world),grid::Pos{x: 0,y: 1},event::MovementCause::Input(input),event_listener);
+			player.gravityfall_time_count = if moved{
 				//Reset timer
 				player.settings.gravityfall_frequency
 			} else {
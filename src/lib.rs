@@ -0,0 +1,29 @@
+//!The game engine of tetr: Grids, worlds, shapes, game states, events, requests, controllers and online connections.
+//!
+//!Rendering through piston and OpenGL is only available with the `window` feature enabled (default).
+//!Headless consumers can disable the default features to build without any graphics dependencies.
+
+#![feature(associated_consts,collections,custom_derive,optin_builtin_traits,plugin,repr_simd,slice_patterns)]
+#![allow(dead_code)]
+
+#![plugin(rand_macros)]
+#![plugin(serde_macros)]
+extern crate bincode;
+extern crate byte_conv;
+extern crate collections;
+extern crate core;
+extern crate num;
+extern crate rand;
+extern crate rustc_serialize;
+extern crate serde;
+extern crate vec_map;
+#[cfg(feature = "window")]extern crate graphics;
+#[cfg(feature = "window")]extern crate opengl_graphics;
+#[cfg(feature = "window")]extern crate piston;
+
+pub mod controller;
+pub mod data;
+pub mod game;
+pub mod input;
+pub mod online;
+#[cfg(feature = "window")]pub mod render;
@@ -1,23 +1,13 @@
-#![feature(associated_consts,collections,custom_derive,ip,lookup_host,optin_builtin_traits,plugin,repr_simd,slice_patterns)]
+#![feature(ip,lookup_host,plugin)]
 #![allow(dead_code)]
 
 #![plugin(docopt_macros)]
-#![plugin(rand_macros)]
-#![plugin(serde_macros)]
-extern crate bincode;
-extern crate byte_conv;
-extern crate collections;
 extern crate core;
 extern crate docopt;
-extern crate fixed_circular_buffer;
-extern crate graphics;
-extern crate num;
 extern crate opengl_graphics;
 extern crate piston;
-extern crate rand;
 extern crate rustc_serialize;
-extern crate serde;
-extern crate vec_map;
+extern crate tetr;
 #[cfg(feature = "include_sdl2")]  extern crate sdl2_window;
 #[cfg(feature = "include_glfw")]  extern crate glfw_window;
 #[cfg(feature = "include_glutin")]extern crate glutin_window;
@@ -27,25 +17,22 @@ macro_rules! PROGRAM_NAME{() => ("tetr")}
 macro_rules! PROGRAM_NAME_VERSION{() => (concat!(PROGRAM_NAME!()," v",env!("CARGO_PKG_VERSION")))}
 
 mod cli;
-mod controller;
-mod data;
-mod game;
-mod input;
-mod online;
-mod render;
-
-use core::f64;
-use piston::window::WindowSettings;
+
+use tetr::{controller,data,game,online,render};
+
+use core::{cmp,f64};
+use piston::window::{Window as WindowTrait,WindowSettings};
 use piston::event_loop::Events;
 use piston::input::{Button,Key,PressEvent,ReleaseEvent,RenderEvent,UpdateEvent,UpdateArgs};
 use opengl_graphics::GlGraphics;
-use std::{net,sync};
+use std::{fs,net,process,sync};
 use std::collections::hash_map::{self,HashMap};
 #[cfg(feature = "include_sdl2")]  use sdl2_window::Sdl2Window as Window;
 #[cfg(feature = "include_glfw")]  use glfw_window::GlfwWindow as Window;
 #[cfg(feature = "include_glutin")]use glutin_window::GlutinWindow as Window;
 
-use ::controller::{ai,Controller};
+use ::controller::ai;
+use ::controller::registry::{self,Control};
 use ::data::{cell,grid,Grid,PairMap};
 use ::data::shapes::tetromino::{Shape,RotatedShape};
 use ::game::data::world::dynamic::World;
@@ -54,11 +41,19 @@ use ::game::{Event,Request};
 
 struct App{
 	gl: GlGraphics,
-	game_state: game::State<World<cell::ShapeCell>,rand::StdRng>,
-	controllers: Vec<Box<Controller<World<cell::ShapeCell>,Event<(PlayerId,WorldId),WorldId>>>>,
+	game_state: game::State<World<cell::ShapeCell>,game::seed::StdRng>,
+	seed: game::seed::Seed,
+	controllers: registry::Registry<World<cell::ShapeCell>>,
+	ai_settings: ai::bruteforce::Settings,
+	ai_pool: ai::pool::Pool,
+	hints: ai::hint::Hints,
 	request_receiver: sync::mpsc::Receiver<Request<PlayerId,WorldId>>,
+	network_request_receiver: sync::mpsc::Receiver<Request<PlayerId,WorldId>>,
 	connection: online::ConnectionType,
-	paused: bool,
+	quit: bool,
+	tick: game::simulation::Tick,
+	replay: Option<game::replay::Replay>,
+	save_path: String,
 	key_map: ::game::data::input::key::KeyMap,
 	key_down: HashMap<Key,f64>,
 }
@@ -66,10 +61,9 @@ struct App{
 impl App{
 	fn update(&mut self,args: &UpdateArgs,request_sender: &sync::mpsc::Sender<Request<PlayerId,WorldId>>){
 		//Controllers
-		if !self.paused{
-			for mut controller in self.controllers.iter_mut(){
-				controller.update(args,&self.game_state.data);
-			}
+		if !self.game_state.paused{
+			self.controllers.update(args.dt,&self.game_state.data);
+			self.hints.update(&self.game_state.data);
 		}
 
 		//Key repeat
@@ -79,7 +73,9 @@ impl App{
 				*time_left <= 0.0
 			}{
 				*time_left = if let Some(mapping) = self.key_map.get(key){
-					request_sender.send(Request::PlayerInput{input: mapping.input,player: mapping.player}).unwrap();
+					if self.controllers.is_human(mapping.player){
+						request_sender.send(Request::PlayerInput{input: mapping.input,player: mapping.player}).unwrap();
+					}
 					*time_left + mapping.repeat_frequency
 				}else{
 					f64::NAN//TODO: If the mapping doesn't exist, it will never be removed
@@ -87,49 +83,108 @@ impl App{
 			}
 		}
 
-		//Input
-		while let Ok(request) = self.request_receiver.try_recv(){match request{
-			Request::PlayerInput{input,player: pid} => {
-				if let Some(player) = self.game_state.data.players.get_mut(pid as usize){
-					if let Some(&mut(ref mut world,false)) = self.game_state.data.worlds.get_mut(player.world as usize){
-						input::perform(input,player,world);
-					}
+		//Connection lost. The players of the client are removed.
+		let timed_out = if let online::ConnectionType::Client(_,_,ref endpoint,_) = self.connection{
+			endpoint.connection.lock().unwrap().is_timed_out()
+		}else{
+			false
+		};
+		if timed_out{
+			println!("Disconnected from the server");
+			if let online::ConnectionType::Client(ref player_ids,_,_,_) = self.connection{
+				for (_,&player_id) in player_ids.iter(){
+					request_sender.send(Request::PlayerRemove{player: player_id}).unwrap();
 				}
+			}
+			self.connection = online::ConnectionType::None;
+		}
 
-				if let online::ConnectionType::Client(ref player_ids,Some(connection_id),ref socket,ref address) = self.connection{if let Some(player_network_id) = player_ids.get2(pid){
-					socket.send_to(&*online::client::packet::Data::Request{
-						connection: connection_id,//TODO: Maybe this should not send directly to the socket. The connection id is not received until the connection has began
+		//Requests. The ones received over the network are performed when the controllers accept them.
+		loop{
+			let (request,from_network) = match self.request_receiver.try_recv(){
+				Ok(request) => (request,false),
+				Err(_) => match self.network_request_receiver.try_recv(){
+					Ok(request) => {
+						if !self.controllers.accepts_network_request(&request){
+							continue;
+						}
+						(request,true)
+					},
+					Err(_) => break
+				}
+			};
+
+			//Local inputs are sent to the server
+			if let (Request::PlayerInput{input,player: pid},false) = (request,from_network){
+				if let online::ConnectionType::Client(ref player_ids,Some(connection_id),ref endpoint,_) = self.connection{if let Some(player_network_id) = player_ids.get2(pid){
+					endpoint.send(online::client::packet::Data::Request{
+						connection: connection_id,
 						request: Request::PlayerInput{
 							player: player_network_id,
 							input: input
 						}
-					}.into_packet(0).serialize(),address).unwrap();
+					},online::reliable::Reliability::ReliableOrdered).unwrap();
 				}}
-			},
-			Request::PlayerAdd{settings,world: world_id} => {
-				let &mut App{game_state: ref mut game,controllers: ref mut cs,..} = self;
-				game.add_player(world_id,settings,&mut |e| for c in cs.iter_mut(){c.event(&e);});
-			},
-			Request::WorldRestart{world: world_id} => {
-				let &mut App{game_state: ref mut game,controllers: ref mut cs,..} = self;
-				game.reset_world(world_id,&mut |e| for c in cs.iter_mut(){c.event(&e);});
-			},
-			_ => ()
-		}}
+			}
+
+			if let Some(ref mut replay) = self.replay{
+				replay.record(self.tick,request);
+			}
+
+			let mut added = None;
+			{let &mut App{game_state: ref mut game,controllers: ref mut cs,ref mut hints,ref mut quit,ref connection,..} = self;
+				game.request(request,&mut |e|{
+					if let Event::GameQuit = e{
+						*quit = true;
+					}
+					cs.event(&e);
+					hints.event(&e);
+
+					if let (&Event::PlayerAdded{player},true) = (&e,from_network){
+						added = Some(player);
+
+						//Players added by the clients are controlled by them
+						if let &online::ConnectionType::Server(..) = connection{
+							cs.set(player.0,Control::Network);
+						}
+					}
+				});
+			}
+
+			//The connection keeps track of the players added over the network
+			if let (Request::PlayerAdd{..},true) = (request,from_network){
+				self.connection.player_added(added);
+
+				//The finesse of the local players is judged
+				if let Some((player_id,_)) = added{
+					if self.controllers.is_human(player_id){
+						self.game_state.finesse.enable(player_id);
+					}
+				}
+			}
+		}
 
 		//Update
-		if !self.paused{
-			let &mut App{game_state: ref mut game,controllers: ref mut cs,..} = self;
-			game.update(args,&mut |e| for c in cs.iter_mut(){c.event(&e);});
+		{
+			let &mut App{game_state: ref mut game,controllers: ref mut cs,ref mut hints,..} = self;
+			game.update(args.dt,&mut |e|{
+				cs.event(&e);
+				hints.event(&e);
+			});
 		}
+
+		if let Some(ref mut replay) = self.replay{
+			replay.record_update(self.tick,&self.game_state.data);
+		}
+		self.tick+= 1;
 	}
 
 	fn on_key_press(&mut self,key: Key,request_sender: &sync::mpsc::Sender<Request<PlayerId,WorldId>>){
-		if self.paused{match key{
-			Key::Return => {self.paused = false},
+		if self.game_state.paused{match key{
+			Key::Return => {request_sender.send(Request::GameUnpause).unwrap();},
 			_ => {},
 		}}else{match key{
-			Key::Return => {self.paused = true},
+			Key::Return => {request_sender.send(Request::GamePause).unwrap();},
 
 			//Player 0 Tests
 			Key::D1 => {if let Some(player) = self.game_state.data.players.get_mut(0 as usize){player.shape = RotatedShape::new(Shape::I);};},
@@ -140,23 +195,85 @@ impl App{
 			Key::D6 => {if let Some(player) = self.game_state.data.players.get_mut(0 as usize){player.shape = RotatedShape::new(Shape::S);};},
 			Key::D7 => {if let Some(player) = self.game_state.data.players.get_mut(0 as usize){player.shape = RotatedShape::new(Shape::Z);};},
 			Key::R  => {
-				match self.game_state.data.players.get(0 as usize).map(|player| player.world){//TODO: New seed for rng
+				match self.game_state.data.players.get(0 as usize).map(|player| player.world){
 					Some(world_id) => {request_sender.send(Request::WorldRestart{world: world_id}).unwrap();},
 					None => ()
 				};
 			},
 			Key::Home => {if let Some(player) = self.game_state.data.players.get_mut(0 as usize){player.pos.y = 0;};},
 
+			//Swap the control of a player: Human -> AI -> Network (When online) -> Human
+			Key::F1 => self.swap_control(0,request_sender),
+			Key::F2 => self.swap_control(1,request_sender),
+
+			//Toggle the hints of the AI for a player
+			Key::F3 => self.toggle_hints(0),
+			Key::F4 => self.toggle_hints(1),
+
+			//Save and load
+			Key::F5 => {
+				match fs::File::create(&self.save_path).map_err(|e| format!("{}",e)).and_then(|mut file| game::save::write(&self.game_state,&mut file).map_err(|e| format!("{:?}",e))){
+					Ok(_)  => println!("Game saved to {}",self.save_path),
+					Err(e) => println!("Error when saving game to {}: {}",self.save_path,e),
+				}
+			},
+			Key::F9 => {
+				match fs::File::open(&self.save_path).map_err(|e| format!("{}",e)).and_then(|mut file| game::save::read(&mut self.game_state,&mut file).map_err(|e| format!("{:?}",e))){
+					Ok(_)  => {
+						println!("Game loaded from {}",self.save_path);
+						self.hints.outdate_all();
+						if self.replay.take().is_some(){
+							println!("Replay: Recording stopped because the loaded game is not a continuation of the recorded one");
+						}
+					},
+					Err(e) => println!("Error when loading game from {}: {}",self.save_path,e),
+				}
+			},
+
 			//Other keys, check key bindings
 			key => if let Some(mapping) = self.key_map.get(&key){
 				if let hash_map::Entry::Vacant(entry) = self.key_down.entry(key){
 					entry.insert(mapping.repeat_delay);
-					request_sender.send(Request::PlayerInput{input: mapping.input,player: mapping.player}).unwrap();
+					if self.controllers.is_human(mapping.player){
+						request_sender.send(Request::PlayerInput{input: mapping.input,player: mapping.player}).unwrap();
+					}
 				}
 			}
 		}}
 	}
 
+	fn swap_control(&mut self,player_id: PlayerId,request_sender: &sync::mpsc::Sender<Request<PlayerId,WorldId>>){
+		let control = match self.controllers.get(player_id){
+			Some(&Control::Human) => Control::Controller(Box::new(ai::bruteforce::Controller::with_pool(
+				request_sender.clone(),
+				player_id,
+				self.ai_settings,
+				self.seed,
+				self.ai_pool.clone()
+			)) as registry::BoxedController<World<cell::ShapeCell>>),
+			Some(&Control::Controller(_)) => if let online::ConnectionType::None = self.connection{Control::Human}else{Control::Network},
+			Some(&Control::Network) => Control::Human,
+			None => return
+		};
+		println!("Player {} is now controlled by {}",player_id,match control{
+			Control::Human         => "a human",
+			Control::Network       => "the network",
+			Control::Controller(_) => "the AI",
+		});
+		if control.is_human(){
+			self.game_state.finesse.enable(player_id);
+		}else{
+			self.game_state.finesse.disable(player_id);
+		}
+		self.controllers.set(player_id,control);
+		self.hints.outdate_all();
+	}
+
+	fn toggle_hints(&mut self,player_id: PlayerId){
+		let enabled = self.hints.toggle(player_id);
+		println!("Hints for player {} are now {}",player_id,if enabled{"enabled"}else{"disabled"});
+	}
+
 	fn on_key_release(&mut self,key: Key){
 		if let hash_map::Entry::Occupied(entry) = self.key_down.entry(key){
 			entry.remove();
@@ -164,6 +281,69 @@ impl App{
 	}
 }
 
+///Constructs an empty game state with its random number generators seeded by the given seed
+fn new_game_state(seed: game::seed::Seed) -> game::State<World<cell::ShapeCell>,game::seed::StdRng>{
+	game::State::new(
+		game::seed::std_rng(seed),
+		{fn f(variant: &RotatedShape) -> cell::ShapeCell{
+			cell::ShapeCell(Some(variant.shape()))
+		}f}as fn(&_) -> _,
+		{fn f<W: Grid + grid::RectangularBound>(shape: &RotatedShape,world: &W) -> grid::Pos{grid::Pos{
+			x: world.width() as grid::PosAxis/2 - shape.center_x() as grid::PosAxis,
+			y: 0//TODO: Optionally spawn above: `-(shape.height() as grid::PosAxis);`. Problem is the collision checking. And this is not how it usually is done in other games
+		}}f::<World<cell::ShapeCell>>}as fn(&_,&_) -> _,
+	)
+}
+
+///Constructs an empty world of the given size
+fn new_world(size: grid::Size) -> World<cell::ShapeCell>{
+	World::new(size.x,size.y)
+}
+
+///Plays a replay in the window until it is closed
+///
+///Keys:
+///  Return/Space: Pause/unpause
+///  Right       : Step one tick forward when paused
+///  Up/Down     : Double/halve the playback speed
+///  PageUp/PageDown: Seek 10 seconds backwards/forwards
+///  Home/End    : Seek to the beginning/end
+fn play_replay(mut playback: game::replay::Playback<World<cell::ShapeCell>,game::seed::StdRng>,mut window: Window,mut gl: GlGraphics){
+	let seek_step = (10.0/playback.simulation.timestep()).round() as game::simulation::Tick;
+
+	let mut events = window.events().ups((1.0/playback.simulation.timestep()).round() as u64);
+	while let Some(e) = events.next(&mut window){
+		if let Some(Button::Keyboard(k)) = e.press_args(){match k{
+			Key::Return | Key::Space => {playback.paused = !playback.paused;},
+			Key::Right    if playback.paused => {playback.step(&mut |_|{});},
+			Key::Up       => {let speed = playback.speed();playback.set_speed(speed * 2.0);println!("Replay: Speed {}x",playback.speed());},
+			Key::Down     => {let speed = playback.speed();playback.set_speed(speed / 2.0);println!("Replay: Speed {}x",playback.speed());},
+			Key::PageUp   => {let tick = playback.tick();playback.seek(tick.saturating_sub(seek_step));},
+			Key::PageDown => {let tick = playback.tick();playback.seek(tick + seek_step);},
+			Key::Home     => {playback.seek(0);},
+			Key::End      => {let ticks = playback.replay().ticks;playback.seek(ticks);},
+			_ => {}
+		}}
+
+		if let Some(u) = e.update_args(){
+			playback.update(u.dt,&mut |_|{});
+		}
+
+		if let Some(r) = e.render_args(){
+			if playback.paused || playback.is_finished(){
+				render::default::pause(&mut playback.simulation.state,&mut gl,&r);
+			}else{
+				render::default::gamestate(&mut playback.simulation.state,&mut gl,&r);
+			}
+		}
+	}
+
+	match playback.divergence(){
+		Some(tick) => println!("Replay: First desynchronized tick: {}",tick),
+		None       => println!("Replay: No desynchronization detected up to tick {}",playback.tick()),
+	}
+}
+
 fn main(){
 	let args: cli::Args = match cli::Args_docopt().decode(){
 		Ok(args) => args,
@@ -200,26 +380,64 @@ fn main(){
 		.opengl(args.flag_gl_version.0)
 	).unwrap();
 
+	//Play a replay instead of a new game
+	if let Some(path) = args.flag_replay{
+		let replay = match fs::File::open(&path).map_err(|e| format!("{}",e)).and_then(|mut file| game::replay::Replay::read(&mut file).map_err(|e| format!("{:?}",e))){
+			Ok(replay) => replay,
+			Err(e) => {
+				println!("Error when reading replay {}: {}",path,e);
+				return;
+			}
+		};
+		let seed = replay.seed;
+		println!("Seed: {}",seed);
+
+		play_replay(game::replay::Playback::new(replay,new_game_state(seed),new_world),window,GlGraphics::new(args.flag_gl_version.0));
+		return;
+	}
+
 	let (request_sender,request_receiver) = sync::mpsc::channel();
+	let (network_request_sender,network_request_receiver) = sync::mpsc::channel();
+
+	//Seed for the random number generators
+	let seed = args.flag_seed.unwrap_or_else(game::seed::random);
+	println!("Seed: {}",seed);
+
+	//Settings of the AI players
+	let mut ai_settings = ai::bruteforce::Settings::preset(match args.flag_ai_difficulty{
+		cli::AiDifficulty::beginner => ai::bruteforce::Difficulty::Beginner,
+		cli::AiDifficulty::easy     => ai::bruteforce::Difficulty::Easy,
+		cli::AiDifficulty::medium   => ai::bruteforce::Difficulty::Medium,
+		cli::AiDifficulty::hard     => ai::bruteforce::Difficulty::Hard,
+		cli::AiDifficulty::expert   => ai::bruteforce::Difficulty::Expert,
+	});
+	if let Some(ref path) = args.flag_ai_weights{
+		match fs::File::open(path).map_err(ai::evaluation::Error::Io).and_then(|mut file| ai::evaluation::Weights::read(&mut file)){
+			Ok(weights) => ai_settings.weights = weights,
+			Err(e)      => println!("Error when reading AI weights from {}: {:?}",path,e),
+		}
+	}
+
+	//Worker threads searching for the AI players and the hints
+	let ai_pool = ai::pool::Pool::new(cmp::max(args.flag_ai_threads,1));
 
 	//Create a new application
 	let mut app = App{
 		gl: GlGraphics::new(args.flag_gl_version.0),
-		game_state: game::State::new(
-			rand::StdRng::new().unwrap(),
-			{fn f(variant: &RotatedShape) -> cell::ShapeCell{
-				cell::ShapeCell(Some(variant.shape()))
-			}f}as fn(&_) -> _,
-			{fn f<W: Grid + grid::RectangularBound>(shape: &RotatedShape,world: &W) -> grid::Pos{grid::Pos{
-				x: world.width() as grid::PosAxis/2 - shape.center_x() as grid::PosAxis,
-				y: 0//TODO: Optionally spawn above: `-(shape.height() as grid::PosAxis);`. Problem is the collision checking. And this is not how it usually is done in other games
-			}}f::<World<cell::ShapeCell>>}as fn(&_,&_) -> _,
-		),
-		paused: false,
-		controllers: Vec::new(),
+		game_state: new_game_state(seed),
+		seed: seed,
+		quit: false,
+		tick: 0,
+		replay: None,
+		save_path: args.flag_save_file.clone(),
+		controllers: registry::Registry::new(),
+		ai_settings: ai_settings,
+		ai_pool: ai_pool.clone(),
+		hints: ai::hint::Hints::with_pool(ai_settings,ai_pool),
 		key_map: HashMap::new(),
 		key_down: HashMap::new(),
 		request_receiver: request_receiver,
+		network_request_receiver: network_request_receiver,
 		connection: match args.flag_online{
 			//No connection
 			cli::OnlineConnection::none => online::ConnectionType::None,
@@ -234,9 +452,9 @@ fn main(){
 					args.flag_port
 				);
 
-				match online::client::start(server_addr,request_sender.clone()){
-					Ok(socket) => online::ConnectionType::Client(PairMap::new(),None,socket,server_addr),
-					Err(_)     => online::ConnectionType::None
+				match online::client::start(server_addr,network_request_sender.clone()){
+					Ok((endpoint,connection_id,player_receiver)) => online::ConnectionType::Client(PairMap::new(),Some(connection_id),endpoint,player_receiver),
+					Err(_)                                       => online::ConnectionType::None
 				}
 			},
 
@@ -244,39 +462,77 @@ fn main(){
 			cli::OnlineConnection::server => {
 				let server_addr = net::SocketAddr::new(args.flag_host.0,args.flag_port);
 
-				match online::server::start(server_addr,request_sender.clone()){
-					Ok(_)  => online::ConnectionType::Server(PairMap::new()),
+				//The players of the clients that are gone are removed by local requests, which are not checked like the network requests
+				match online::server::start(server_addr,network_request_sender.clone(),request_sender.clone()){
+					Ok(player_sender) => online::ConnectionType::Server(player_sender),
 					Err(_) => online::ConnectionType::None
 				}
 			}
 		},
 	};
 
-	//Create world
-	app.game_state.data.worlds.insert(0,(World::new(10,20),false));
-	app.game_state.data.worlds.insert(1,(World::new(10,20),false));
-
-	{let App{game_state: ref mut game,controllers: ref mut cs,..} = app;
-		if let online::ConnectionType::None = app.connection{
-			//Create player 0
-			game.rngs.insert_from_global(game::data::mappings::Key::Player(0));
-			game.add_player(0,player::Settings{
+	//Initial worlds and players
+	let settings = game::replay::Settings{
+		timestep: game::simulation::DEFAULT_TIMESTEP,
+		worlds: vec![
+			(0,grid::Size{x: 10,y: 20}),
+			(1,grid::Size{x: 10,y: 20}),
+		],
+		players: if let online::ConnectionType::None = app.connection{vec![
+			(0,player::Settings{
 				gravityfall_frequency: 1.0,
 				fastfall_shadow      : true,
-			},&mut |e| for c in cs.iter_mut(){c.event(&e);});
-
-			//Create player 1
-			game.rngs.insert_from_global(game::data::mappings::Key::Player(1));
-			cs.push(Box::new(ai::bruteforce::Controller::new(//TODO: Controllers shoulld probably be bound to the individual players
-				request_sender.clone(),
-				1,
-				ai::bruteforce::Settings::default()
-			)));
-			game.add_player(1,player::Settings{
+			}),
+			(1,player::Settings{
 				gravityfall_frequency: 1.0,
 				fastfall_shadow      : true,
-			},&mut |e| for c in cs.iter_mut(){c.event(&e);});
+			}),
+		]}else{
+			Vec::new()
+		},
+	};
+
+	{let App{game_state: ref mut game,controllers: ref mut cs,..} = app;
+		if let online::ConnectionType::None = app.connection{
+			//An external bot replaces the built-in AI when specified
+			let bot = args.flag_bot.as_ref().and_then(|command|
+				match controller::external::Controller::new(
+					request_sender.clone(),
+					1,
+					process::Command::new("sh").arg("-c").arg(command),
+					controller::external::Settings::default()
+				){
+					Ok(bot) => Some(bot),
+					Err(e)  => {println!("Error when starting the bot \"{}\": {:?}",command,e);None},
+				}
+			);
+
+			let controller: registry::BoxedController<World<cell::ShapeCell>> = match bot{
+				Some(bot) => Box::new(bot),
+				None => Box::new(ai::bruteforce::Controller::with_pool(
+					request_sender.clone(),
+					1,
+					app.ai_settings,
+					app.seed,
+					app.ai_pool.clone()
+				)),
+			};
+			cs.set(1,Control::Controller(controller));
+
+			//Only the finesse of the player controlled by keys is judged
+			game.finesse.enable(0);
 		}
+
+		settings.setup(
+			game,
+			new_world,
+			&mut |e| cs.event(&e)
+		);
+	}
+
+	//Start recording
+	if args.flag_record.is_some(){
+		app.replay = Some(game::replay::Replay::new(seed,settings));
 	}
 
 	{//Key mappings
@@ -300,7 +556,7 @@ fn main(){
 	}
 
 	//Run the created application: Listen for events
-	let mut events = window.events();
+	let mut events = window.events().ups((1.0/game::simulation::DEFAULT_TIMESTEP).round() as u64);
 	while let Some(e) = events.next(&mut window){
 		//Player inflicted input: Keyboard events
 		if let Some(Button::Keyboard(k)) = e.press_args(){
@@ -313,15 +569,36 @@ fn main(){
 		//Update
 		if let Some(u) = e.update_args(){
 			app.update(&u,&request_sender);
+
+			if app.quit{
+				window.set_should_close(true);
+			}
 		}
 
 		//Render
 		if let Some(r) = e.render_args(){
-			if app.paused{
+			if app.game_state.paused{
 				render::default::pause(&mut app.game_state,&mut app.gl,&r);
 			}else{
 				render::default::gamestate(&mut app.game_state,&mut app.gl,&r);
+				render::default::hints(&mut app.game_state,&app.hints,&mut app.gl,&r);
 			}
 		}
 	}
+
+	//Disconnect from the server
+	if let online::ConnectionType::Client(_,Some(connection_id),ref endpoint,_) = app.connection{
+		online::client::disconnect(endpoint,connection_id);
+	}
+
+	//Save the recorded replay
+	if let (Some(path),Some(replay)) = (args.flag_record,app.replay){
+		match fs::File::create(&path){
+			Ok(mut file) => match replay.write(&mut file){
+				Ok(_)  => println!("Replay saved to {}",path),
+				Err(e) => println!("Error when writing replay to {}: {:?}",path,e),
+			},
+			Err(e) => println!("Error when creating replay file {}: {}",path,e),
+		}
+	}
 }
@@ -2,90 +2,168 @@ pub mod packet;
 
 
 
-use byte_conv::As;
-use core::mem;
-use std::{net,sync,thread};
+use std::{io,net,sync,thread};
 use std::error::Error;
+use std::time::Duration;
 
-use super::{server,Packet};
+use super::{reliable,server,Packet};
+use super::packet::{ConnectionId,Header,PlayerNetworkId,MAX_SIZE};
+use super::reliable::Reliability;
 use ::game::data::{player,PlayerId,WorldId};
 use ::game::Request;
 
-pub fn start(server_addr: net::SocketAddr,request_sender: sync::mpsc::Sender<Request<PlayerId,WorldId>>) -> Result<net::UdpSocket,()>{
+///Connection to the server
+pub type Endpoint = reliable::Endpoint<packet::Data,server::packet::Data>;
+
+///Time between checks for retransmissions when no packets are received
+const POLL_TIME_MS: u64 = 20;
+
+///Time to wait for the server to reply to a connection request before retrying
+const CONNECT_TIME_MS: u64 = 1000;
+
+///Number of times the disconnection is sent
+const DISCONNECT_REPEATS: u8 = 3;
+
+///Connects to the server and starts listening for its packets in a new thread.
+///Returns the connection, its id and the receiver of the network ids of the players created by the server.
+///
+///The player of the client is requested from the server, and is only added to the game when the server has created it.
+///The requests for adding the players created by the server and the requests received from the server are sent through `request_sender`.
+///The network id of a created player is sent before the request for adding it, so that the game can map the network id to the id of the added player (See `ConnectionType::player_added`).
+///
+///The thread stops when the server has not sent anything for a while (See `reliable::Settings::timeout`).
+///The connection is to be dropped when it has timed out (See `reliable::Connection::is_timed_out`), removing the players of the client.
+pub fn start(server_addr: net::SocketAddr,request_sender: sync::mpsc::Sender<Request<PlayerId,WorldId>>) -> Result<(Endpoint,ConnectionId,sync::mpsc::Receiver<PlayerNetworkId>),()>{
 	match net::UdpSocket::bind((net::Ipv4Addr::new(0,0,0,0),0)){
 		Ok(socket) => {
 			println!("Client: Connecting to {}...",server_addr);
+			let endpoint = Endpoint::new(socket,server_addr,reliable::Settings::default());
+
+			//Perform the handshake with the server
+			let connection_id = try!(connect_server(&endpoint,5));
+			println!("Client: Connection established to {} (Id: {})",server_addr,connection_id);
+
+			//Request new player
+			println!("Client: Request new player...");
+			let settings = player::Settings{
+				gravityfall_frequency: 1.0,
+				fastfall_shadow      : true,
+			};
+			if let Err(e) = endpoint.send(
+				packet::Data::Request{
+					connection: connection_id,
+					request: Request::PlayerAdd{
+						settings: settings,
+						world: 1
+					}
+				},
+				Reliability::ReliableOrdered
+			){
+				println!("Client: Error when requesting a new player: {:?}",e);
+				return Err(());
+			}
 
-			//Send connect packet to server
-			try!(connect_server(&socket,server_addr,5));
+			let (player_sender,player_receiver) = sync::mpsc::channel();
 
 			//Listen for packets from server in a new thread
-			{let socket = socket.try_clone().unwrap();thread::spawn(move ||{
-				let mut _buffer: Packet<packet::Data> = unsafe{mem::uninitialized::<Packet<packet::Data>>()};
-				let buffer = unsafe{_buffer.as_bytes_mut()};
-				let mut connected = false;
-				let mut connection_id;
-
-				//For each received packet
-				while let Ok((buffer_size,address)) = socket.recv_from(buffer){
-					if server_addr != address{
-						println!("Client: Not server who sent the packet ({} (Server) != {})",server_addr,address);
-						continue;
-					}
+			{let endpoint = endpoint.try_clone().unwrap();thread::spawn(move ||{
+				//Number of requested players not yet created or denied by the server.
+				//They are the only requests sent before the players exist, so a denied request while waiting for them is the denial of a player.
+				let mut requested_players = 1;
+
+				//One byte larger than the largest packet so that truncated packets are detected
+				let mut buffer = [0; MAX_SIZE + 1];
+
+				endpoint.socket.set_read_timeout(Some(Duration::from_millis(POLL_TIME_MS))).unwrap();
+
+				loop{
+					match endpoint.socket.recv_from(&mut buffer){
+						//For each received packet
+						Ok((buffer_size,address)) => {
+							if endpoint.address != address{
+								println!("Client: Not server who sent the packet ({} (Server) != {})",endpoint.address,address);
+								continue;
+							}
+
+							if buffer_size > MAX_SIZE{
+								println!("Client: Server sent too big of a packet: More than {} bytes",MAX_SIZE);
+								continue;
+							}
+
+							//Deserialize packet
+							let packet = match Packet::deserialize(&buffer[..buffer_size]){
+								Ok(packet) => packet,
+
+								//Received other stuff
+								Err(e) => {
+									println!("Client: Received data but error: {}: {}",e,e.description());
+									if let Ok(header) = Header::deserialize(&buffer[..buffer_size]){
+										let _ = endpoint.send(packet::Data::UnknownPacketResponse{packet: header.packet},Reliability::Unreliable);
+									}
+									continue;
+								}
+							};
+
+							let received = endpoint.connection.lock().unwrap().receive(packet);
+							for data in received{match data{
+								//The reply to the connection request was sent again because the response was lost, which the retransmission of the response takes care of
+								server::packet::Data::ConnectionEstablished{..} => (),
+
+								//Received player input
+								server::packet::Data::PlayerInput{input,..} => {
+									request_sender.send(Request::PlayerInput{input: input,player: 0}).unwrap();
+								},
+
+								//The requested player was created by the server, and is added to the game
+								server::packet::Data::PlayerCreated{player: player_network_id,settings} if requested_players > 0 => {
+									requested_players-= 1;
+									println!("Client: Player created (Network id: {})",player_network_id);
+									player_sender.send(player_network_id).unwrap();
+									request_sender.send(Request::PlayerAdd{settings: settings,world: 1}).unwrap();
+								},
+
+								server::packet::Data::Ack => (),
+
+								server::packet::Data::RequestDeniedReponse{..} if requested_players > 0 => {
+									requested_players-= 1;
+									println!("Client: Request for a new player was denied");
+								},
+								server::packet::Data::RequestDeniedReponse{packet} => println!("Client: Request in packet {} was denied",packet),
+								server::packet::Data::UnknownPacketResponse{packet} => println!("Client: Packet {} was not understood by the server",packet),
+
+								//Received unimplemented TODO stuff
+								data => println!("Client: {:?}",data),
+							}}
+						},
 
-					if buffer_size > buffer.len(){
-						println!("Client: Server sent too big of a packet: {} bytes",buffer_size);
-						continue;
-					}
+						//No packets received in time
+						Err(ref e) if e.kind() == io::ErrorKind::WouldBlock || e.kind() == io::ErrorKind::TimedOut => (),
 
-					//Deserialize packet
-					match Packet::deserialize(buffer){
-						Ok(Packet{data,..}) => match data{
-							//Received connection request established
-							server::packet::Data::ConnectionEstablished{connection} if !connected => {
-								println!("Client: Connection established to {} (Id: {})",address,connection);
-								connected = true;
-								connection_id = connection;
-
-								//Request new player
-								println!("Client: Request new player...");
-								let settings = player::Settings{
-									gravityfall_frequency: 1.0,
-									fastfall_shadow      : true,
-								};
-								socket.send_to(
-									&*packet::Data::Request{
-										connection: connection_id,
-										request: Request::PlayerAdd{
-											settings: settings,
-											world: 1
-										}
-									}.into_packet(0).serialize(),//TODO: Packet id and all other `into_packet`s
-									address
-								).unwrap();
-
-								request_sender.send(Request::PlayerAdd{settings: settings,world: 1}).unwrap();
-							},
-
-							//Received player input
-							server::packet::Data::PlayerInput{input,..} if connected => {
-								request_sender.send(Request::PlayerInput{input: input,player: 0}).unwrap();
-							},
-
-							//Received player add response
-							//server::packet::Data::PlayerCreateResponse{..} if connected => {},
-
-							//Received unimplemented TODO stuff
-							data => println!("Client: {:?} (Connected: {})",data,connected),
-						},
+						Err(e) => {
+							println!("Client: Error when receiving: {:?}",e);
+							break;
+						}
+					}
 
-						//Received other stuff
-						Err(e) => println!("Client: Received data but error: {}: {}",e,e.description())
+					//Retransmit the lost reliable packets, acknowledge received ones and keep the connection alive
+					if let Err(e) = endpoint.retransmit(){
+						println!("Client: Error when retransmitting: {:?}",e);
+					}
+					let (needs_ack,timed_out) = {
+						let connection = endpoint.connection.lock().unwrap();
+						(connection.needs_ack() || connection.needs_keepalive(),connection.is_timed_out())
+					};
+					if timed_out{
+						println!("Client: Connection to {} timed out",endpoint.address);
+						break;
+					}
+					if needs_ack{
+						let _ = endpoint.send(packet::Data::Ack,Reliability::Unreliable);
 					}
 				}
 			});}
 
-			Ok(socket)
+			Ok((endpoint,connection_id,player_receiver))
 		},
 		Err(e) => {
 			println!("Client: Socket error: {:?}",e);
@@ -94,32 +172,66 @@ pub fn start(server_addr: net::SocketAddr,request_sender: sync::mpsc::Sender<Req
 	}
 }
 
-pub fn connect_server(socket: &net::UdpSocket,address: net::SocketAddr,mut retries: u8) -> Result<(),()>{
-	//Send packet with retries
-	loop{match socket.send_to(
-		&*packet::Data::Connect{
-			protocol_version: 1
-		}.into_packet(0).serialize(),//TODO: Packet id and all other `into_packet`s
-		address
-	){
-		Ok(_) => return Ok(()),
-		Err(e) => {use std::io::ErrorKind::*;match e.kind(){
-			Interrupted | TimedOut => {
-				match retries.checked_sub(1){
-					Some(n) => {
-						retries = n;
-						continue;
-					},
-					None => {
-						println!("Client: Error when sending socket: Gave up");
-						return Err(());
-					}
+///Performs the handshake with the server: Sends connection requests until the server replies, and confirms the connection id of the reply.
+///Returns the connection id, or an error when the server refuses the connection or does not reply to any of the requests.
+pub fn connect_server(endpoint: &Endpoint,retries: u8) -> Result<ConnectionId,()>{
+	let mut buffer = [0; MAX_SIZE + 1];
+	if let Err(e) = endpoint.socket.set_read_timeout(Some(Duration::from_millis(CONNECT_TIME_MS))){
+		println!("Client: Socket error: {:?}",e);
+		return Err(());
+	}
+
+	for _ in 0..retries as u16 + 1{
+		//Send connection request. It is sent again when no reply arrives in time, so it is not retransmitted by the connection.
+		if let Err(e) = endpoint.send(packet::Data::Connect{protocol_version: 1},Reliability::Unreliable){
+			println!("Client: {:?} error when sending socket",e.kind());
+			return Err(());
+		}
+
+		//Wait for the reply
+		loop{
+			let packet = match endpoint.socket.recv_from(&mut buffer){
+				Ok((buffer_size,_)) if buffer_size > MAX_SIZE => continue,
+				Ok((buffer_size,address)) if address == endpoint.address => match Packet::deserialize(&buffer[..buffer_size]){
+					Ok(packet) => packet,
+					Err(_)     => continue,
+				},
+				Ok(_) => continue,
+				Err(ref e) if e.kind() == io::ErrorKind::WouldBlock || e.kind() == io::ErrorKind::TimedOut => break,
+				Err(e) => {
+					println!("Client: Error when receiving: {:?}",e);
+					return Err(());
 				}
-			},
-			kind => {
-				println!("Client: {:?} error when sending socket",kind);
-				return Err(());
-			}
-		}}
-	}}
+			};
+
+			let received = endpoint.connection.lock().unwrap().receive(packet);
+			for data in received{match data{
+				server::packet::Data::ConnectionEstablished{connection} => {
+					//Confirm the connection id
+					return endpoint.send(packet::Data::ConnectionEstablishedResponse{connection: connection},Reliability::Reliable)
+						.map(|_| connection)
+						.map_err(|e| println!("Client: {:?} error when sending socket",e.kind()));
+				},
+				server::packet::Data::ConnectionInvalid => {
+					println!("Client: Connection refused by {}",endpoint.address);
+					return Err(());
+				},
+				_ => ()
+			}}
+		}
+	}
+
+	println!("Client: Error when connecting to {}: Gave up",endpoint.address);
+	Err(())
+}
+
+///Disconnects from the server
+pub fn disconnect(endpoint: &Endpoint,connection: ConnectionId){
+	//Not sent reliably because the client stops listening for acknowledgements. Sent multiple times instead, so that the server only has to detect the disconnection by a timeout when all of them are lost.
+	for _ in 0..DISCONNECT_REPEATS{
+		if let Err(e) = endpoint.send(packet::Data::Disconnect{connection: connection},Reliability::Unreliable){
+			println!("Client: Error when disconnecting: {:?}",e);
+			return;
+		}
+	}
 }
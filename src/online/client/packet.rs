@@ -6,6 +6,9 @@ use ::game::Request;
 pub enum Data{
 	Invalid,
 
+	///Sent to acknowledge received packets and to keep the connection alive when there is nothing else to send
+	Ack,
+
 	///Sent when connecting
 	Connect{
 		protocol_version: ProtocolVersion,
@@ -32,14 +35,3 @@ pub enum Data{
 		request: Request<PlayerNetworkId,WorldNetworkId>
 	},
 }
-
-impl Data{
-	#[inline(always)]
-	pub fn into_packet(self,id: Id) -> Packet<Self>{
-		Packet{
-			protocol: ProtocolId,
-			packet: id,
-			data: self
-		}
-	}
-}
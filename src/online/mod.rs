@@ -1,8 +1,10 @@
 //! Online network connection related stuff
 //!
 //! The packets sent are serialized by `serde` and `bincode`
+//! Reliable delivery of packets is handled by `reliable`
 
 pub mod packet;
+pub mod reliable;
 pub mod client;
 pub mod server;
 
@@ -10,15 +12,38 @@ pub use self::packet::Packet;
 
 
 
-use std::net;
+use std::sync;
 
 use self::packet::{ConnectionId,PlayerNetworkId};
 use ::data::PairMap;
-use ::game::data::PlayerId;
+use ::game::data::{PlayerId,WorldId};
 
 pub enum ConnectionType{
-	Server(PairMap<PlayerNetworkId,PlayerId>),
-	Client(PairMap<PlayerNetworkId,PlayerId>,Option<ConnectionId>,net::UdpSocket,net::SocketAddr),
+	///Acting as a server, with the sender of the outcomes of the players requested by the clients (See `server::start`)
+	Server(sync::mpsc::Sender<Option<(PlayerId,WorldId)>>),
+
+	///Acting as a client, with the players of the client by their network ids and the receiver of the network ids of the players created by the server (See `client::start`)
+	Client(PairMap<PlayerNetworkId,PlayerId>,Option<ConnectionId>,client::Endpoint,sync::mpsc::Receiver<PlayerNetworkId>),
+
 	//TODO: PeerToPeer
 	None
 }
+
+impl ConnectionType{
+	///Signals the outcome of a `PlayerAdd` request received over the network: The player and the world it was added to, or None when it could not be added.
+	///The server is told about the players requested by its clients, while the client maps the network ids of the players created by the server to the players added.
+	pub fn player_added(&mut self,player: Option<(PlayerId,WorldId)>){
+		match self{
+			&mut ConnectionType::Server(ref player_sender) => {
+				//The server not listening anymore is not an error
+				let _ = player_sender.send(player);
+			},
+			&mut ConnectionType::Client(ref mut player_ids,_,_,ref player_receiver) => match (player_receiver.try_recv(),player){
+				(Ok(player_network_id),Some((player_id,_))) => {player_ids.insert(player_network_id,player_id);},
+				(Ok(player_network_id),None) => println!("Client: Player {} created by the server could not be added",player_network_id),
+				(Err(_),_) => println!("Client: A player was added without being created by the server"),
+			},
+			&mut ConnectionType::None => ()
+		}
+	}
+}
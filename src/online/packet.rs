@@ -43,12 +43,54 @@ impl Deserialize for ProtocolId{
 	}
 }
 
+///Sequence number of a packet or a reliable message. Wraps around (See `sequence_greater`).
 pub type Id = u16;
 
+///Acknowledgements of the 32 most recent packets up to and including `Header::ack`, bit `i` being set when the packet `ack-i` was received
+pub type AckBits = u32;
+
+///How the data of a packet is delivered
+#[derive(Copy,Clone,Debug,Eq,PartialEq,Serialize,Deserialize)]
+pub enum Delivery{
+	///Delivered at most once, possibly out of order
+	Unreliable,
+
+	///Delivered exactly once, possibly out of order. The message is retransmitted in new packets until acknowledged.
+	Reliable{message: Id},
+
+	///Delivered exactly once and in the order sent relative to the other ordered messages
+	ReliableOrdered{message: Id},
+}
+
+///The beginning of every packet, readable even when the data of the packet is not understood
 #[derive(Copy,Clone,Debug,Serialize,Deserialize)]
-pub struct Packet<Data: Serialize + Deserialize>{
+pub struct Header{
 	pub protocol: ProtocolId,
+
+	///Sequence number of the packet, unique for every packet sent in a connection
 	pub packet: Id,
+
+	///The most recent packet received from the other end
+	pub ack: Id,
+	pub ack_bits: AckBits,
+
+	pub delivery: Delivery,
+}
+
+impl Header{
+	#[inline(always)]
+	pub fn deserialize(bytes: &[u8]) -> Result<Self,::bincode::serde::DeserializeError>{
+		::bincode::serde::deserialize(bytes)
+	}
+}
+
+///Maximum size of a serialized packet
+///Unit: bytes
+pub const MAX_SIZE: usize = 256;
+
+#[derive(Copy,Clone,Debug,Serialize,Deserialize)]
+pub struct Packet<Data: Serialize + Deserialize>{
+	pub header: Header,
 	pub data: Data,
 }
 
@@ -57,7 +99,7 @@ impl<Data> Packet<Data>
 {
 	#[inline(always)]
 	pub fn serialize(&self) -> Vec<u8>{
-		::bincode::serde::serialize(self,::bincode::SizeLimit::Bounded(256)).unwrap()
+		::bincode::serde::serialize(self,::bincode::SizeLimit::Bounded(MAX_SIZE as u64)).unwrap()
 	}
 
 	#[inline(always)]
@@ -66,6 +108,13 @@ impl<Data> Packet<Data>
 	}
 }
 
+///Whether the sequence number `a` is more recent than `b`, accounting for wrap around
+#[inline]
+pub fn sequence_greater(a: Id,b: Id) -> bool{
+	const HALF: Id = 1 << 15;
+	(a > b && a - b <= HALF) || (a < b && b - a > HALF)
+}
+
 pub type ProtocolVersion = u16;
 pub type ConnectionId = u32;
 pub type PlayerNetworkId = u32;
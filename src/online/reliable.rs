@@ -0,0 +1,396 @@
+//!Reliability on top of UDP
+//!
+//!Every packet sent in a connection gets a new sequence number and carries the acknowledgements of the most recently received packets (See `packet::Header`), so that acknowledgements are piggybacked on the regular traffic.
+//!Packets received twice and packets too old to be tracked are dropped.
+//!
+//!Reliable data is identified by a message id, separate from the packet sequence number.
+//!Until a packet containing the message is acknowledged, the message is retransmitted in new packets, and the receiver uses the message id to deliver it only once.
+//!Ordered messages are in addition buffered by the receiver until every message sent before them has been delivered.
+//!
+//!Acknowledgements only cover the 32 most recent packets, so a side that only receives reliable data has to send packets anyway (See `Connection::needs_ack`).
+//!
+//!A connection is considered lost when nothing has been received for a while (See `Settings::timeout`), or when the other end does not acknowledge the reliable messages (See `Settings::max_retransmissions` and `Settings::max_pending`).
+//!To prevent that while idle, packets are to be sent regularly (See `Connection::needs_keepalive`).
+
+use serde::{Serialize,Deserialize};
+use std::collections::BTreeMap;
+use std::time::{Duration,Instant};
+use std::{io,net,sync};
+
+use super::packet::{self,AckBits,Delivery,Header,Id,Packet,ProtocolId};
+
+///Number of sequence numbers remembered, both for the sent packets and for the received packets and messages
+const BUFFER_SIZE: usize = 1024;
+
+///How the data is to be delivered
+#[derive(Copy,Clone,Debug,Eq,PartialEq)]
+pub enum Reliability{
+	Unreliable,
+	Reliable,
+	ReliableOrdered,
+}
+
+#[derive(Copy,Clone,Debug,PartialEq)]
+pub struct Settings{
+	///Time to wait for an acknowledgement before retransmitting a reliable message
+	///Unit: seconds
+	pub retransmit_time: f64,
+
+	///Time without sending anything before a packet should be sent to keep the connection alive
+	///Unit: seconds
+	pub keepalive_time: f64,
+
+	///Time without receiving anything before the connection is considered lost
+	///Unit: seconds
+	pub timeout: f64,
+
+	///Number of times a reliable message is retransmitted before the connection is considered lost
+	pub max_retransmissions: u32,
+
+	///Number of reliable messages waiting for an acknowledgement before the connection is considered lost
+	pub max_pending: usize,
+}
+
+impl Default for Settings{
+	fn default() -> Self{Settings{
+		retransmit_time    : 0.1,
+		keepalive_time     : 0.5,
+		timeout            : 5.0,
+		max_retransmissions: 50,
+		max_pending        : 256,
+	}}
+}
+
+///The most recent sequence numbers and their associated values
+struct SequenceBuffer<T>{
+	entries: Vec<Option<(Id,T)>>,
+
+	///The most recent sequence number inserted
+	latest: Option<Id>,
+}
+
+impl<T> SequenceBuffer<T>{
+	fn new() -> Self{SequenceBuffer{
+		entries: (0..BUFFER_SIZE).map(|_| None).collect(),
+		latest: None,
+	}}
+
+	///Whether the sequence number is too old to be remembered
+	fn is_stale(&self,id: Id) -> bool{
+		match self.latest{
+			Some(latest) => packet::sequence_greater(latest,id) && latest.wrapping_sub(id) as usize >= BUFFER_SIZE,
+			None => false
+		}
+	}
+
+	fn contains(&self,id: Id) -> bool{
+		match self.entries[id as usize % BUFFER_SIZE]{
+			Some((entry_id,_)) => entry_id == id,
+			None => false
+		}
+	}
+
+	///Inserts the value, forgetting the value of the sequence number sharing the same slot
+	fn insert(&mut self,id: Id,value: T){
+		if self.latest.map_or(true,|latest| packet::sequence_greater(id,latest)){
+			self.latest = Some(id);
+		}
+		self.entries[id as usize % BUFFER_SIZE] = Some((id,value));
+	}
+
+	fn remove(&mut self,id: Id) -> Option<T>{
+		if self.contains(id){
+			self.entries[id as usize % BUFFER_SIZE].take().map(|(_,value)| value)
+		}else{
+			None
+		}
+	}
+}
+
+///A reliable message not yet acknowledged
+struct Pending<Out>{
+	delivery: Delivery,
+	data: Out,
+	sent_time: Instant,
+	retransmissions: u32,
+}
+
+///The state of one end of a connection, sending data of type `Out` and receiving data of type `In`
+pub struct Connection<Out,In>
+	where Out: Serialize + Deserialize,
+	      In: Serialize + Deserialize
+{
+	pub settings: Settings,
+
+	///Sequence number of the next packet sent
+	packet_sequence: Id,
+
+	///Id of the next reliable message sent
+	message_sequence: Id,
+
+	///Id of the next ordered message sent
+	ordered_sequence: Id,
+
+	///Packets sent, with the reliable message they contain
+	sent: SequenceBuffer<Delivery>,
+
+	///Reliable messages sent but not yet acknowledged
+	pending: Vec<Pending<Out>>,
+
+	///Packets received
+	received: SequenceBuffer<()>,
+
+	///Unordered reliable messages received
+	received_messages: SequenceBuffer<()>,
+
+	///Id of the next ordered message to deliver
+	next_ordered: Id,
+
+	///Ordered messages received before the ones preceding them, at most `BUFFER_SIZE` messages ahead of `next_ordered`
+	ordered_buffer: BTreeMap<Id,In>,
+
+	///Whether a reliable message has been received since the last packet was sent
+	ack_needed: bool,
+
+	///Whether the other end failed to acknowledge the reliable messages in time
+	failed: bool,
+
+	last_sent: Instant,
+	last_received: Instant,
+}
+
+impl<Out,In> Connection<Out,In>
+	where Out: Serialize + Deserialize + Clone,
+	      In: Serialize + Deserialize
+{
+	pub fn new(settings: Settings) -> Self{Connection{
+		settings: settings,
+		packet_sequence: 0,
+		message_sequence: 0,
+		ordered_sequence: 0,
+		sent: SequenceBuffer::new(),
+		pending: Vec::new(),
+		received: SequenceBuffer::new(),
+		received_messages: SequenceBuffer::new(),
+		next_ordered: 0,
+		ordered_buffer: BTreeMap::new(),
+		ack_needed: false,
+		failed: false,
+		last_sent: Instant::now(),
+		last_received: Instant::now(),
+	}}
+
+	///Constructs the packet to send for the given data
+	pub fn send(&mut self,data: Out,reliability: Reliability) -> Packet<Out>{
+		let delivery = match reliability{
+			Reliability::Unreliable => Delivery::Unreliable,
+			Reliability::Reliable => {
+				let message = self.message_sequence;
+				self.message_sequence = message.wrapping_add(1);
+				Delivery::Reliable{message: message}
+			},
+			Reliability::ReliableOrdered => {
+				let message = self.ordered_sequence;
+				self.ordered_sequence = message.wrapping_add(1);
+				Delivery::ReliableOrdered{message: message}
+			},
+		};
+
+		if delivery != Delivery::Unreliable{
+			if self.pending.len() >= self.settings.max_pending{
+				self.failed = true;
+			}else{
+				self.pending.push(Pending{
+					delivery: delivery,
+					data: data.clone(),
+					sent_time: Instant::now(),
+					retransmissions: 0,
+				});
+			}
+		}
+
+		self.packet(delivery,data)
+	}
+
+	///Constructs the packets retransmitting the reliable messages not acknowledged in time.
+	///Nothing is retransmitted anymore when a message has been retransmitted too many times, the connection being considered lost.
+	pub fn retransmissions(&mut self) -> Vec<Packet<Out>>{
+		let now = Instant::now();
+		let retransmit_time = Duration::new(self.settings.retransmit_time as u64,(self.settings.retransmit_time.fract() * 1e9) as u32);
+
+		if self.failed || self.pending.iter().any(|pending| pending.retransmissions >= self.settings.max_retransmissions && now.duration_since(pending.sent_time) >= retransmit_time){
+			self.failed = true;
+			return Vec::new();
+		}
+
+		let messages: Vec<(Delivery,Out)> = self.pending.iter_mut()
+			.filter(|pending| now.duration_since(pending.sent_time) >= retransmit_time)
+			.map(|pending|{
+				pending.sent_time = now;
+				pending.retransmissions+= 1;
+				(pending.delivery,pending.data.clone())
+			})
+			.collect();
+
+		messages.into_iter().map(|(delivery,data)| self.packet(delivery,data)).collect()
+	}
+
+	///Whether reliable messages have been received since the last packet was sent.
+	///A packet should be sent (e.g. with data acknowledging packets) when nothing else is to be sent, so that the other end stops retransmitting them.
+	#[inline]
+	pub fn needs_ack(&self) -> bool{self.ack_needed}
+
+	///Whether nothing has been sent for a while, and a packet should be sent to keep the connection alive
+	#[inline]
+	pub fn needs_keepalive(&self) -> bool{seconds_since(self.last_sent) >= self.settings.keepalive_time}
+
+	///Whether nothing has been received for a while or the reliable messages were not acknowledged in time, and the connection should be considered lost
+	#[inline]
+	pub fn is_timed_out(&self) -> bool{self.failed || seconds_since(self.last_received) >= self.settings.timeout}
+
+	///Number of reliable messages sent but not yet acknowledged
+	#[inline]
+	pub fn pending(&self) -> usize{self.pending.len()}
+
+	///Handles a received packet, returning the data to be delivered.
+	///Nothing is returned for duplicates, and multiple messages can be returned when an ordered message that was waited for arrives.
+	pub fn receive(&mut self,packet: Packet<In>) -> Vec<In>{
+		let Packet{header,data} = packet;
+
+		//Duplicate or too old packets
+		if self.received.contains(header.packet) || self.received.is_stale(header.packet){
+			return Vec::new();
+		}
+
+		//Ordered messages too far ahead to be buffered. The packet is not acknowledged, so the message is retransmitted later.
+		if let Delivery::ReliableOrdered{message} = header.delivery{
+			if packet::sequence_greater(message,self.next_ordered) && message.wrapping_sub(self.next_ordered) as usize > BUFFER_SIZE{
+				return Vec::new();
+			}
+		}
+
+		self.received.insert(header.packet,());
+		self.last_received = Instant::now();
+
+		//Acknowledgements of sent packets
+		for i in 0..32{
+			if (header.ack_bits >> i) & 1 == 1{
+				if let Some(delivery) = self.sent.remove(header.ack.wrapping_sub(i)){
+					self.pending.retain(|pending| pending.delivery != delivery);
+				}
+			}
+		}
+
+		match header.delivery{
+			Delivery::Unreliable => vec![data],
+
+			Delivery::Reliable{message} => {
+				self.ack_needed = true;
+
+				if self.received_messages.contains(message) || self.received_messages.is_stale(message){
+					Vec::new()
+				}else{
+					self.received_messages.insert(message,());
+					vec![data]
+				}
+			},
+
+			Delivery::ReliableOrdered{message} => {
+				self.ack_needed = true;
+
+				if message == self.next_ordered{
+					//Deliver the message and the buffered ones following it
+					let mut delivered = vec![data];
+					self.next_ordered = self.next_ordered.wrapping_add(1);
+					while let Some(data) = self.ordered_buffer.remove(&self.next_ordered){
+						delivered.push(data);
+						self.next_ordered = self.next_ordered.wrapping_add(1);
+					}
+					delivered
+				}else{
+					//Messages preceding the next one have already been delivered
+					if packet::sequence_greater(message,self.next_ordered){
+						self.ordered_buffer.insert(message,data);
+					}
+					Vec::new()
+				}
+			},
+		}
+	}
+
+	///Constructs a new packet with the acknowledgements of the received packets
+	fn packet(&mut self,delivery: Delivery,data: Out) -> Packet<Out>{
+		let id = self.packet_sequence;
+		self.packet_sequence = id.wrapping_add(1);
+		self.sent.insert(id,delivery);
+		self.ack_needed = false;
+		self.last_sent = Instant::now();
+
+		let (ack,ack_bits) = match self.received.latest{
+			Some(ack) => (ack,(0..32).fold(0 as AckBits,|bits,i| if self.received.contains(ack.wrapping_sub(i)){bits | (1 << i)}else{bits})),
+			None => (0,0),
+		};
+
+		Packet{
+			header: Header{
+				protocol: ProtocolId,
+				packet: id,
+				ack: ack,
+				ack_bits: ack_bits,
+				delivery: delivery,
+			},
+			data: data,
+		}
+	}
+}
+
+///Time elapsed since the given instant
+///Unit: seconds
+fn seconds_since(instant: Instant) -> f64{
+	let elapsed = instant.elapsed();
+	elapsed.as_secs() as f64 + elapsed.subsec_nanos() as f64 * 1e-9
+}
+
+///A connection to a single address, shared between the threads sending to it
+pub struct Endpoint<Out,In>
+	where Out: Serialize + Deserialize,
+	      In: Serialize + Deserialize
+{
+	pub socket: net::UdpSocket,
+	pub address: net::SocketAddr,
+	pub connection: sync::Arc<sync::Mutex<Connection<Out,In>>>,
+}
+
+impl<Out,In> Endpoint<Out,In>
+	where Out: Serialize + Deserialize + Clone,
+	      In: Serialize + Deserialize
+{
+	pub fn new(socket: net::UdpSocket,address: net::SocketAddr,settings: Settings) -> Self{Endpoint{
+		socket: socket,
+		address: address,
+		connection: sync::Arc::new(sync::Mutex::new(Connection::new(settings))),
+	}}
+
+	pub fn try_clone(&self) -> io::Result<Self>{
+		Ok(Endpoint{
+			socket: try!(self.socket.try_clone()),
+			address: self.address,
+			connection: self.connection.clone(),
+		})
+	}
+
+	///Sends the data
+	pub fn send(&self,data: Out,reliability: Reliability) -> io::Result<()>{
+		let packet = self.connection.lock().unwrap().send(data,reliability);
+		self.socket.send_to(&*packet.serialize(),self.address).map(|_| ())
+	}
+
+	///Retransmits the reliable messages not acknowledged in time
+	pub fn retransmit(&self) -> io::Result<()>{
+		let packets = self.connection.lock().unwrap().retransmissions();
+		for packet in packets{
+			try!(self.socket.send_to(&*packet.serialize(),self.address));
+		}
+		Ok(())
+	}
+}
@@ -2,87 +2,291 @@ pub mod packet;
 
 
 
-use byte_conv::As;
-use core::mem;
 use rand::{Rng,StdRng};
-use std::{net,sync,thread};
+use std::{io,net,sync,thread};
+use std::collections::{HashMap,VecDeque};
 use std::error::Error;
+use std::time::Duration;
 
-use super::{client,Packet};
+use super::{client,reliable,Packet};
+use super::packet::{ConnectionId,Header,Id,PlayerNetworkId,MAX_SIZE};
+use super::reliable::Reliability;
+use ::data::PairMap;
 use ::game::Request;
-use ::game::data::{WorldId,PlayerId};
+use ::game::data::{player,WorldId,PlayerId};
+
+///Connection to a client
+pub type Connection = reliable::Connection<packet::Data,client::packet::Data>;
+
+///Time between checks for retransmissions when no packets are received
+const POLL_TIME_MS: u64 = 20;
+
+///A client connected or connecting to the server
+struct Client{
+	connection: Connection,
+	id: ConnectionId,
+
+	///Whether the client has confirmed the connection id (The last step of the handshake)
+	established: bool,
+
+	///Network ids of the players added by the client
+	players: Vec<PlayerNetworkId>,
+}
+
+///A player requested by a client, waiting for the game to add it
+struct PendingPlayer{
+	address: net::SocketAddr,
+	connection: ConnectionId,
+
+	///The packet containing the request
+	packet: Id,
+
+	settings: player::Settings,
+}
+
+///The clients of the server and the packets to send to them.
+///
+///A client connects by a three-way handshake: `Connect` from the client, `ConnectionEstablished` from the server with the id of the connection, and `ConnectionEstablishedResponse` from the client confirming the id.
+///Requests are only accepted from clients that have completed the handshake.
+///The clients refer to their players by network ids, which the server maps to the ids of the players in the game. A client can only send inputs to its own players.
+///The players requested by the clients are added by the game, which is to signal the outcome of every requested player in the order requested (See `Server::player_added`).
+///Clients are forgotten when they disconnect or when they have not sent anything for a while (See `reliable::Settings::timeout`), and their players are removed from the game.
+///
+///The requests of the clients are sent through `request_sender`, to be checked by the game before being performed (See `Registry::accepts_network_request`).
+///The requests of the server itself, removing the players of the clients that are gone, are sent through `server_request_sender` and are always to be performed.
+pub struct Server{
+	pub settings: reliable::Settings,
+	clients: HashMap<net::SocketAddr,Client>,
+	connection_id_gen: StdRng,
+	request_sender: sync::mpsc::Sender<Request<PlayerId,WorldId>>,
+	server_request_sender: sync::mpsc::Sender<Request<PlayerId,WorldId>>,
+
+	///Players added by the clients, by their network ids
+	players: PairMap<PlayerNetworkId,PlayerId>,
+
+	///Network id of the next player added
+	next_player_network_id: PlayerNetworkId,
+
+	///Players requested by the clients, in the order requested
+	pending_players: VecDeque<PendingPlayer>,
+
+	///Packets to be sent and their destinations
+	outgoing: Vec<(net::SocketAddr,Packet<packet::Data>)>,
+}
+
+impl Server{
+	pub fn new(settings: reliable::Settings,request_sender: sync::mpsc::Sender<Request<PlayerId,WorldId>>,server_request_sender: sync::mpsc::Sender<Request<PlayerId,WorldId>>) -> io::Result<Self>{
+		Ok(Server{
+			settings: settings,
+			clients: HashMap::new(),
+			connection_id_gen: try!(StdRng::new()),
+			request_sender: request_sender,
+			server_request_sender: server_request_sender,
+			players: PairMap::new(),
+			next_player_network_id: 0,
+			pending_players: VecDeque::new(),
+			outgoing: Vec::new(),
+		})
+	}
+
+	///Returns whether the client at the given address has completed the handshake
+	pub fn is_established(&self,address: &net::SocketAddr) -> bool{
+		self.clients.get(address).map_or(false,|client| client.established)
+	}
+
+	///Returns whether the client at the given address is connected or connecting
+	#[inline]
+	pub fn is_connected(&self,address: &net::SocketAddr) -> bool{
+		self.clients.contains_key(address)
+	}
+
+	///Handles a packet received from the given address
+	pub fn receive(&mut self,address: net::SocketAddr,packet: Packet<client::packet::Data>){
+		//Recevied connection request from a new client
+		if let client::packet::Data::Connect{protocol_version} = packet.data{
+			if !self.clients.contains_key(&address){
+				print!("Server: Connection request from {}... ",address);
+				match protocol_version{
+					1 => {
+						let connection_id = self.connection_id_gen.gen::<u32>();
+						println!("OK (As id: {})",connection_id);
+						self.clients.insert(address,Client{
+							connection: Connection::new(self.settings),
+							id: connection_id,
+							established: false,
+							players: Vec::new(),
+						});
+					},
+
+					version => {
+						println!("Server: Invalid version: {}",version);
+						let mut connection = Connection::new(self.settings);
+						send(&mut self.outgoing,address,&mut connection,packet::Data::ConnectionInvalid,Reliability::Unreliable);
+						return;
+					}
+				}
+			}
+		}
+
+		let disconnected = match self.clients.get_mut(&address){
+			Some(client) => handle(&mut self.outgoing,&mut self.pending_players,&self.players,address,client,packet,&self.request_sender),
+
+			//The disconnection is sent multiple times, so the client is already forgotten when the other ones arrive
+			None => {
+				match packet.data{
+					client::packet::Data::Disconnect{..} => (),
+					_ => println!("Server: Packet from unknown client {}",address)
+				}
+				false
+			}
+		};
+		if disconnected{
+			println!("Server: {} disconnected",address);
+			let client = self.clients.remove(&address).unwrap();
+			remove_players(client.players,&mut self.players,&self.server_request_sender);
+		}
+	}
+
+	///Signals the outcome of the oldest player requested by a client: The player and the world it was added to, or None when it could not be added.
+	///The player is removed together with the other players of the client when the client is no longer connected.
+	pub fn player_added(&mut self,player: Option<(PlayerId,WorldId)>){
+		let pending = match self.pending_players.pop_front(){
+			Some(pending) => pending,
+			None => {
+				println!("Server: A player was added without being requested");
+				return;
+			}
+		};
+
+		//The client disconnected before its player was added
+		if !self.clients.get(&pending.address).map_or(false,|client| client.id == pending.connection){
+			if let Some((player_id,_)) = player{
+				let _ = self.server_request_sender.send(Request::PlayerRemove{player: player_id});
+			}
+			return;
+		}
+
+		let client = self.clients.get_mut(&pending.address).unwrap();
+		match player{
+			Some((player_id,_)) => {
+				let player_network_id = self.next_player_network_id;
+				self.next_player_network_id = player_network_id.wrapping_add(1);
+				self.players.insert(player_network_id,player_id);
+				client.players.push(player_network_id);
+
+				send(&mut self.outgoing,pending.address,&mut client.connection,packet::Data::PlayerCreated{
+					player: player_network_id,
+					settings: pending.settings,
+				},Reliability::ReliableOrdered);
+			},
+			None => {
+				send(&mut self.outgoing,pending.address,&mut client.connection,packet::Data::RequestDeniedReponse{packet: pending.packet},Reliability::Unreliable);
+			}
+		}
+	}
+
+	///Replies to a packet from the given address that was not understood
+	pub fn receive_unknown(&mut self,address: net::SocketAddr,header: Header){
+		if let Some(client) = self.clients.get_mut(&address){
+			send(&mut self.outgoing,address,&mut client.connection,packet::Data::UnknownPacketResponse{packet: header.packet},Reliability::Unreliable);
+		}
+	}
+
+	///Forgets the clients that have not sent anything for a while, retransmits the lost reliable packets, acknowledges received ones and keeps the connections alive
+	pub fn update(&mut self){
+		let timed_out: Vec<net::SocketAddr> = self.clients.iter()
+			.filter(|&(_,client)| client.connection.is_timed_out())
+			.map(|(&address,_)| address)
+			.collect();
+		for address in timed_out{
+			println!("Server: {} timed out",address);
+			let client = self.clients.remove(&address).unwrap();
+			remove_players(client.players,&mut self.players,&self.server_request_sender);
+		}
+
+		for (&address,client) in self.clients.iter_mut(){
+			for packet in client.connection.retransmissions(){
+				self.outgoing.push((address,packet));
+			}
+			if client.connection.needs_ack() || client.connection.needs_keepalive(){
+				send(&mut self.outgoing,address,&mut client.connection,packet::Data::Ack,Reliability::Unreliable);
+			}
+		}
+	}
+
+	///Takes the packets to be sent and their destinations
+	pub fn outgoing(&mut self) -> Vec<(net::SocketAddr,Packet<packet::Data>)>{
+		self.outgoing.drain(..).collect()
+	}
+}
+
+///Starts listening for clients in a new thread (See `Server`)
+///Returns the sender of the outcomes of the players requested by the clients (See `Server::player_added`).
+pub fn start(host_addr: net::SocketAddr,request_sender: sync::mpsc::Sender<Request<PlayerId,WorldId>>,server_request_sender: sync::mpsc::Sender<Request<PlayerId,WorldId>>) -> Result<sync::mpsc::Sender<Option<(PlayerId,WorldId)>>,()>{
+	let mut server = match Server::new(reliable::Settings::default(),request_sender,server_request_sender){
+		Ok(server) => server,
+		Err(e) => {
+			println!("Server: Error when seeding the connection ids: {:?}",e);
+			return Err(());
+		}
+	};
 
-pub fn start(host_addr: net::SocketAddr,request_sender: sync::mpsc::Sender<Request<PlayerId,WorldId>>) -> Result<(),()>{
 	match net::UdpSocket::bind(host_addr){
 		Ok(socket) => {
 			println!("Server: Listening on {}...",host_addr);
+			let (player_sender,player_receiver) = sync::mpsc::channel();
 
 			//Listen for packets from clients in a new thread
 			thread::spawn(move ||{
-				let mut _buffer: Packet<packet::Data> = unsafe{mem::uninitialized()};
-				let mut buffer = unsafe{_buffer.as_bytes_mut()};
-				let mut connection_id_gen = StdRng::new().unwrap();
-
-				//For each received packet
-				while let Ok((buffer_size,address)) = socket.recv_from(buffer){
-					if buffer_size > buffer.len(){
-						println!("Server: Client sent too big of a packet: {} bytes",buffer_size);
-						continue;
-					}
+				//One byte larger than the largest packet so that truncated packets are detected
+				let mut buffer = [0; MAX_SIZE + 1];
 
-					//Deserialize packet
-					match Packet::deserialize(buffer){
-						Ok(Packet{data,..}) => match data{
-							//Recevied connection request
-							client::packet::Data::Connect{protocol_version} => {
-								print!("Server: Connection request from {}... ",address);
-								match protocol_version{
-									1 => {
-										let connection_id = connection_id_gen.gen::<u32>();
-										println!("OK (As id: {})",connection_id);
-										socket.send_to(
-											&*packet::Data::ConnectionEstablished{
-												connection: connection_id
-											}.into_packet(0).serialize(),
-											address
-										).unwrap();
-									},
-
-									version => {
-										println!("Server: Invalid version: {}",version);
-										socket.send_to(&*packet::Data::ConnectionInvalid.into_packet(0).serialize(),address).unwrap();
+				socket.set_read_timeout(Some(Duration::from_millis(POLL_TIME_MS))).unwrap();
+
+				loop{
+					match socket.recv_from(&mut buffer){
+						//For each received packet
+						Ok((buffer_size,address)) => {
+							if buffer_size > MAX_SIZE{
+								println!("Server: {} sent too big of a packet: More than {} bytes",address,MAX_SIZE);
+								continue;
+							}
+
+							//Deserialize packet
+							match Packet::deserialize(&buffer[..buffer_size]){
+								Ok(packet) => server.receive(address,packet),
+
+								//Received other stuff
+								Err(e) => {
+									println!("Server: Received data but error: {}: {}",e,e.description());
+									if let Ok(header) = Header::deserialize(&buffer[..buffer_size]){
+										server.receive_unknown(address,header);
 									}
 								}
-							},
-
-							//Received player input
-							client::packet::Data::Request{request: Request::PlayerInput{input,..},..} => {//TODO: request::Player.map_player
-								request_sender.send(Request::PlayerInput{input: input,player: 0}).unwrap();
-							},
-
-							//Received player add reqeust
-							client::packet::Data::Request{request: Request::PlayerAdd{settings,..},..} => {
-								request_sender.send(Request::PlayerAdd{settings: settings,world: 1}).unwrap();
-
-								socket.send_to(
-									&*packet::Data::PlayerCreated{
-										player: 0,
-										settings: settings,
-									}.into_packet(0).serialize(),
-									address
-								).unwrap();
-							},
-
-							//Received unimplemented TODO stuff
-							data => println!("Server: {:?}",data),
+							}
 						},
 
-						//Received other stuff
-						Err(e) => println!("Server: Received data but error: {}: {}",e,e.description())
+						//No packets received in time
+						Err(ref e) if e.kind() == io::ErrorKind::WouldBlock || e.kind() == io::ErrorKind::TimedOut => (),
+
+						Err(e) => {
+							println!("Server: Error when receiving: {:?}",e);
+							break;
+						}
+					}
+
+					while let Ok(player) = player_receiver.try_recv(){
+						server.player_added(player);
+					}
+					server.update();
+					for (address,packet) in server.outgoing(){
+						if let Err(e) = socket.send_to(&*packet.serialize(),address){
+							println!("Server: Error when sending to {}: {:?}",address,e);
+						}
 					}
 				}
 			});
-			Ok(())
+			Ok(player_sender)
 		},
 		Err(e) => {
 			println!("Server:  Socket error: {:?}",e);
@@ -90,3 +294,94 @@ pub fn start(host_addr: net::SocketAddr,request_sender: sync::mpsc::Sender<Reque
 		}
 	}
 }
+
+///Handles a packet from a known client
+///Returns whether the client disconnected
+fn handle(outgoing: &mut Vec<(net::SocketAddr,Packet<packet::Data>)>,pending_players: &mut VecDeque<PendingPlayer>,players: &PairMap<PlayerNetworkId,PlayerId>,address: net::SocketAddr,client: &mut Client,packet: Packet<client::packet::Data>,request_sender: &sync::mpsc::Sender<Request<PlayerId,WorldId>>) -> bool{
+	let packet_id = packet.header.packet;
+
+	for data in client.connection.receive(packet){
+		//The confirmation of the connection id can arrive after requests containing the id, which completes the handshake as well
+		match data{
+			client::packet::Data::ConnectionEstablishedResponse{connection} |
+			client::packet::Data::Request{connection,..} if connection == client.id && !client.established => {
+				println!("Server: Connection established to {} (Id: {})",address,connection);
+				client.established = true;
+			},
+			_ => ()
+		}
+
+		match data{
+			//Received connection request. Sent again by the client when the reply is lost.
+			client::packet::Data::Connect{..} => {
+				send(outgoing,address,&mut client.connection,packet::Data::ConnectionEstablished{
+					connection: client.id
+				},Reliability::Unreliable);
+			},
+
+			//Received confirmation of the connection id
+			client::packet::Data::ConnectionEstablishedResponse{connection} => {
+				if connection != client.id{
+					println!("Server: Connection id mismatch from {}: {} != {}",address,connection,client.id);
+				}
+			},
+
+			//Received disconnection
+			client::packet::Data::Disconnect{connection} if connection == client.id => return true,
+
+			//Received request with the wrong id
+			client::packet::Data::Request{connection,..} if connection != client.id => {
+				send(outgoing,address,&mut client.connection,packet::Data::RequestDeniedReponse{packet: packet_id},Reliability::Unreliable);
+			},
+
+			//Received player input, only accepted for the players of the client
+			client::packet::Data::Request{request: Request::PlayerInput{input,player: player_network_id},..} => match players.get(player_network_id){
+				Some(player_id) if client.players.contains(&player_network_id) => {
+					request_sender.send(Request::PlayerInput{input: input,player: player_id}).unwrap();
+				},
+				_ => send(outgoing,address,&mut client.connection,packet::Data::RequestDeniedReponse{packet: packet_id},Reliability::Unreliable),
+			},
+
+			//Received player add reqeust. The player is created when the game has added it (See `Server::player_added`).
+			//The players of the clients share world 1, which is set up by the game and is kept when the clients leave.
+			client::packet::Data::Request{request: Request::PlayerAdd{settings,..},..} => {
+				request_sender.send(Request::PlayerAdd{settings: settings,world: 1}).unwrap();
+				pending_players.push_back(PendingPlayer{
+					address: address,
+					connection: client.id,
+					packet: packet_id,
+					settings: settings,
+				});
+			},
+
+			//Received other requests, which are not supported
+			client::packet::Data::Request{..} => {
+				send(outgoing,address,&mut client.connection,packet::Data::RequestDeniedReponse{packet: packet_id},Reliability::Unreliable);
+			},
+
+			client::packet::Data::Ack => (),
+
+			client::packet::Data::UnknownPacketResponse{packet} => println!("Server: Packet {} was not understood by {}",packet,address),
+
+			//Received unimplemented TODO stuff
+			data => println!("Server: {:?}",data),
+		}
+	}
+
+	false
+}
+
+///Removes the players with the given network ids of a client that is no longer connected
+fn remove_players(player_network_ids: Vec<PlayerNetworkId>,players: &mut PairMap<PlayerNetworkId,PlayerId>,server_request_sender: &sync::mpsc::Sender<Request<PlayerId,WorldId>>){
+	for player_network_id in player_network_ids{
+		if let Some(player_id) = players.remove(player_network_id){
+			//The game not listening for requests anymore is not an error
+			let _ = server_request_sender.send(Request::PlayerRemove{player: player_id});
+		}
+	}
+}
+
+///Queues the data to be sent to the client at the given address
+fn send(outgoing: &mut Vec<(net::SocketAddr,Packet<packet::Data>)>,address: net::SocketAddr,connection: &mut Connection,data: packet::Data,reliability: Reliability){
+	outgoing.push((address,connection.send(data,reliability)));
+}
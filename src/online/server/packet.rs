@@ -7,6 +7,9 @@ use ::game::data::{player,Input};
 pub enum Data{
 	Invalid,
 
+	///Sent to acknowledge received packets and to keep the connection alive when there is nothing else to send
+	Ack,
+
 	///Sent when connecting and the connection is OK
 	ConnectionEstablished{
 		connection: ConnectionId,
@@ -48,14 +51,3 @@ pub enum Data{
 		shape : Shape//TODO: Rotation?
 	},
 }
-
-impl Data{
-	#[inline(always)]
-	pub fn into_packet(self,id: Id) -> Packet<Self>{
-		Packet{
-			protocol: ProtocolId,
-			packet: id,
-			data: self
-		}
-	}
-}
@@ -6,11 +6,18 @@ pub mod default{
 	use opengl_graphics::GlGraphics;
 	use piston::input::RenderArgs;
 
+	use ::controller::ai::hint::Hints;
 	use ::data::{cell,colors,grid};
 	use ::data::shapes::tetromino::Shape;
 	use ::game::data::World;
 	use ::game;
 
+	const BLOCK_PIXEL_SIZE: f64 = 24.0;
+
+	fn world_render_pos(world_no: usize) -> (f64,f64){
+		(world_no as f64 * 12.0 * BLOCK_PIXEL_SIZE,0.0)
+	}
+
 	///Renders the pause state
 	pub fn pause<W,Rng>(state: &mut game::State<W,Rng>,gl: &mut GlGraphics,args: &RenderArgs)
 		where W: World<Cell = cell::ShapeCell>
@@ -28,12 +35,6 @@ pub mod default{
 	pub fn gamestate<W,Rng>(state: &mut game::State<W,Rng>,gl: &mut GlGraphics,args: &RenderArgs)
 		where W: World<Cell = cell::ShapeCell>
 	{
-		const BLOCK_PIXEL_SIZE: f64 = 24.0;
-
-		fn world_render_pos(world_no: usize) -> (f64,f64){
-			(world_no as f64 * 12.0 * BLOCK_PIXEL_SIZE,0.0)
-		}
-
 		//Unit square
 		let square = graphics::rectangle::square(0.0,0.0,BLOCK_PIXEL_SIZE);
 
@@ -115,4 +116,31 @@ pub mod default{
 			}}
 		});
 	}
+
+	///Renders the hints as outlines of the suggested placements on top of the game state (See `controller::ai::hint`)
+	pub fn hints<W,Rng>(state: &mut game::State<W,Rng>,hints: &Hints,gl: &mut GlGraphics,args: &RenderArgs)
+		where W: World<Cell = cell::ShapeCell>
+	{
+		let square = graphics::rectangle::square(0.0,0.0,BLOCK_PIXEL_SIZE);
+		let outline = graphics::Rectangle::new_border(colors::WHITE,1.0);
+
+		gl.draw(args.viewport(),|context,gl|{
+			for (player_id,(shape,pos)) in hints.iter(){match state.data.players.get(player_id as usize){
+				Some(player) if state.data.worlds.contains_key(player.world as usize) => {
+					let transform = {
+						let (x,y) = world_render_pos(player.world as usize);
+						context.transform.trans(x,y)
+					};
+
+					for (cell_pos,cell) in grid::cells_iter::Iter::new(&shape){
+						if cell{
+							let transform = transform.trans((cell_pos.x as grid::PosAxis + pos.x) as f64 * BLOCK_PIXEL_SIZE, (cell_pos.y as grid::PosAxis + pos.y) as f64 * BLOCK_PIXEL_SIZE);
+							outline.draw(square,&context.draw_state,transform,gl);
+						}
+					}
+				},
+				_ => ()
+			}}
+		});
+	}
 }
@@ -0,0 +1,95 @@
+extern crate tetr;
+
+mod common;
+
+use std::sync;
+
+use tetr::controller::Controller as ControllerTrait;
+use tetr::controller::ai::{bruteforce,pool};
+use tetr::game::{pathfinder,Event,Request};
+use tetr::game::data::{Input,PlayerId,WorldId};
+use tetr::game::data::world::dynamic::World;
+
+fn new_controller(request_sender: sync::mpsc::Sender<Request<PlayerId,WorldId>>,pool: pool::Pool) -> Box<ControllerTrait<World<bool>,Event<(PlayerId,WorldId),WorldId>>>{
+	Box::new(bruteforce::Controller::with_pool(request_sender,0,bruteforce::Settings{
+		move_time  : 0.0,
+		fall_time  : 0.0,
+		rotate_time: 0.0,
+		..bruteforce::Settings::default()
+	},0,pool))
+}
+
+///Performs a search like the workers of a pool
+fn perform(job: pool::Job){
+	let targets = bruteforce::search_targets(&job.world,job.shape,job.pos,job.lookahead,&job.settings);
+	job.result_sender.send((job.generation,targets)).unwrap();
+}
+
+///Completes a job with the placement furthest to the left or to the right, without rotating the shape
+fn complete_sideways(job: pool::Job,left: bool){
+	let rotation = job.shape.rotation();
+	let placements = pathfinder::placements(&job.world,job.shape,job.pos).into_iter().filter(|placement| placement.shape.rotation() == rotation);
+	let placement = if left{
+		placements.min_by_key(|placement| placement.pos.x)
+	}else{
+		placements.max_by_key(|placement| placement.pos.x)
+	}.unwrap();
+	job.result_sender.send((job.generation,vec![(placement.pos,rotation)])).unwrap();
+}
+
+#[test]
+fn threaded_search_places_shapes(){
+	let (request_sender,request_receiver) = sync::mpsc::channel();
+	let (pool,job_receiver) = pool::Pool::manual();
+	let mut controller = new_controller(request_sender,pool);
+	let mut simulation = common::new_simulation(&mut |e| controller.event(&e));
+
+	//Every shape is placed without losing
+	let mut imprints = 0;
+	for _ in 0..5000{
+		controller.update(simulation.timestep(),&simulation.state.data);
+		while let Ok(job) = job_receiver.try_recv(){
+			perform(job);
+		}
+		simulation.receive(&request_receiver);
+		simulation.step(&mut |e|{
+			if let Event::WorldImprintedShape{..} = e{
+				imprints+= 1;
+			}
+			controller.event(&e);
+		});
+
+		assert!(!simulation.state.data.worlds.get(0).map_or(true,|&(_,paused)| paused));
+		if imprints >= 10{
+			return;
+		}
+	}
+	panic!("Only {} shapes were placed",imprints);
+}
+
+#[test]
+fn results_of_previous_generations_are_discarded(){
+	let (request_sender,request_receiver) = sync::mpsc::channel();
+	let (pool,job_receiver) = pool::Pool::manual();
+	let mut controller = new_controller(request_sender,pool);
+	let simulation = common::new_simulation(&mut |e| controller.event(&e));
+
+	//No inputs are performed while searching
+	controller.update(simulation.timestep(),&simulation.state.data);
+	let old_job = job_receiver.try_recv().unwrap();
+	assert!(request_receiver.try_recv().is_err());
+
+	//The world changing starts a new generation
+	controller.event(&Event::WorldUpdated{world: 0});
+	controller.update(simulation.timestep(),&simulation.state.data);
+	let new_job = job_receiver.try_recv().unwrap();
+	assert!(job_receiver.try_recv().is_err());
+	assert!(old_job.generation != new_job.generation);
+
+	//The result of the old job arrives last, and is discarded
+	complete_sideways(new_job,false);
+	complete_sideways(old_job,true);
+	controller.update(simulation.timestep(),&simulation.state.data);
+	assert_eq!(request_receiver.try_recv(),Ok(Request::PlayerInput{input: Input::MoveRight,player: 0}));
+	assert!(job_receiver.try_recv().is_err());
+}
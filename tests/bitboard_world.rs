@@ -0,0 +1,126 @@
+extern crate tetr;
+
+use tetr::controller::ai::{bruteforce,perfect_clear};
+use tetr::controller::ai::evaluation::{self,Weights};
+use tetr::data::grid::{self,Grid,RectangularBound};
+use tetr::data::shapes::tetromino::{RotatedShape,Shape};
+use tetr::game::data::World as WorldTrait;
+use tetr::game::data::world::{bitboard,dynamic,CellIntersection};
+
+const SHAPES: [Shape; 7] = [Shape::I,Shape::L,Shape::O,Shape::J,Shape::T,Shape::S,Shape::Z];
+
+fn fill<W>(mut world: W) -> W
+	where W: WorldTrait + Grid<Cell = bool>
+{
+	const ROWS: [&'static str; 6] = [
+		".........#",
+		"##......##",
+		"###..#.###",
+		"####.#####",
+		"#########.",
+		"#.#######.",
+	];
+
+	for (y,row) in ROWS.iter().enumerate(){
+		for (x,c) in row.chars().enumerate(){
+			if c == '#'{
+				world.set_position(grid::Pos{x: x as grid::PosAxis,y: (world.height() as usize - ROWS.len() + y) as grid::PosAxis},true).unwrap();
+			}
+		}
+	}
+	world
+}
+
+fn intersection(intersection: CellIntersection) -> Option<(bool,grid::Pos)>{
+	match intersection{
+		CellIntersection::Imprint(pos)     => Some((true,pos)),
+		CellIntersection::OutOfBounds(pos) => Some((false,pos)),
+		CellIntersection::None             => None,
+	}
+}
+
+fn assert_same_cells(a: &dynamic::World<bool>,b: &bitboard::World){
+	for (pos,cell) in grid::cells_iter::Iter::new(a){
+		assert_eq!(b.position(pos),Some(cell),"{:?}",pos);
+	}
+}
+
+#[test]
+fn shape_intersects_like_dynamic(){
+	let dynamic = fill(dynamic::World::new(10,20));
+	let bitboard = fill(bitboard::World::new(10,20));
+	assert_same_cells(&dynamic,&bitboard);
+
+	for &shape in SHAPES.iter(){
+		for rotation in 0..shape.rotation_count(){
+			let shape = RotatedShape::new(shape).with_rotation(rotation);
+			for y in -4 .. 22{
+				for x in -4 .. 12{
+					let pos = grid::Pos{x: x,y: y};
+					assert_eq!(intersection(bitboard.shape_intersects(&shape,pos)),intersection(dynamic.shape_intersects(&shape,pos)),"{:?} at {:?}",shape,pos);
+				}
+			}
+		}
+	}
+}
+
+#[test]
+fn imprint_and_full_rows_like_dynamic(){
+	fn occupied(_: &RotatedShape) -> bool{true}
+
+	let mut dynamic = fill(dynamic::World::new(10,20));
+	let mut bitboard = fill(bitboard::World::new(10,20));
+
+	//A vertical I shape in the rightmost column completes one row and leaves one with a hole
+	let shape = RotatedShape::new(Shape::I).with_rotation(1);
+	let pos = grid::Pos{x: 9 - shape.real_bound_x().unwrap().0 as grid::PosAxis,y: 16};
+	dynamic.imprint_shape(&shape,pos,&(occupied as fn(&_) -> _));
+	bitboard.imprint_shape(&shape,pos,&(occupied as fn(&_) -> _));
+	assert_same_cells(&dynamic,&bitboard);
+
+	assert_eq!(bitboard.handle_full_rows(16 .. 20),dynamic.handle_full_rows(16 .. 20));
+	assert_same_cells(&dynamic,&bitboard);
+}
+
+#[test]
+fn evaluate_like_dynamic(){
+	let dynamic = fill(dynamic::World::new(10,20));
+	let bitboard = fill(bitboard::World::new(10,20));
+
+	let weights = Weights{
+		height_sum         : 1.0,
+		height_max         : 2.0,
+		rows_completed     : 3.0,
+		holes              : 5.0,
+		holes_weighted     : 7.0,
+		bumpiness          : 11.0,
+		bumpiness_allowance: 1.0,
+		wells              : 13.0,
+		row_transitions    : 17.0,
+		column_transitions : 19.0,
+	};
+	assert_eq!(evaluation::evaluate_bitboard(&bitboard,&weights),evaluation::evaluate(&dynamic,&weights));
+
+	assert_eq!(bitboard.holes(),3);
+	assert_eq!(bitboard.column_height(0),5);
+	assert_eq!(bitboard.column_height(9),6);
+}
+
+#[test]
+fn too_wide_worlds_are_not_searched(){
+	let settings = bruteforce::Settings{
+		perfect_clear: Some(perfect_clear::Settings::default()),
+		..bruteforce::Settings::default()
+	};
+	let shape = RotatedShape::new(Shape::O);
+
+	let widest = dynamic::World::<bool>::new(bitboard::WIDTH_MAX,20);
+	let pos = grid::Pos{x: bitboard::WIDTH_MAX as grid::PosAxis/2,y: 0};
+	assert!(bitboard::World::try_from_world(&widest).is_some());
+	assert!(!bruteforce::search_targets(&widest,shape,pos,vec![Shape::O],&settings).is_empty());
+
+	let too_wide = dynamic::World::<bool>::new(bitboard::WIDTH_MAX+1,20);
+	assert!(bitboard::World::try_from_world(&too_wide).is_none());
+	assert!(bruteforce::search_targets(&too_wide,shape,pos,vec![Shape::O],&settings).is_empty());
+	assert!(perfect_clear::search(&too_wide,shape,pos,vec![Shape::O],&settings.perfect_clear.unwrap()).is_none());
+}
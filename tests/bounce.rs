@@ -0,0 +1,87 @@
+extern crate tetr;
+
+mod common;
+
+use std::sync;
+
+use tetr::controller::Controller as ControllerTrait;
+use tetr::controller::ai::bounce;
+use tetr::game::{self,event,Event,Request,Simulation};
+use tetr::game::data::{Input,PlayerId,WorldId};
+use tetr::game::data::World as WorldTrait;
+use tetr::game::data::world::{dynamic,CellIntersection};
+
+type World = dynamic::World<bool>;
+
+///A simulation with one player in one empty world, the shapes falling slowly enough to never land during the tests
+fn new_simulation<EL>(event_listener: &mut EL) -> Simulation<World,game::seed::StdRng>
+	where EL: FnMut(Event<(PlayerId,WorldId),WorldId>)
+{
+	let mut settings = common::settings();
+	settings.players[0].1.gravityfall_frequency = 1000.0;
+	Simulation::headless(0,&settings,event_listener)
+}
+
+fn is_out_of_bounds(intersection: CellIntersection) -> bool{
+	match intersection{
+		CellIntersection::OutOfBounds(_) => true,
+		_ => false
+	}
+}
+
+#[test]
+fn collision_with_wall_is_signaled(){
+	let mut simulation = new_simulation(&mut |_| ());
+
+	let mut collisions = Vec::new();
+	for _ in 0..10{
+		simulation.state.request(Request::PlayerInput{input: Input::MoveLeft,player: 0},&mut |e| if let Event::PlayerCollidedOnMovement{player,current,target,cause} = e{
+			collisions.push((player,current,target,cause));
+		});
+	}
+
+	//The shape reaches the wall in less than 10 moves and then collides with it on every move
+	assert!(!collisions.is_empty());
+	let pos = simulation.state.data.players.get(0).unwrap().pos;
+	for (player,current,target,cause) in collisions{
+		assert_eq!(player,(0,0));
+		assert_eq!(current,pos);
+		assert_eq!(target,pos.with_x(|x| x - 1));
+		match cause{
+			event::MovementCause::Input(Input::MoveLeft) => (),
+			cause => panic!("Unexpected cause {:?}",cause)
+		}
+	}
+}
+
+#[test]
+fn bounce_reaches_both_walls(){
+	let (request_sender,request_receiver) = sync::mpsc::channel();
+	let mut controller: Box<ControllerTrait<World,Event<(PlayerId,WorldId),WorldId>>> = Box::new(bounce::Controller::new(request_sender,0));
+	let mut simulation = new_simulation(&mut |e| controller.event(&e));
+
+	let mut left = 0;
+	let mut right = 0;
+	for _ in 0..(60.0/game::simulation::DEFAULT_TIMESTEP) as usize{
+		controller.update(simulation.timestep(),&simulation.state.data);
+		simulation.receive(&request_receiver);
+		simulation.step(&mut |e| controller.event(&e));
+
+		let player = simulation.state.data.players.get(0).unwrap();
+		let &(ref world,_) = simulation.state.data.worlds.get(0).unwrap();
+		if is_out_of_bounds(world.shape_intersects(&player.shape,player.pos.with_x(|x| x - 1))){
+			if left == right{
+				left+= 1;
+			}
+		}else if is_out_of_bounds(world.shape_intersects(&player.shape,player.pos.with_x(|x| x + 1))){
+			if right < left{
+				right+= 1;
+			}
+		}
+
+		if right >= 2{
+			return;
+		}
+	}
+	panic!("The walls were reached {} times on the left and {} times on the right",left,right);
+}
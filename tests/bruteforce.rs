@@ -0,0 +1,206 @@
+extern crate tetr;
+
+mod common;
+
+use std::f64;
+use std::sync;
+
+use tetr::controller::Controller as ControllerTrait;
+use tetr::controller::ai::bruteforce::{self,Difficulty,Settings};
+use tetr::data::grid;
+use tetr::data::shapes::tetromino::{Rotation,RotatedShape,Shape};
+use tetr::game::{pathfinder,seed,Event,Request};
+use tetr::game::data::World as WorldTrait;
+use tetr::game::data::world::bitboard;
+use tetr::game::data::world::dynamic::World;
+use tetr::game::event::ShapeImprintCause;
+
+///A 10x20 world with its lowest rows filled except for the rightmost column
+fn well_world() -> World<bool>{
+	let mut world = World::new(10,20);
+	for y in 16..20{
+		for x in 0..9{
+			world.set_position(grid::Pos{x: x,y: y},true).unwrap();
+		}
+	}
+	world
+}
+
+#[test]
+fn line_clear_is_chosen(){
+	let world = well_world();
+	let shape = RotatedShape::new(Shape::I);
+	let settings = Settings{search_time: 10.0,..Settings::default()};
+
+	let (pos,rotation) = bruteforce::search(&world,shape,grid::Pos{x: 3,y: 0},vec![Shape::O],&settings).unwrap();
+	let mut scratch_world = bitboard::World::from_world(&world);
+	assert_eq!(bruteforce::imprint(&mut scratch_world,&shape.with_rotation(rotation),pos),4);
+}
+
+#[test]
+fn search_is_deterministic(){
+	let world = well_world();
+	let settings = Settings{search_time: 10.0,depth: 3,..Settings::default()};
+	let search = || bruteforce::search_ranked(&world,RotatedShape::new(Shape::T),grid::Pos{x: 3,y: 0},vec![Shape::S,Shape::Z],&settings);
+
+	let ranked = search();
+	assert!(!ranked.is_empty());
+	for _ in 0..3{
+		assert_eq!(search(),ranked);
+	}
+}
+
+#[test]
+fn zero_search_time_completes_the_first_ply(){
+	let world = well_world();
+	let shape = RotatedShape::new(Shape::T);
+	let lookahead = vec![Shape::S,Shape::Z];
+
+	let ranked = bruteforce::search_ranked(&world,shape,grid::Pos{x: 3,y: 0},lookahead.clone(),&Settings{search_time: 0.0,depth: 3,..Settings::default()});
+	assert!(!ranked.is_empty());
+	assert_eq!(ranked,bruteforce::search_ranked(&world,shape,grid::Pos{x: 3,y: 0},lookahead,&Settings{search_time: 10.0,depth: 1,..Settings::default()}));
+}
+
+///Settings of the given difficulty acting without delays, and searching without a time budget so that the searches are reproducible
+fn instant(difficulty: Difficulty) -> Settings{Settings{
+	move_time        : 0.0,
+	fall_time        : 0.0,
+	rotate_time      : 0.0,
+	reaction_time    : 0.0,
+	pieces_per_second: f64::INFINITY,
+	search_time      : 10.0,
+	..Settings::preset(difficulty)
+}}
+
+///Lets a controller with the given settings place shapes, returning the placements of the shapes together with the best ranked placements when they spawned
+fn play(settings: Settings,seed: seed::Seed,shapes: usize) -> Vec<((grid::Pos,Rotation),(grid::Pos,Rotation))>{
+	let (request_sender,request_receiver) = sync::mpsc::channel();
+	let mut controller = bruteforce::Controller::new(request_sender,0,settings,seed);
+	let mut simulation = common::new_simulation(&mut |e| ControllerTrait::<World<bool>,_>::event(&mut controller,&e));
+
+	let mut best = None;
+	let mut placements = Vec::new();
+	for _ in 0..100000{
+		if best.is_none(){
+			let player = simulation.state.data.players.get(0).unwrap();
+			let &(ref world,_) = simulation.state.data.worlds.get(0).unwrap();
+			best = bruteforce::search_ranked(world,player.shape,player.pos,None,&settings).first().cloned();
+			assert!(best.is_some());
+		}
+
+		controller.update(simulation.timestep(),&simulation.state.data);
+		simulation.receive(&request_receiver);
+		simulation.step(&mut |e|{
+			if let Event::WorldImprintedShape{shape: (shape,pos),cause: ShapeImprintCause::PlayerInflicted((0,_)),..} = e{
+				placements.push(((pos,shape.rotation()),best.take().unwrap()));
+			}
+			ControllerTrait::<World<bool>,_>::event(&mut controller,&e);
+		});
+
+		if placements.len() >= shapes{
+			return placements;
+		}
+	}
+	panic!("Only {} shapes were placed",placements.len());
+}
+
+#[test]
+fn hard_places_the_best_ranked(){
+	for (placement,best) in play(instant(Difficulty::Hard),0,20){
+		assert_eq!(placement,best);
+	}
+}
+
+#[test]
+fn easy_chooses_worse_placements(){
+	let settings = instant(Difficulty::Easy);
+	assert!(settings.suboptimal_probability > 0.0 && settings.misdrop_probability > 0.0);
+
+	//With this seed, some of the shapes are misdropped or placed at a worse ranked placement
+	let placements = play(settings,0,20);
+	assert!(placements.iter().any(|&(placement,best)| placement != best));
+
+	//The same seed makes the same choices
+	assert_eq!(play(settings,0,20),placements);
+}
+
+#[test]
+fn world_changing_mid_placement_changes_the_target(){
+	let settings = instant(Difficulty::Hard);
+	let (request_sender,request_receiver) = sync::mpsc::channel();
+	let mut controller = bruteforce::Controller::new(request_sender,0,settings,0);
+	let mut simulation = common::new_simulation(&mut |e| ControllerTrait::<World<bool>,_>::event(&mut controller,&e));
+
+	let (old_pos,old_rotation) = {
+		let player = simulation.state.data.players.get(0).unwrap();
+		bruteforce::search_ranked(&simulation.state.data.worlds.get(0).unwrap().0,player.shape,player.pos,None,&settings)[0]
+	};
+
+	//The first input is performed towards the best placement
+	controller.update(simulation.timestep(),&simulation.state.data);
+	simulation.receive(&request_receiver);
+	simulation.step(&mut |e| ControllerTrait::<World<bool>,_>::event(&mut controller,&e));
+
+	//Find a change to the world making another placement the best one while the old one is still reachable, so that only searching again changes the target
+	let (world,new_target) = {
+		let player = simulation.state.data.players.get(0).unwrap();
+		let world = &simulation.state.data.worlds.get(0).unwrap().0;
+		(0..10).flat_map(|x| (1..4).map(move |height| (x,height))).filter_map(|(x,height)|{
+			let mut world = world.clone();
+			for y in 20-height .. 20{
+				world.set_position(grid::Pos{x: x,y: y},true).unwrap();
+			}
+			let new_target = bruteforce::search_ranked(&world,player.shape,player.pos,None,&settings)[0];
+			if new_target != (old_pos,old_rotation) && pathfinder::path(&world,player.shape,player.pos,old_pos,old_rotation).is_some(){
+				Some((world,new_target))
+			}else{
+				None
+			}
+		}).next().unwrap()
+	};
+	simulation.state.data.worlds.get_mut(0).unwrap().0 = world;
+	ControllerTrait::<World<bool>,_>::event(&mut controller,&Event::WorldUpdated{world: 0});
+
+	for _ in 0..1000{
+		controller.update(simulation.timestep(),&simulation.state.data);
+		simulation.receive(&request_receiver);
+		let mut placement = None;
+		simulation.step(&mut |e|{
+			if let Event::WorldImprintedShape{shape: (shape,pos),cause: ShapeImprintCause::PlayerInflicted((0,_)),..} = e{
+				placement = Some((pos,shape.rotation()));
+			}
+			ControllerTrait::<World<bool>,_>::event(&mut controller,&e);
+		});
+
+		if let Some(placement) = placement{
+			assert_eq!(placement,new_target);
+			return;
+		}
+	}
+	panic!("The shape was not placed");
+}
+
+#[test]
+fn reaction_time_delays_the_first_input(){
+	for &reaction_time in &[0.0,0.5]{
+		let (request_sender,request_receiver) = sync::mpsc::channel();
+		let mut controller = bruteforce::Controller::new(request_sender,0,Settings{
+			reaction_time: reaction_time,
+			..instant(Difficulty::Hard)
+		},0);
+		let simulation = common::new_simulation(&mut |e| ControllerTrait::<World<bool>,_>::event(&mut controller,&e));
+
+		let mut time = 0.0;
+		loop{
+			controller.update(simulation.timestep(),&simulation.state.data);
+			time+= simulation.timestep();
+			if let Ok(request) = request_receiver.try_recv(){
+				assert!(if let Request::PlayerInput{player: 0,..} = request{true}else{false});
+				break;
+			}
+			assert!(time < reaction_time + 1.0);
+		}
+		assert!(time >= reaction_time - 1e-9);
+		assert!(time < reaction_time + 2.0*simulation.timestep());
+	}
+}
@@ -0,0 +1,28 @@
+//!Setup shared by the tests
+
+#![allow(dead_code)]
+
+use tetr::data::grid;
+use tetr::game::{self,Event,Simulation,State};
+use tetr::game::data::{player,PlayerId,WorldId};
+use tetr::game::data::world::dynamic::World;
+
+///Settings of a game with one player in one empty world of standard size
+pub fn settings() -> game::replay::Settings{game::replay::Settings{
+	timestep: game::simulation::DEFAULT_TIMESTEP,
+	worlds  : vec![(0,grid::Size{x: 10,y: 20})],
+	players : vec![(0,player::Settings{
+		gravityfall_frequency: 1.0,
+		fastfall_shadow      : false,
+	})],
+}}
+
+pub fn new_state() -> State<World<bool>,game::seed::StdRng>{
+	State::headless(0,&settings(),&mut |_| ())
+}
+
+pub fn new_simulation<EL>(event_listener: &mut EL) -> Simulation<World<bool>,game::seed::StdRng>
+	where EL: FnMut(Event<(PlayerId,WorldId),WorldId>)
+{
+	Simulation::headless(0,&settings(),event_listener)
+}
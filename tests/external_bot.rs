@@ -0,0 +1,153 @@
+extern crate tetr;
+
+mod common;
+
+use std::{process,sync,thread};
+use std::time::Duration;
+
+use tetr::controller::Controller as ControllerTrait;
+use tetr::controller::external;
+use tetr::data::grid;
+use tetr::game::{self,Event,Simulation};
+use tetr::game::data::{PlayerId,WorldId};
+use tetr::game::data::world::dynamic::World;
+
+use common::new_simulation;
+
+fn event(controller: &mut external::Controller,event: &Event<(PlayerId,WorldId),WorldId>){
+	ControllerTrait::<World<bool>,_>::event(controller,event)
+}
+
+fn settings() -> external::Settings{external::Settings{
+	input_time  : 0.0,
+	info_timeout: 2.0,
+	move_timeout: 2.0,
+}}
+
+///Updates the controller in real time until the given condition is true or the time limit is reached
+fn run_until<F>(controller: &mut external::Controller,simulation: &mut Simulation<World<bool>,game::seed::StdRng>,request_receiver: &sync::mpsc::Receiver<game::Request<PlayerId,WorldId>>,mut condition: F)
+	where F: FnMut(&Event<(PlayerId,WorldId),WorldId>) -> bool
+{
+	for _ in 0..5000{
+		controller.update(simulation.timestep(),&simulation.state.data);
+		if controller.error().is_some(){
+			return;
+		}
+
+		simulation.receive(request_receiver);
+		let mut done = false;
+		simulation.step(&mut |e|{
+			done = done || condition(&e);
+			event(controller,&e);
+		});
+		if done{
+			return;
+		}
+
+		thread::sleep(Duration::from_millis(1));
+	}
+}
+
+#[test]
+fn mock_bot_places_shapes(){
+	let (request_sender,request_receiver) = sync::mpsc::channel();
+	let mut controller = external::Controller::new(
+		request_sender,
+		0,
+		process::Command::new("sh").arg(concat!(env!("CARGO_MANIFEST_DIR"),"/tests/mock_bot.sh")),
+		settings()
+	).unwrap();
+	let mut simulation = new_simulation(&mut |e| event(&mut controller,&e));
+
+	//The mock bot moves every shape to the left wall
+	let mut imprints = 0;
+	run_until(&mut controller,&mut simulation,&request_receiver,|e| match e{
+		&Event::WorldImprintedShape{shape: (shape,pos),..} => {
+			assert_eq!(shape.real_bound_x().unwrap().0 as grid::PosAxis + pos.x,0);
+			imprints+= 1;
+			imprints >= 3
+		},
+		_ => false
+	});
+
+	assert!(controller.error().is_none(),"{:?}",controller.error());
+	assert_eq!(controller.info().map(|info| &*info.name),Some("mock"));
+	assert_eq!(imprints,3);
+}
+
+#[test]
+fn silent_bot_times_out(){
+	let (request_sender,_request_receiver) = sync::mpsc::channel();
+	let mut controller = external::Controller::new(
+		request_sender,
+		0,
+		process::Command::new("sh").arg("-c").arg("read -r line"),
+		external::Settings{info_timeout: 0.0,..settings()}
+	).unwrap();
+	let simulation = new_simulation(&mut |e| event(&mut controller,&e));
+
+	//The bot has no time at all to send its information
+	controller.update(simulation.timestep(),&simulation.state.data);
+	match controller.error(){
+		Some(&external::Error::Timeout) => (),
+		error => panic!("Expected a timeout, got {:?}",error)
+	}
+}
+
+#[test]
+fn invalid_message_is_reported(){
+	let (request_sender,request_receiver) = sync::mpsc::channel();
+	let mut controller = external::Controller::new(
+		request_sender,
+		0,
+		process::Command::new("sh").arg("-c").arg("echo nonsense; cat > /dev/null"),
+		settings()
+	).unwrap();
+	let mut simulation = new_simulation(&mut |e| event(&mut controller,&e));
+
+	run_until(&mut controller,&mut simulation,&request_receiver,|_| false);
+
+	match controller.error(){
+		Some(&external::Error::InvalidMessage(ref line)) => assert_eq!(line,"nonsense"),
+		error => panic!("Expected an invalid message error, got {:?}",error)
+	}
+}
+
+#[test]
+fn placement_with_rotation_out_of_range_is_reported(){
+	let (request_sender,request_receiver) = sync::mpsc::channel();
+	let mut controller = external::Controller::new(
+		request_sender,
+		0,
+		process::Command::new("sh").arg("-c").arg(r#"echo '{"type": "info", "name": "bad", "version": "1", "author": "test"}'; read -r line; echo '{"type": "placement", "x": 0, "y": 0, "rotation": 7}'; cat > /dev/null"#),
+		settings()
+	).unwrap();
+	let mut simulation = new_simulation(&mut |e| event(&mut controller,&e));
+
+	run_until(&mut controller,&mut simulation,&request_receiver,|_| false);
+
+	match controller.error(){
+		Some(&external::Error::InvalidMessage(ref line)) => assert!(line.contains("\"rotation\": 7"),"{}",line),
+		error => panic!("Expected an invalid message error, got {:?}",error)
+	}
+}
+
+#[test]
+fn unreachable_placement_is_reported_to_the_bot(){
+	let (request_sender,request_receiver) = sync::mpsc::channel();
+	let mut controller = external::Controller::new(
+		request_sender,
+		0,
+		process::Command::new("sh").arg("-c").arg(r#"echo '{"type": "info", "name": "bad", "version": "1", "author": "test"}'; read -r line; echo '{"type": "placement", "x": 0, "y": 30, "rotation": 0}'; read -r line; case "$line" in *'Unreachable placement'*) echo '{"type": "error", "message": "reported"}';; esac; cat > /dev/null"#),
+		settings()
+	).unwrap();
+	let mut simulation = new_simulation(&mut |e| event(&mut controller,&e));
+
+	run_until(&mut controller,&mut simulation,&request_receiver,|_| false);
+
+	//The bot replies to the report by an error
+	match controller.error(){
+		Some(&external::Error::Bot(ref message)) => assert_eq!(message,"reported"),
+		error => panic!("Expected the error of the bot, got {:?}",error)
+	}
+}
@@ -0,0 +1,108 @@
+extern crate tetr;
+
+mod common;
+
+use tetr::game::{self,Event,Request,Simulation,State};
+use tetr::game::data::Input;
+use tetr::game::data::world::dynamic::World;
+
+///Constructs the game state of the common settings with the finesse of the player judged
+fn new_state() -> State<World<bool>,game::seed::StdRng>{
+	let mut state = State::headless_empty(0);
+	state.finesse.enable(0);
+	common::settings().setup(&mut state,State::headless_world,&mut |_| ());
+	state
+}
+
+///Performs the inputs and locks the shape by fast falling it, returning the finesse faults
+fn place(state: &mut State<World<bool>,game::seed::StdRng>,inputs: &[Input]) -> Vec<(u32,u32)>{
+	let mut faults = Vec::new();
+	for &input in inputs.iter().chain(Some(&Input::FastFall)){
+		assert!(state.request(Request::PlayerInput{input: input,player: 0},&mut |_| ()));
+	}
+	state.update(0.0,&mut |e| if let Event::PlayerFinesseFault{player: (0,0),inputs,minimum_inputs,..} = e{
+		faults.push((inputs,minimum_inputs));
+	});
+	faults
+}
+
+#[test]
+fn optimal_placement(){
+	let mut state = new_state();
+
+	assert_eq!(place(&mut state,&[]),vec![]);
+	assert_eq!(place(&mut state,&[Input::MoveLeft]),vec![]);
+
+	let stats = state.finesse.stats(0).unwrap();
+	assert_eq!(stats.pieces,2);
+	assert_eq!(stats.faults,0);
+	assert_eq!(stats.inputs,1);
+	assert_eq!(stats.minimum_inputs,1);
+}
+
+#[test]
+fn unnecessary_inputs(){
+	let mut state = new_state();
+
+	assert_eq!(place(&mut state,&[Input::MoveLeft,Input::MoveRight]),vec![(2,0)]);
+	assert_eq!(place(&mut state,&[Input::MoveRight,Input::MoveRight,Input::MoveLeft,Input::SlowFall]),vec![(3,1)]);
+
+	let stats = *state.finesse.stats(0).unwrap();
+	assert_eq!(stats.pieces,2);
+	assert_eq!(stats.faults,2);
+	assert_eq!(stats.extra_inputs(),4);
+
+	//Restarting the world resets the statistics
+	state.request(Request::WorldRestart{world: 0},&mut |_| ());
+	assert_eq!(state.finesse.stats(0).unwrap().pieces,0);
+}
+
+#[test]
+fn loading_a_save_restarts_the_tracking(){
+	let mut state = new_state();
+	assert_eq!(place(&mut state,&[Input::MoveLeft,Input::MoveRight]),vec![(2,0)]);
+
+	let mut save = Vec::new();
+	game::save::write(&state,&mut save).unwrap();
+
+	//Inputs performed before loading are forgotten
+	assert!(state.request(Request::PlayerInput{input: Input::MoveLeft,player: 0},&mut |_| ()));
+	game::save::read(&mut state,&mut &save[..]).unwrap();
+	assert_eq!(*state.finesse.stats(0).unwrap(),game::finesse::Stats::default());
+	assert_eq!(place(&mut state,&[]),vec![]);
+}
+
+#[test]
+fn restoring_a_snapshot_restores_the_tracking(){
+	let mut simulation = Simulation::new(new_state(),game::simulation::DEFAULT_TIMESTEP);
+	let snapshot = simulation.snapshot();
+
+	assert_eq!(place(&mut simulation.state,&[Input::MoveLeft,Input::MoveRight]),vec![(2,0)]);
+	simulation.restore(&snapshot);
+	assert_eq!(*simulation.state.finesse.stats(0).unwrap(),game::finesse::Stats::default());
+	assert_eq!(place(&mut simulation.state,&[Input::MoveLeft]),vec![]);
+}
+
+#[test]
+fn disabled_by_default(){
+	let mut state = common::new_state();
+
+	assert_eq!(place(&mut state,&[Input::MoveLeft,Input::MoveRight]),vec![]);
+	assert_eq!(state.finesse.stats(0),None);
+
+	//The tracking starts at the next shape
+	state.finesse.enable(0);
+	assert_eq!(place(&mut state,&[Input::MoveLeft,Input::MoveRight]),vec![]);
+	assert_eq!(place(&mut state,&[Input::MoveLeft,Input::MoveRight]),vec![(2,0)]);
+
+	//Disabling forgets the statistics
+	state.finesse.disable(0);
+	assert_eq!(place(&mut state,&[Input::MoveLeft,Input::MoveRight]),vec![]);
+	assert_eq!(state.finesse.stats(0),None);
+}
+
+#[test]
+fn extra_inputs_saturate(){
+	let stats = game::finesse::Stats{inputs: 1,minimum_inputs: 2,..game::finesse::Stats::default()};
+	assert_eq!(stats.extra_inputs(),0);
+}
@@ -0,0 +1,46 @@
+extern crate tetr;
+
+mod common;
+
+use tetr::data::grid::{self,Grid,RectangularBound};
+use tetr::game::{self,Event,State};
+use tetr::game::data::World as WorldTrait;
+use tetr::game::data::world::dynamic::World;
+
+use common::new_state;
+
+fn occupied(state: &State<World<bool>,game::seed::StdRng>,y: grid::PosAxis) -> Vec<bool>{
+	let &(ref world,_) = state.data.worlds.get(0).unwrap();
+	(0..world.width() as grid::PosAxis).map(|x| world.position(grid::Pos{x: x,y: y}).unwrap()).collect()
+}
+
+#[test]
+fn garbage_pushes_rows_up(){
+	let mut state = new_state();
+	state.data.worlds.get_mut(0).unwrap().0.set_position(grid::Pos{x: 3,y: 19},true).unwrap();
+
+	let mut updated = false;
+	assert!(state.add_garbage(0,2,true,&mut |e| if let Event::WorldUpdated{world: 0} = e{updated = true;}));
+	assert!(updated);
+
+	//The old bottom row is pushed up
+	assert_eq!(occupied(&state,17).iter().filter(|&&cell| cell).count(),1);
+	assert!(occupied(&state,17)[3]);
+
+	//Every garbage row has a single hole in the same column
+	let row = occupied(&state,19);
+	assert_eq!(row.iter().filter(|&&cell| !cell).count(),1);
+	assert_eq!(occupied(&state,18),row);
+
+	assert!(!state.data.worlds.get(0).unwrap().1);
+	assert!(!state.add_garbage(1,1,true,&mut |_| ()));
+}
+
+#[test]
+fn garbage_pushing_out_cells_loses_the_world(){
+	let mut state = new_state();
+	state.data.worlds.get_mut(0).unwrap().0.set_position(grid::Pos{x: 0,y: 0},true).unwrap();
+
+	state.add_garbage(0,1,true,&mut |_| ());
+	assert!(state.data.worlds.get(0).unwrap().1);
+}
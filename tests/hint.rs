@@ -0,0 +1,62 @@
+extern crate tetr;
+
+mod common;
+
+use tetr::controller::ai::{bruteforce,pool};
+use tetr::controller::ai::hint::Hints;
+use tetr::controller::ai::bruteforce::Settings;
+use tetr::game::Request;
+use tetr::game::data::Input;
+
+use common::new_state;
+
+#[test]
+fn hint_follows_the_shape(){
+	let mut state = new_state();
+	let mut hints = Hints::new(Settings::default());
+
+	hints.update(&state.data);
+	assert!(hints.get(0).is_none());
+
+	assert!(hints.toggle(0));
+	hints.update(&state.data);
+	let (shape,_) = hints.get(0).unwrap();
+	assert_eq!(shape.shape(),state.data.players.get(0).unwrap().shape.shape());
+
+	//The hint is searched for again for the next shape
+	state.request(Request::PlayerInput{input: Input::FastFall,player: 0},&mut |e| hints.event(&e));
+	state.update(0.0,&mut |e| hints.event(&e));
+	hints.update(&state.data);
+	let (shape,_) = hints.get(0).unwrap();
+	assert_eq!(shape.shape(),state.data.players.get(0).unwrap().shape.shape());
+
+	assert!(!hints.toggle(0));
+	assert!(hints.get(0).is_none());
+}
+
+#[test]
+fn pooled_hint_uses_the_latest_search(){
+	let state = new_state();
+	let (pool,job_receiver) = pool::Pool::manual();
+	let mut hints = Hints::with_pool(Settings::default(),pool);
+
+	//Nothing is shown while searching
+	assert!(hints.toggle(0));
+	hints.update(&state.data);
+	let old_job = job_receiver.try_recv().unwrap();
+	assert!(hints.get(0).is_none());
+
+	hints.outdate_all();
+	hints.update(&state.data);
+	let new_job = job_receiver.try_recv().unwrap();
+
+	//The result of the previous search arrives last, and is discarded
+	let targets = bruteforce::search_targets(&new_job.world,new_job.shape,new_job.pos,new_job.lookahead.clone(),&new_job.settings);
+	new_job.result_sender.send((new_job.generation,targets.clone())).unwrap();
+	old_job.result_sender.send((old_job.generation,Vec::new())).unwrap();
+	hints.update(&state.data);
+
+	let (shape,pos) = hints.get(0).unwrap();
+	assert_eq!((pos,shape.rotation()),targets[0]);
+	assert!(job_receiver.try_recv().is_err());
+}
@@ -0,0 +1,12 @@
+#!/bin/sh
+#Mock bot for the external bot protocol tests
+#Moves every shape to the left wall and drops it
+
+echo '{"type":"info","name":"mock","version":"1.0","author":"tetr"}'
+
+while read -r line; do
+	case "$line" in
+		*'"type":"start"'*) echo '{"type":"inputs","inputs":["MoveLeft","MoveLeft","MoveLeft","MoveLeft","MoveLeft","MoveLeft","FastFall"]}';;
+		*'"type":"quit"'*) exit 0;;
+	esac
+done
@@ -0,0 +1,54 @@
+extern crate rand;
+extern crate tetr;
+
+mod common;
+
+use rand::Rand;
+use tetr::data::grid;
+use tetr::data::shapes::tetromino::Shape;
+use tetr::game::{seed,Event,Request,State};
+use tetr::game::data::world::dynamic::World;
+
+///Sets up a game with two empty worlds and one player in the first one
+fn new_state() -> State<World<bool>,seed::StdRng>{
+	let mut settings = common::settings();
+	settings.worlds.push((1,grid::Size{x: 10,y: 20}));
+	State::headless(0,&settings,&mut |_| ())
+}
+
+#[test]
+fn moved_player_resides_in_the_new_world(){
+	let mut state = new_state();
+	let mut events = Vec::new();
+
+	assert!(state.request(Request::PlayerMoveWorld{player: 0,world: 1},&mut |e| events.push(e)));
+	assert_eq!(state.data.players.get(0).map(|player| player.world),Some(1));
+	match events.pop(){
+		Some(Event::PlayerMovedWorld{player: (0,1),old: 0,new: 1}) => (),
+		event => panic!("Unexpected event: {:?}",event)
+	}
+
+	//Already in the world
+	assert!(!state.request(Request::PlayerMoveWorld{player: 0,world: 1},&mut |_| ()));
+
+	//The world does not exist
+	assert!(!state.request(Request::PlayerMoveWorld{player: 0,world: 2},&mut |_| ()));
+}
+
+#[test]
+fn moved_player_draws_shapes_from_the_new_world(){
+	let mut state = new_state();
+
+	//The worlds start with copies of the same generator, so the generator of the new world is reseeded to make it differ
+	state.reset_world(1,&mut |_| ());
+	assert!(state.rngs.player_get(0,0).seed() != state.rngs.world_get(1).seed());
+
+	assert!(state.request(Request::PlayerMoveWorld{player: 0,world: 1},&mut |_| ()));
+	let mut world_rng = state.rngs.world_get(1).clone();
+	for _ in 0..10{
+		assert_eq!(Shape::rand(state.rngs.player_get_mut(1,0)),Shape::rand(&mut world_rng));
+	}
+
+	//The player has a copy of its own, leaving the generator of the world untouched
+	assert!(state.rngs.world_get(1).generated() < world_rng.generated());
+}
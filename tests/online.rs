@@ -0,0 +1,312 @@
+extern crate tetr;
+
+use std::{net,sync};
+
+use tetr::controller::registry::{Control,Registry};
+use tetr::data::grid;
+use tetr::game::{self,Event,Request,State};
+use tetr::game::data::{player,Input,PlayerId,WorldId};
+use tetr::game::data::world::dynamic::World;
+use tetr::online::packet::{ConnectionId,PlayerNetworkId};
+use tetr::online::reliable::{self,Connection,Reliability};
+use tetr::online::server::Server;
+use tetr::online::{client,server};
+
+type ClientConnection = Connection<client::packet::Data,server::packet::Data>;
+
+fn address(port: u16) -> net::SocketAddr{
+	net::SocketAddr::new(net::IpAddr::V4(net::Ipv4Addr::new(127,0,0,1)),port)
+}
+
+///Constructs a server, returning the receivers of the requests of the clients and of the requests of the server itself
+fn new_server(settings: reliable::Settings) -> (Server,sync::mpsc::Receiver<Request<PlayerId,WorldId>>,sync::mpsc::Receiver<Request<PlayerId,WorldId>>){
+	let (request_sender,request_receiver) = sync::mpsc::channel();
+	let (server_request_sender,server_request_receiver) = sync::mpsc::channel();
+	(Server::new(settings,request_sender,server_request_sender).unwrap(),request_receiver,server_request_receiver)
+}
+
+///Delivers the packets sent by the server, which are all expected to be sent to the given address
+fn receive(server: &mut Server,address: net::SocketAddr,connection: &mut ClientConnection) -> Vec<server::packet::Data>{
+	server.outgoing().into_iter().flat_map(|(destination,packet)|{
+		assert_eq!(destination,address);
+		connection.receive(packet)
+	}).collect()
+}
+
+///Performs the handshake of a new client at the given address
+fn connect(server: &mut Server,address: net::SocketAddr) -> (ClientConnection,ConnectionId){
+	let mut connection = ClientConnection::new(reliable::Settings::default());
+
+	let packet = connection.send(client::packet::Data::Connect{protocol_version: 1},Reliability::Unreliable);
+	server.receive(address,packet);
+	let received = receive(server,address,&mut connection);
+	assert_eq!(received.len(),1);
+	let connection_id = match received[0]{
+		server::packet::Data::ConnectionEstablished{connection: connection_id} => connection_id,
+		ref data => panic!("Unexpected data: {:?}",data)
+	};
+	assert!(server.is_connected(&address));
+	assert!(!server.is_established(&address));
+
+	let packet = connection.send(client::packet::Data::ConnectionEstablishedResponse{connection: connection_id},Reliability::Reliable);
+	server.receive(address,packet);
+	assert!(server.is_established(&address));
+
+	(connection,connection_id)
+}
+
+///Requests a new player from the client at the given address, which the game adds as the given player
+///Returns the network id of the created player
+fn add_player(server: &mut Server,address: net::SocketAddr,connection: &mut ClientConnection,connection_id: ConnectionId,player: Option<(PlayerId,WorldId)>) -> Option<PlayerNetworkId>{
+	request_player(server,address,connection,connection_id);
+	server.player_added(player);
+
+	match (receive(server,address,connection).pop(),player){
+		(Some(server::packet::Data::PlayerCreated{player: player_network_id,..}),Some(_)) => Some(player_network_id),
+		(Some(server::packet::Data::RequestDeniedReponse{..}),None) => None,
+		data => panic!("Unexpected data: {:?}",data)
+	}
+}
+
+///Requests a new player from the client at the given address without the game adding it
+fn request_player(server: &mut Server,address: net::SocketAddr,connection: &mut ClientConnection,connection_id: ConnectionId){
+	let packet = connection.send(client::packet::Data::Request{
+		connection: connection_id,
+		request: Request::PlayerAdd{
+			settings: player::Settings{
+				gravityfall_frequency: 1.0,
+				fastfall_shadow      : true,
+			},
+			world: 0
+		}
+	},Reliability::ReliableOrdered);
+	server.receive(address,packet);
+	assert!(receive(server,address,connection).is_empty());
+}
+
+fn disconnect(server: &mut Server,address: net::SocketAddr,connection: &mut ClientConnection,connection_id: ConnectionId){
+	let packet = connection.send(client::packet::Data::Disconnect{connection: connection_id},Reliability::Unreliable);
+	server.receive(address,packet);
+	assert!(!server.is_connected(&address));
+}
+
+///Performs the requests of the server and its clients like the game does: The requests of the server are always performed, while the requests of the clients are only performed when the controls of the players accept them
+fn perform(state: &mut State<World<bool>,game::seed::StdRng>,registry: &mut Registry<World<bool>>,server: &mut Server,request_receiver: &sync::mpsc::Receiver<Request<PlayerId,WorldId>>,server_request_receiver: &sync::mpsc::Receiver<Request<PlayerId,WorldId>>){
+	while let Ok(request) = server_request_receiver.try_recv(){
+		state.request(request,&mut |e| registry.event(&e));
+	}
+
+	while let Ok(request) = request_receiver.try_recv(){
+		if !registry.accepts_network_request(&request){
+			continue;
+		}
+
+		let mut added = None;
+		state.request(request,&mut |e|{
+			if let Event::PlayerAdded{player} = e{
+				added = Some(player);
+			}
+			registry.event(&e);
+		});
+
+		//Players added by the clients are controlled by them
+		if let Request::PlayerAdd{..} = request{
+			if let Some((player_id,_)) = added{
+				registry.set(player_id,Control::Network);
+			}
+			server.player_added(added);
+		}
+	}
+}
+
+#[test]
+fn connect_and_disconnect(){
+	let (mut server,request_receiver,server_request_receiver) = new_server(reliable::Settings::default());
+	let address = address(1);
+
+	let (mut connection,connection_id) = connect(&mut server,address);
+	assert!(request_receiver.try_recv().is_err());
+
+	//The game adds the player to another world than requested
+	add_player(&mut server,address,&mut connection,connection_id,Some((4,3)));
+	match request_receiver.try_recv(){
+		Ok(Request::PlayerAdd{world: 1,..}) => (),
+		request => panic!("Unexpected request: {:?}",request)
+	}
+
+	//The player is removed by the server when the client disconnects, and the world is kept
+	disconnect(&mut server,address,&mut connection,connection_id);
+	assert_eq!(server_request_receiver.try_recv(),Ok(Request::PlayerRemove{player: 4}));
+	assert!(server_request_receiver.try_recv().is_err());
+	assert!(request_receiver.try_recv().is_err());
+
+	//The repeated disconnections of a forgotten client are ignored
+	disconnect(&mut server,address,&mut connection,connection_id);
+	assert!(server_request_receiver.try_recv().is_err());
+}
+
+#[test]
+fn player_not_added_is_denied(){
+	let (mut server,request_receiver,server_request_receiver) = new_server(reliable::Settings::default());
+	let address = address(1);
+
+	let (mut connection,connection_id) = connect(&mut server,address);
+	add_player(&mut server,address,&mut connection,connection_id,None);
+	assert!(request_receiver.try_recv().is_ok());
+
+	//No player to remove
+	disconnect(&mut server,address,&mut connection,connection_id);
+	assert!(server_request_receiver.try_recv().is_err());
+}
+
+#[test]
+fn player_added_after_disconnection_is_removed(){
+	let (mut server,request_receiver,server_request_receiver) = new_server(reliable::Settings::default());
+	let address = address(1);
+
+	let (mut connection,connection_id) = connect(&mut server,address);
+	request_player(&mut server,address,&mut connection,connection_id);
+	assert!(request_receiver.try_recv().is_ok());
+	disconnect(&mut server,address,&mut connection,connection_id);
+	assert!(server_request_receiver.try_recv().is_err());
+
+	server.player_added(Some((0,2)));
+	assert_eq!(server_request_receiver.try_recv(),Ok(Request::PlayerRemove{player: 0}));
+}
+
+#[test]
+fn request_with_wrong_id_is_denied(){
+	let (mut server,request_receiver,_) = new_server(reliable::Settings::default());
+	let address = address(1);
+
+	let (mut connection,connection_id) = connect(&mut server,address);
+	let packet = connection.send(client::packet::Data::Request{
+		connection: connection_id.wrapping_add(1),
+		request: Request::PlayerRemove{player: 0}
+	},Reliability::ReliableOrdered);
+	server.receive(address,packet);
+
+	match receive(&mut server,address,&mut connection).pop(){
+		Some(server::packet::Data::RequestDeniedReponse{..}) => (),
+		data => panic!("Unexpected data: {:?}",data)
+	}
+	assert!(request_receiver.try_recv().is_err());
+}
+
+#[test]
+fn inputs_are_only_accepted_for_the_players_of_the_client(){
+	let (mut server,request_receiver,_) = new_server(reliable::Settings::default());
+	let (address_a,address_b) = (address(1),address(2));
+
+	let (mut connection_a,connection_id_a) = connect(&mut server,address_a);
+	let player_a = add_player(&mut server,address_a,&mut connection_a,connection_id_a,Some((3,1))).unwrap();
+	let (mut connection_b,connection_id_b) = connect(&mut server,address_b);
+	let player_b = add_player(&mut server,address_b,&mut connection_b,connection_id_b,Some((5,1))).unwrap();
+	assert!(player_a != player_b);
+	assert!(request_receiver.try_recv().is_ok());
+	assert!(request_receiver.try_recv().is_ok());
+
+	//The input is performed by the player of the client in the game
+	let packet = connection_a.send(client::packet::Data::Request{
+		connection: connection_id_a,
+		request: Request::PlayerInput{input: Input::MoveLeft,player: player_a}
+	},Reliability::ReliableOrdered);
+	server.receive(address_a,packet);
+	assert_eq!(request_receiver.try_recv(),Ok(Request::PlayerInput{input: Input::MoveLeft,player: 3}));
+
+	//The player of another client
+	let packet = connection_a.send(client::packet::Data::Request{
+		connection: connection_id_a,
+		request: Request::PlayerInput{input: Input::MoveLeft,player: player_b}
+	},Reliability::ReliableOrdered);
+	server.receive(address_a,packet);
+	match receive(&mut server,address_a,&mut connection_a).pop(){
+		Some(server::packet::Data::RequestDeniedReponse{..}) => (),
+		data => panic!("Unexpected data: {:?}",data)
+	}
+	assert!(request_receiver.try_recv().is_err());
+}
+
+#[test]
+fn timed_out_client_is_forgotten(){
+	//Every client times out on the next update
+	let (mut server,request_receiver,server_request_receiver) = new_server(reliable::Settings{timeout: 0.0,..reliable::Settings::default()});
+	let address = address(1);
+
+	let (mut connection,connection_id) = connect(&mut server,address);
+	add_player(&mut server,address,&mut connection,connection_id,Some((0,1)));
+	assert!(request_receiver.try_recv().is_ok());
+
+	server.update();
+	assert!(!server.is_connected(&address));
+	assert_eq!(server_request_receiver.try_recv(),Ok(Request::PlayerRemove{player: 0}));
+}
+
+#[test]
+fn only_the_players_of_the_leaving_client_are_removed(){
+	let (mut server,request_receiver,server_request_receiver) = new_server(reliable::Settings::default());
+	let (address_a,address_b) = (address(1),address(2));
+
+	let (mut connection_a,connection_id_a) = connect(&mut server,address_a);
+	add_player(&mut server,address_a,&mut connection_a,connection_id_a,Some((0,1)));
+	let (mut connection_b,connection_id_b) = connect(&mut server,address_b);
+	add_player(&mut server,address_b,&mut connection_b,connection_id_b,Some((1,1)));
+	assert!(request_receiver.try_recv().is_ok());
+	assert!(request_receiver.try_recv().is_ok());
+
+	disconnect(&mut server,address_a,&mut connection_a,connection_id_a);
+	assert_eq!(server_request_receiver.try_recv(),Ok(Request::PlayerRemove{player: 0}));
+	assert!(server_request_receiver.try_recv().is_err());
+	assert!(server.is_established(&address_b));
+
+	disconnect(&mut server,address_b,&mut connection_b,connection_id_b);
+	assert_eq!(server_request_receiver.try_recv(),Ok(Request::PlayerRemove{player: 1}));
+}
+
+#[test]
+fn players_of_leaving_clients_are_removed_from_the_game(){
+	let (mut server,request_receiver,server_request_receiver) = new_server(reliable::Settings::default());
+	let (address_a,address_b) = (address(1),address(2));
+
+	//A local player in world 0, and the shared world 1 for the players of the clients
+	let mut registry = Registry::new();
+	let mut state = State::headless(0,&game::replay::Settings{
+		timestep: game::simulation::DEFAULT_TIMESTEP,
+		worlds  : vec![(0,grid::Size{x: 10,y: 20}),(1,grid::Size{x: 10,y: 20})],
+		players : vec![(0,player::Settings{
+			gravityfall_frequency: 1.0,
+			fastfall_shadow      : false,
+		})],
+	},&mut |e| registry.event(&e));
+
+	let (mut connection_a,connection_id_a) = connect(&mut server,address_a);
+	request_player(&mut server,address_a,&mut connection_a,connection_id_a);
+	perform(&mut state,&mut registry,&mut server,&request_receiver,&server_request_receiver);
+	match receive(&mut server,address_a,&mut connection_a).pop(){
+		Some(server::packet::Data::PlayerCreated{..}) => (),
+		data => panic!("Unexpected data: {:?}",data)
+	}
+	assert_eq!(state.data.players.get(1).map(|player| player.world),Some(1));
+	assert!(registry.is_network(1));
+
+	//The control of the player is taken from the client, so that requests from the network can no longer remove it
+	registry.set(1,Control::Human);
+	assert!(!registry.accepts_network_request(&Request::PlayerRemove{player: 1}));
+
+	disconnect(&mut server,address_a,&mut connection_a,connection_id_a);
+	perform(&mut state,&mut registry,&mut server,&request_receiver,&server_request_receiver);
+	assert!(state.data.players.get(1).is_none());
+	assert!(state.data.players.get(0).is_some());
+	assert!(state.data.worlds.get(1).is_some());
+
+	//Other clients can still join the world
+	let (mut connection_b,connection_id_b) = connect(&mut server,address_b);
+	request_player(&mut server,address_b,&mut connection_b,connection_id_b);
+	perform(&mut state,&mut registry,&mut server,&request_receiver,&server_request_receiver);
+	match receive(&mut server,address_b,&mut connection_b).pop(){
+		Some(server::packet::Data::PlayerCreated{..}) => (),
+		data => panic!("Unexpected data: {:?}",data)
+	}
+	assert_eq!(state.data.players.get(1).map(|player| player.world),Some(1));
+	assert!(registry.is_network(1));
+}
@@ -0,0 +1,24 @@
+extern crate tetr;
+
+use tetr::data::PairMap;
+
+#[test]
+fn both_directions(){
+	let mut map = PairMap::<u32,u8>::new();
+	assert_eq!(map.insert(10,1),None);
+	assert_eq!(map.insert2(2,20),None);
+	assert_eq!(map.get(10),Some(1));
+	assert_eq!(map.get2(1),Some(10));
+	assert_eq!(map.get(20),Some(2));
+	assert_eq!(map.get2(2),Some(20));
+	assert_eq!(map.len(),2);
+
+	//Replacing a value forgets the old one in the other direction
+	assert_eq!(map.insert(10,3),Some(1));
+	assert_eq!(map.get2(1),None);
+	assert_eq!(map.get2(3),Some(10));
+
+	assert_eq!(map.remove2(3),Some(10));
+	assert_eq!(map.get(10),None);
+	assert_eq!(map.iter().collect::<Vec<_>>(),vec![(&20,&2)]);
+}
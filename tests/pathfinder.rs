@@ -0,0 +1,77 @@
+extern crate tetr;
+
+use tetr::data::grid;
+use tetr::data::shapes::tetromino::{RotatedShape,Shape};
+use tetr::game::data::{player,Input,Player};
+use tetr::game::data::World as WorldTrait;
+use tetr::game::data::world::dynamic::World;
+use tetr::game::pathfinder;
+
+///A 10x8 world with an overhang over the lowest rows on the left side
+fn overhang_world() -> World<bool>{
+	let mut world = World::new(10,8);
+	for x in 0..4{
+		world.set_position(grid::Pos{x: x,y: 5},true).unwrap();
+	}
+	world
+}
+
+///Performs the inputs on the shape from the given position, returning the position reached
+fn perform(world: &World<bool>,shape: RotatedShape,pos: grid::Pos,inputs: &[Input]) -> (grid::Pos,RotatedShape){
+	let mut player = Player{
+		pos                   : pos,
+		shadow_pos            : None,
+		shapes_lookahead      : None,
+		shape                 : shape,
+		world                 : 0,
+		points                : 0,
+		gravityfall_time_count: 0.0,
+		settings              : player::Settings{
+			gravityfall_frequency: 1.0,
+			fastfall_shadow      : false,
+		},
+	};
+	for &input in inputs{
+		tetr::input::perform(input,(0,&mut player),(0,world),&mut |_| ());
+	}
+	(player.pos,player.shape)
+}
+
+#[test]
+fn tuck_under_overhang(){
+	let world = overhang_world();
+	let shape = RotatedShape::new(Shape::O);
+	let start = grid::Pos{x: 4,y: 0};
+	let tuck = grid::Pos{x: 2,y: 6};
+
+	//The placement under the overhang is found, and its inputs reach it
+	let placements = pathfinder::placements(&world,shape,start);
+	let placement = placements.iter().find(|placement| placement.pos == tuck).unwrap();
+	assert_eq!(placement.shape,shape);
+	assert_eq!(placement.inputs.last(),Some(&Input::FastFall));
+	assert_eq!(perform(&world,shape,start,&placement.inputs),(tuck,shape));
+
+	//Dropping from above lands on the overhang instead
+	assert!(placements.iter().any(|placement| placement.pos == grid::Pos{x: 2,y: 3}));
+
+	//The path to the placement reaches it, which is not possible without slow falls
+	let inputs = pathfinder::path(&world,shape,start,tuck,shape.rotation()).unwrap();
+	assert!(inputs.contains(&Input::SlowFall));
+	assert_eq!(perform(&world,shape,start,&inputs),(tuck,shape));
+	assert_eq!(pathfinder::drop_path(&world,shape,start,tuck,shape.rotation()),None);
+}
+
+#[test]
+fn unreachable_placement(){
+	let mut world = overhang_world();
+
+	//Closes the space under the overhang
+	world.set_position(grid::Pos{x: 4,y: 5},true).unwrap();
+	world.set_position(grid::Pos{x: 4,y: 6},true).unwrap();
+	world.set_position(grid::Pos{x: 4,y: 7},true).unwrap();
+
+	let shape = RotatedShape::new(Shape::O);
+	let start = grid::Pos{x: 4,y: 0};
+	assert!(pathfinder::placements(&world,shape,start).iter().all(|placement| placement.pos.y < 6 || placement.pos.x > 4));
+	assert_eq!(pathfinder::path(&world,shape,start,grid::Pos{x: 2,y: 6},shape.rotation()),None);
+}
@@ -0,0 +1,87 @@
+extern crate tetr;
+
+use tetr::controller::ai::perfect_clear::{self,Settings};
+use tetr::data::grid;
+use tetr::data::shapes::tetromino::{RotatedShape,Shape};
+use tetr::game::data::World as WorldTrait;
+use tetr::game::data::world::dynamic::World;
+
+///A 10x20 world with the given lowest rows
+fn world(rows: &[&str]) -> World<bool>{
+	let mut world = World::new(10,20);
+	for (y,row) in rows.iter().enumerate(){
+		for (x,c) in row.chars().enumerate(){
+			if c == '#'{
+				world.set_position(grid::Pos{x: x as grid::PosAxis,y: (20 - rows.len() + y) as grid::PosAxis},true).unwrap();
+			}
+		}
+	}
+	world
+}
+
+fn settings() -> Settings{Settings{
+	search_time: 10.0,
+	..Settings::default()
+}}
+
+fn clears(world: &World<bool>,placements: &[tetr::game::pathfinder::Placement]) -> bool{
+	let mut world = tetr::game::data::world::bitboard::World::from_world(world);
+	for placement in placements{
+		tetr::controller::ai::bruteforce::imprint(&mut world,&placement.shape,placement.pos);
+	}
+	(0..20).all(|y| world.row(y) == 0)
+}
+
+#[test]
+fn single_shape(){
+	let world = world(&[
+		"#########.",
+		"#########.",
+		"#########.",
+		"#########.",
+	]);
+
+	let placements = perfect_clear::search(&world,RotatedShape::new(Shape::I),grid::Pos{x: 3,y: 0},None,&settings()).unwrap();
+	assert_eq!(placements.len(),1);
+	assert!(clears(&world,&placements));
+}
+
+#[test]
+fn multiple_shapes(){
+	let world = world(&[
+		"########..",
+		"########..",
+		"########..",
+		"########..",
+	]);
+
+	let placements = perfect_clear::search(&world,RotatedShape::new(Shape::O),grid::Pos{x: 4,y: 0},vec![Shape::O],&settings()).unwrap();
+	assert_eq!(placements.len(),2);
+	assert!(clears(&world,&placements));
+}
+
+#[test]
+fn line_clear_partway(){
+	let world = world(&[
+		"...###....",
+		"######....",
+		".#########",
+	]);
+
+	//The cell at the bottom left is enclosed until the O shapes clear the middle row, after which the L shape fills it together with the cells dropped above it
+	let placements = perfect_clear::search(&world,RotatedShape::new(Shape::O),grid::Pos{x: 4,y: 0},vec![Shape::O,Shape::L],&settings()).unwrap();
+	assert_eq!(placements.len(),3);
+	assert!(clears(&world,&placements));
+}
+
+#[test]
+fn impossible(){
+	let world = world(&[
+		"..########",
+		"..########",
+	]);
+
+	//An I shape cannot fill a 2x2 hole and there are not enough shapes for clearing 4 rows
+	assert!(perfect_clear::search(&world,RotatedShape::new(Shape::I),grid::Pos{x: 3,y: 0},vec![Shape::I,Shape::I],&settings()).is_none());
+	assert!(perfect_clear::search(&world,RotatedShape::new(Shape::O),grid::Pos{x: 3,y: 0},None,&settings()).is_some());
+}
@@ -0,0 +1,148 @@
+extern crate tetr;
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use tetr::controller::Controller as ControllerTrait;
+use tetr::controller::registry::{Control,Registry};
+use tetr::data::shapes::tetromino::{RotatedShape,Shape};
+use tetr::data::grid;
+use tetr::game::{self,Event,Request};
+use tetr::game::data::{player,Input,PlayerId,WorldId};
+use tetr::game::data::world::dynamic::World;
+
+type Events = Rc<RefCell<Vec<Event<(PlayerId,WorldId),WorldId>>>>;
+
+///Records the events it receives
+struct Recorder(Events);
+
+impl ControllerTrait<World<bool>,Event<(PlayerId,WorldId),WorldId>> for Recorder{
+	fn update(&mut self,_: f64,_: &game::Data<World<bool>>){}
+
+	fn event(&mut self,event: &Event<(PlayerId,WorldId),WorldId>){
+		self.0.borrow_mut().push(event.clone());
+	}
+}
+
+fn recorder() -> (Control<World<bool>>,Events){
+	let events = Rc::new(RefCell::new(Vec::new()));
+	(Control::Controller(Box::new(Recorder(events.clone()))),events)
+}
+
+fn changed_shape(player: (PlayerId,WorldId)) -> Event<(PlayerId,WorldId),WorldId>{
+	Event::PlayerChangedShape{
+		player: player,
+		shape: Shape::I,
+		pos: grid::Pos{x: 0,y: 0},
+		cause: game::event::ShapeChangeCause::NewAfterImprint,
+	}
+}
+
+fn imprinted(world: WorldId) -> Event<(PlayerId,WorldId),WorldId>{
+	Event::WorldImprintedShape{
+		world: world,
+		shape: (RotatedShape::new(Shape::I),grid::Pos{x: 0,y: 0}),
+		full_rows: 0,
+		cause: game::event::ShapeImprintCause::Desync,
+	}
+}
+
+#[test]
+fn events_are_routed_to_the_player_and_its_world(){
+	let mut registry = Registry::new();
+	let (control,events) = recorder();
+	registry.set(0,control);
+
+	registry.event(&Event::PlayerAdded{player: (0,0)});
+	registry.event(&Event::PlayerAdded{player: (1,1)});
+	registry.event(&changed_shape((1,1)));
+	registry.event(&imprinted(1));
+	assert_eq!(events.borrow().len(),1);
+
+	registry.event(&changed_shape((0,0)));
+	registry.event(&imprinted(0));
+	registry.event(&Event::GamePaused);
+	assert_eq!(events.borrow().len(),4);
+
+	//After moving, the events of the new world are received instead
+	registry.event(&Event::PlayerMovedWorld{player: (0,1),old: 0,new: 1});
+	registry.event(&imprinted(0));
+	registry.event(&imprinted(1));
+	assert_eq!(events.borrow().len(),6);
+	match events.borrow()[5]{
+		Event::WorldImprintedShape{world: 1,..} => (),
+		ref event => panic!("Unexpected event {:?}",event)
+	}
+}
+
+#[test]
+fn bindings_follow_the_players(){
+	let mut registry: Registry<World<bool>> = Registry::new();
+	assert!(registry.get(0).is_none());
+
+	//Players without a prepared binding are human controlled
+	registry.event(&Event::PlayerAdded{player: (0,0)});
+	assert!(registry.is_human(0));
+
+	//A controller swapped in is told about the existing player
+	let (control,events) = recorder();
+	assert!(registry.set(0,control).map_or(false,|control| control.is_human()));
+	assert!(!registry.is_human(0));
+	assert_eq!(events.borrow().len(),1);
+	match events.borrow()[0]{
+		Event::PlayerAdded{player: (0,0)} => (),
+		ref event => panic!("Unexpected event {:?}",event)
+	}
+
+	registry.event(&Event::PlayerRemoved{player: (0,0)});
+	assert_eq!(events.borrow().len(),2);
+	assert!(registry.get(0).is_none());
+}
+
+#[test]
+fn network_control_only_accepts_requests_for_its_player(){
+	let mut registry: Registry<World<bool>> = Registry::new();
+	registry.event(&Event::PlayerAdded{player: (0,0)});
+	registry.event(&Event::PlayerAdded{player: (1,0)});
+
+	let input = |player_id| Request::PlayerInput{input: Input::MoveLeft,player: player_id};
+	assert!(!registry.accepts_network_request(&input(0)));
+	assert!(!registry.accepts_network_request(&Request::PlayerRemove{player: 0}));
+	assert!(!registry.accepts_network_request(&Request::PlayerMoveWorld{player: 0,world: 1}));
+
+	//Swapping to the network
+	assert!(registry.set(0,Control::Network).map_or(false,|control| control.is_human()));
+	assert!(registry.is_network(0));
+	assert!(!registry.is_human(0));
+	assert!(registry.accepts_network_request(&input(0)));
+	assert!(registry.accepts_network_request(&Request::PlayerRemove{player: 0}));
+	assert!(registry.accepts_network_request(&Request::PlayerMoveWorld{player: 0,world: 1}));
+	assert!(!registry.accepts_network_request(&input(1)));
+	assert!(!registry.accepts_network_request(&Request::PlayerRemove{player: 1}));
+
+	//Swapping back
+	assert!(registry.set(0,Control::Human).map_or(false,|control| control.is_network()));
+	assert!(registry.is_human(0));
+	assert!(!registry.accepts_network_request(&input(0)));
+}
+
+#[test]
+fn network_requests_for_worlds_and_the_game_are_rejected(){
+	let mut registry: Registry<World<bool>> = Registry::new();
+	registry.event(&Event::PlayerAdded{player: (0,0)});
+	registry.set(0,Control::Network);
+
+	assert!(registry.accepts_network_request(&Request::PlayerAdd{settings: player::Settings{gravityfall_frequency: 1.0,fastfall_shadow: true},world: 0}));
+	for request in &[
+		Request::WorldRemove{world: 0},
+		Request::WorldRestart{world: 0},
+		Request::WorldPause{world: 0},
+		Request::WorldUnpause{world: 0},
+		Request::GamePause,
+		Request::GameUnpause,
+		Request::GameRestart,
+		Request::GameQuit,
+	]{
+		assert!(!registry.accepts_network_request(request),"{:?}",request);
+	}
+}
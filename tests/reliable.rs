@@ -0,0 +1,131 @@
+extern crate tetr;
+
+use tetr::online::Packet;
+use tetr::online::packet::Header;
+use tetr::online::reliable::{Connection,Reliability,Settings};
+
+fn connections() -> (Connection<u32,u32>,Connection<u32,u32>){
+	//Retransmit immediately
+	let settings = Settings{retransmit_time: 0.0,..Settings::default()};
+	(Connection::new(settings),Connection::new(settings))
+}
+
+#[test]
+fn lost_reliable_message_is_retransmitted(){
+	let (mut a,mut b) = connections();
+
+	//The first packet is lost
+	let _ = a.send(1,Reliability::Reliable);
+	assert_eq!(a.pending(),1);
+
+	let retransmissions = a.retransmissions();
+	assert_eq!(retransmissions.len(),1);
+	for packet in retransmissions{
+		assert_eq!(b.receive(packet),vec![1]);
+	}
+	assert!(b.needs_ack());
+
+	//The acknowledgement is piggybacked on the next packet
+	let packet = b.send(2,Reliability::Unreliable);
+	assert!(!b.needs_ack());
+	assert_eq!(a.receive(packet),vec![2]);
+	assert_eq!(a.pending(),0);
+	assert!(a.retransmissions().is_empty());
+}
+
+#[test]
+fn retransmitted_message_is_delivered_once(){
+	let (mut a,mut b) = connections();
+
+	let packet = a.send(1,Reliability::Reliable);
+	let retransmission = a.retransmissions().pop().unwrap();
+	assert!(packet.header.packet != retransmission.header.packet);
+
+	assert_eq!(b.receive(packet),vec![1]);
+	assert_eq!(b.receive(retransmission),vec![]);
+}
+
+#[test]
+fn duplicate_packets_are_dropped(){
+	let (mut a,mut b) = connections();
+
+	let packet = a.send(1,Reliability::Unreliable);
+	assert_eq!(b.receive(packet),vec![1]);
+	assert_eq!(b.receive(packet),vec![]);
+}
+
+#[test]
+fn ordered_messages_are_delivered_in_order(){
+	let (mut a,mut b) = connections();
+
+	let first  = a.send(1,Reliability::ReliableOrdered);
+	let second = a.send(2,Reliability::ReliableOrdered);
+	let third  = a.send(3,Reliability::ReliableOrdered);
+	let unordered = a.send(4,Reliability::Reliable);
+
+	assert_eq!(b.receive(third),vec![]);
+	assert_eq!(b.receive(unordered),vec![4]);
+	assert_eq!(b.receive(second),vec![]);
+	assert_eq!(b.receive(first),vec![1,2,3]);
+}
+
+#[test]
+fn header_of_packet(){
+	let (mut a,_) = connections();
+
+	let _ = a.send(1,Reliability::Unreliable);
+	let packet: Packet<u32> = a.send(2,Reliability::Unreliable);
+	let header = Header::deserialize(&packet.serialize()).unwrap();
+	assert_eq!(header.packet,packet.header.packet);
+}
+
+#[test]
+fn keepalive_and_timeout(){
+	let connection: Connection<u32,u32> = Connection::new(Settings::default());
+	assert!(!connection.needs_keepalive());
+	assert!(!connection.is_timed_out());
+
+	let connection: Connection<u32,u32> = Connection::new(Settings{keepalive_time: 0.0,timeout: 0.0,..Settings::default()});
+	assert!(connection.needs_keepalive());
+	assert!(connection.is_timed_out());
+}
+
+#[test]
+fn ordered_message_too_far_ahead_is_not_buffered(){
+	let settings = Settings{retransmit_time: 0.0,max_pending: 2048,..Settings::default()};
+	let (mut a,mut b): (Connection<u32,u32>,Connection<u32,u32>) = (Connection::new(settings),Connection::new(settings));
+
+	let packets: Vec<_> = (0..1026).map(|i| a.send(i,Reliability::ReliableOrdered)).collect();
+
+	//Neither buffered nor acknowledged
+	assert_eq!(b.receive(packets[1025]),vec![]);
+
+	//Buffered and acknowledged
+	assert_eq!(b.receive(packets[2]),vec![]);
+
+	assert_eq!(a.receive(b.send(0,Reliability::Unreliable)),vec![0]);
+	assert_eq!(a.pending(),1025);
+	assert_eq!(b.receive(packets[0]),vec![0]);
+	assert_eq!(b.receive(packets[1]),vec![1,2]);
+}
+
+#[test]
+fn unacknowledged_messages_time_out(){
+	//Too many retransmissions
+	let mut connection: Connection<u32,u32> = Connection::new(Settings{retransmit_time: 0.0,max_retransmissions: 2,..Settings::default()});
+	let _ = connection.send(1,Reliability::Reliable);
+	assert_eq!(connection.retransmissions().len(),1);
+	assert_eq!(connection.retransmissions().len(),1);
+	assert!(!connection.is_timed_out());
+	assert!(connection.retransmissions().is_empty());
+	assert!(connection.is_timed_out());
+
+	//Too many messages waiting for acknowledgements
+	let mut connection: Connection<u32,u32> = Connection::new(Settings{max_pending: 2,..Settings::default()});
+	let _ = connection.send(1,Reliability::Reliable);
+	let _ = connection.send(2,Reliability::ReliableOrdered);
+	assert!(!connection.is_timed_out());
+	let _ = connection.send(3,Reliability::Reliable);
+	assert_eq!(connection.pending(),2);
+	assert!(connection.is_timed_out());
+}
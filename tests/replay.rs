@@ -0,0 +1,115 @@
+extern crate bincode;
+extern crate tetr;
+
+mod common;
+
+use tetr::game::{replay,seed,Request,State};
+use tetr::game::data::{mappings,Input};
+use tetr::game::data::checksum::{self,Checksum};
+use tetr::game::data::world::dynamic::World;
+use tetr::game::replay::{Playback,Replay};
+use tetr::game::simulation::Tick;
+
+const INPUTS: [Input; 5] = [Input::MoveLeft,Input::RotateClockwise,Input::MoveLeft,Input::MoveRight,Input::FastFall];
+
+///Records a game where an input is performed every seventh tick
+fn record(ticks: Tick) -> Replay{
+	let mut replay = Replay::new(0,common::settings());
+	let mut simulation = common::new_simulation(&mut |_| ());
+
+	for tick in 0..ticks{
+		if tick % 7 == 0{
+			let request = Request::PlayerInput{input: INPUTS[(tick/7) as usize % INPUTS.len()],player: 0};
+			simulation.schedule(tick,request);
+			replay.record(tick,request);
+		}
+		simulation.step(&mut |_| ());
+		replay.record_update(tick,&simulation.state.data);
+	}
+	replay
+}
+
+fn new_playback(replay: Replay) -> Playback<World<bool>,seed::StdRng>{
+	let seed = replay.seed;
+	Playback::new(replay,State::headless_empty(seed),State::headless_world)
+}
+
+///Plays the replay to the end, returning the checksum of the game data at the end
+fn play(playback: &mut Playback<World<bool>,seed::StdRng>) -> Checksum{
+	while !playback.is_finished(){
+		playback.step(&mut |_| ());
+	}
+	checksum::checksum(&playback.simulation.state.data)
+}
+
+///The recorded checksum of the game data after the update of the given tick
+fn recorded_checksum(replay: &Replay,tick: Tick) -> Checksum{
+	replay.checksums.iter().find(|&&(checksum_tick,_,_)| checksum_tick == tick).unwrap().1
+}
+
+#[test]
+fn checksums_are_recorded_every_tick(){
+	let replay = record(130);
+	assert_eq!(replay.ticks,130);
+	assert_eq!(replay.checksums.len(),130);
+	for (tick,&(checksum_tick,_,ref parts)) in replay.checksums.iter().enumerate(){
+		assert_eq!(checksum_tick,tick as Tick);
+		assert_eq!(parts.is_some(),checksum_tick % replay::CHECKSUM_PARTS_INTERVAL == 0);
+	}
+}
+
+#[test]
+fn written_replay_plays_back_the_same_game(){
+	let replay = record(700);
+	let mut file = Vec::new();
+	replay.write(&mut file).unwrap();
+
+	let read = Replay::read(&mut &file[..]).unwrap();
+	assert_eq!(read,replay);
+
+	let mut playback = new_playback(read);
+	assert_eq!(play(&mut playback),recorded_checksum(&replay,699));
+	assert_eq!(playback.tick(),700);
+	assert_eq!(playback.divergence(),None);
+
+	//Seeking backwards past a snapshot simulates the same ticks again
+	playback.seek(650);
+	assert_eq!(checksum::checksum(&playback.simulation.state.data),recorded_checksum(&replay,649));
+	assert_eq!(playback.divergence(),None);
+}
+
+#[test]
+fn divergence_is_found_at_the_exact_tick(){
+	let mut replay = record(200);
+	replay.checksums[123].1 = replay.checksums[123].1.wrapping_add(1);
+
+	let mut playback = new_playback(replay);
+	play(&mut playback);
+	assert_eq!(playback.divergence(),Some(123));
+}
+
+#[test]
+fn players_set_up_after_a_gap_get_their_own_generators(){
+	let mut settings = common::settings();
+	settings.players.push(settings.players[0]);
+	let mut state = State::headless(0,&settings,&mut |_| ());
+	assert!(state.remove_player(0,&mut |_| ()));
+
+	//The new player gets the lowest unused id, and the generator of the remaining player is kept
+	let settings = replay::Settings{worlds: Vec::new(),..common::settings()};
+	settings.setup(&mut state,State::headless_world,&mut |_| ());
+	assert!(state.data.players.contains_key(0));
+	assert_eq!(state.rngs.1.get(&mappings::Key::Player(0)).map(|rng| rng.generated()),Some(1));
+	assert_eq!(state.rngs.1.get(&mappings::Key::Player(1)).map(|rng| rng.generated()),Some(1));
+}
+
+#[test]
+fn unsupported_version_is_an_error(){
+	let mut file = Vec::new();
+	bincode::serde::serialize_into(&mut file,&(replay::VERSION + 1),bincode::SizeLimit::Infinite).unwrap();
+
+	match Replay::read(&mut &file[..]){
+		Err(replay::Error::UnsupportedVersion(version)) => assert_eq!(version,replay::VERSION + 1),
+		result => panic!("Unexpected result: {:?}",result.map(|replay| replay.ticks))
+	}
+}
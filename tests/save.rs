@@ -0,0 +1,101 @@
+extern crate bincode;
+extern crate rand;
+extern crate tetr;
+
+mod common;
+
+use rand::Rng;
+use tetr::data::grid;
+use tetr::game::{save,seed,Request};
+use tetr::game::data::Input;
+use tetr::game::data::checksum::{Checksums,Difference,PlayerChecksums};
+
+#[test]
+fn loaded_save_has_the_same_checksums(){
+	let mut simulation = common::new_simulation(&mut |_| ());
+	for tick in 0..200{
+		simulation.schedule(tick*3,Request::PlayerInput{input: if tick % 4 == 0{Input::FastFall}else{Input::MoveLeft},player: 0});
+	}
+	simulation.run_until(600,&mut |_| ());
+	let checksums = Checksums::new(&simulation.state.data);
+
+	let mut file = Vec::new();
+	save::write(&simulation.state,&mut file).unwrap();
+
+	let mut state = common::new_state();
+	assert!(Checksums::new(&state.data) != checksums);
+	save::read(&mut state,&mut &file[..]).unwrap();
+	assert_eq!(Checksums::new(&state.data),checksums);
+
+	//The random number generators are restored too, making the loaded game continue the same way
+	let saved = simulation.snapshot();
+	simulation.run_until(900,&mut |_| ());
+	let continued = Checksums::new(&simulation.state.data);
+	simulation.restore(&saved);
+	simulation.state = state;
+	simulation.run_until(900,&mut |_| ());
+	assert_eq!(Checksums::new(&simulation.state.data),continued);
+}
+
+#[test]
+fn unsupported_version_is_an_error(){
+	let mut state = common::new_state();
+	let checksums = Checksums::new(&state.data);
+
+	match save::read(&mut state,&mut &[0xFF as u8,0xFF][..]){
+		Err(save::Error::UnsupportedVersion(0xFFFF)) => (),
+		result => panic!("Unexpected result: {:?}",result)
+	}
+	assert_eq!(Checksums::new(&state.data),checksums);
+}
+
+#[test]
+fn deserialized_rng_continues_the_same_way(){
+	let mut rng = seed::std_rng(1);
+	for _ in 0..100{
+		rng.gen::<u32>();
+	}
+
+	let bytes = bincode::serde::serialize(&rng,bincode::SizeLimit::Infinite).unwrap();
+	let mut deserialized: seed::StdRng = bincode::serde::deserialize(&bytes).unwrap();
+	assert_eq!(deserialized.generated(),100);
+	assert_eq!(deserialized.gen::<u64>(),rng.gen::<u64>());
+}
+
+#[test]
+fn rng_with_too_many_values_generated_is_an_error(){
+	let bytes = bincode::serde::serialize(&(vec![1 as usize,0],seed::MAX_GENERATED + 1),bincode::SizeLimit::Infinite).unwrap();
+	assert!(bincode::serde::deserialize::<seed::StdRng>(&bytes).is_err());
+}
+
+#[test]
+fn differences_name_the_changed_part(){
+	let mut state = common::new_state();
+	let original = Checksums::new(&state.data);
+	assert_eq!(original.differences(&original),vec![]);
+
+	assert!(state.request(Request::PlayerInput{input: Input::MoveLeft,player: 0},&mut |_| ()));
+	assert_eq!(original.differences(&Checksums::new(&state.data)),vec![Difference::PlayerPosition(0)]);
+
+	let mut state = common::new_state();
+	state.data.players.get_mut(0).unwrap().points+= 1;
+	assert_eq!(original.differences(&Checksums::new(&state.data)),vec![Difference::PlayerScore(0)]);
+
+	let mut state = common::new_state();
+	assert!(state.request(Request::WorldPause{world: 0},&mut |_| ()));
+	assert_eq!(original.differences(&Checksums::new(&state.data)),vec![Difference::World(0)]);
+
+	let mut state = common::new_state();
+	assert!(state.request(Request::PlayerRemove{player: 0},&mut |_| ()));
+	assert_eq!(original.differences(&Checksums::new(&state.data)),vec![Difference::PlayerExistence(0)]);
+}
+
+#[test]
+fn missing_shadow_differs_from_a_shadow(){
+	let mut state = common::new_state();
+	state.data.players.get_mut(0).unwrap().shadow_pos = None;
+	let without_shadow = PlayerChecksums::new(state.data.players.get(0).unwrap());
+
+	state.data.players.get_mut(0).unwrap().shadow_pos = Some(grid::Pos{x: 0,y: 0});
+	assert!(PlayerChecksums::new(state.data.players.get(0).unwrap()).position != without_shadow.position);
+}
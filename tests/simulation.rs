@@ -0,0 +1,101 @@
+extern crate tetr;
+
+mod common;
+
+use tetr::game::{event,seed,simulation,Event,Request,Simulation};
+use tetr::game::data::{checksum,Input};
+use tetr::game::data::world::dynamic::World;
+
+///Advances the simulation until the given tick, returning the checksum of the game data after every step
+fn checksums_until(simulation: &mut Simulation<World<bool>,seed::StdRng>,tick: simulation::Tick) -> Vec<(simulation::Tick,checksum::Checksum)>{
+	let mut checksums = Vec::new();
+	while simulation.tick() < tick{
+		simulation.step(&mut |_| ());
+		checksums.push((simulation.tick(),checksum::checksum(&simulation.state.data)));
+	}
+	checksums
+}
+
+#[test]
+fn run_until_advances_fixed_ticks(){
+	let mut simulation = common::new_simulation(&mut |_| ());
+	let mut falls = 0;
+
+	//The player falls once per second, which is 60 ticks
+	simulation.run_until(90,&mut |e| if let Event::PlayerMoved{cause: event::MovementCause::Gravity,..} = e{falls+= 1;});
+	assert_eq!(simulation.tick(),90);
+	assert_eq!(falls,1);
+
+	simulation.run_until(150,&mut |e| if let Event::PlayerMoved{cause: event::MovementCause::Gravity,..} = e{falls+= 1;});
+	assert_eq!(simulation.tick(),150);
+	assert_eq!(falls,2);
+
+	//A tick that already has passed is not reached again
+	simulation.run_until(5,&mut |_| ());
+	assert_eq!(simulation.tick(),150);
+}
+
+#[test]
+fn requests_of_the_same_tick_are_performed_in_scheduling_order(){
+	let x_after = |requests: [Request<_,_>; 2]|{
+		let mut simulation = common::new_simulation(&mut |_| ());
+		for &request in requests.iter(){
+			simulation.schedule(0,request);
+		}
+		simulation.step(&mut |_| ());
+		simulation.state.data.players.get(0).unwrap().pos.x
+	};
+
+	//The input is only kept when the world is restarted before it
+	let restart = Request::WorldRestart{world: 0};
+	let input   = Request::PlayerInput{input: Input::MoveLeft,player: 0};
+	assert_eq!(x_after([restart,input]) + 1,x_after([input,restart]));
+}
+
+#[test]
+fn requests_scheduled_for_passed_ticks_are_performed_at_the_next_step(){
+	let mut simulation = common::new_simulation(&mut |_| ());
+	simulation.run_until(10,&mut |_| ());
+	let x = simulation.state.data.players.get(0).unwrap().pos.x;
+
+	simulation.schedule(3,Request::PlayerInput{input: Input::MoveLeft,player: 0});
+	assert!(simulation.has_scheduled_requests());
+
+	simulation.step(&mut |_| ());
+	assert_eq!(simulation.tick(),11);
+	assert!(!simulation.has_scheduled_requests());
+	assert_eq!(simulation.state.data.players.get(0).unwrap().pos.x,x - 1);
+}
+
+#[test]
+fn quitting_stops_the_simulation(){
+	let mut simulation = common::new_simulation(&mut |_| ());
+	simulation.schedule(5,Request::GameQuit);
+	assert!(!simulation.has_quit());
+
+	let mut quits = 0;
+	simulation.run_until(100,&mut |e| if let Event::GameQuit = e{quits+= 1;});
+	assert!(simulation.has_quit());
+	assert_eq!(simulation.tick(),6);
+	assert_eq!(quits,1);
+}
+
+#[test]
+fn restored_snapshot_reproduces_the_same_ticks(){
+	let mut simulation = common::new_simulation(&mut |_| ());
+	simulation.run_until(30,&mut |_| ());
+
+	//Requests scheduled for later are a part of the snapshot
+	for tick in 40..50{
+		simulation.schedule(tick,Request::PlayerInput{input: if tick % 2 == 0{Input::MoveLeft}else{Input::RotateClockwise},player: 0});
+	}
+	simulation.schedule(100,Request::PlayerInput{input: Input::FastFall,player: 0});
+	simulation.schedule(150,Request::WorldRestart{world: 0});
+	let snapshot = simulation.snapshot();
+
+	let checksums = checksums_until(&mut simulation,300);
+	simulation.restore(&snapshot);
+	assert_eq!(simulation.tick(),30);
+	assert!(simulation.has_scheduled_requests());
+	assert_eq!(checksums_until(&mut simulation,300),checksums);
+}